@@ -0,0 +1,30 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use x68kemu::x68k::{X68k, STOCK_CLOCK};
+
+const IPLROM_PATH: &str = "X68BIOSE/IPLROM.DAT";
+const BOOT_CYCLES: usize = 2_000_000;  // 0.2s at the stock clock, well into the IPL.
+
+// Boot from reset to a fixed cycle count: CPU and bus together on real ROM code.
+fn ipl_boot(c: &mut Criterion) {
+    let ipl = match fs::read(IPLROM_PATH) {
+        Ok(ipl) => ipl,
+        Err(_) => {
+            eprintln!("Skipping ipl_boot: cannot load IPLROM: {}", IPLROM_PATH);
+            return;
+        },
+    };
+    c.bench_function("ipl_boot", |b| b.iter(|| {
+        let mut x68k = X68k::new(ipl.clone(), STOCK_CLOCK);
+        x68k.update(BOOT_CYCLES).ok()
+    }));
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = ipl_boot
+}
+criterion_main!(benches);
@@ -0,0 +1,100 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use m68k::{BusTrait, Cpu};
+use m68k::types::{Adr, Word};
+
+const RAM_SIZE: usize = 0x100000;
+const CODE: Adr = 0x1000;
+const CYCLES: usize = 100_000;
+
+struct Ram(Vec<u8>);
+
+impl BusTrait for Ram {
+    fn read16(&self, adr: Adr) -> Word {
+        let i = adr as usize & (RAM_SIZE - 1);
+        Word::from_be_bytes([self.0[i], self.0[i + 1]])
+    }
+
+    fn write16(&mut self, adr: Adr, value: Word) {
+        let i = adr as usize & (RAM_SIZE - 1);
+        self.0[i..i + 2].copy_from_slice(&value.to_be_bytes());
+    }
+}
+
+// A CPU with `code` at CODE, reset to run it with the stack out of the way.
+fn cpu_with(code: &[Word]) -> Cpu<Ram> {
+    let mut ram = vec![0; RAM_SIZE];
+    ram[0..8].copy_from_slice(&[0x00, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00]);
+    for (i, word) in code.iter().enumerate() {
+        let adr = CODE as usize + i * 2;
+        ram[adr..adr + 2].copy_from_slice(&word.to_be_bytes());
+    }
+    let mut cpu = Cpu::new(Ram(ram));
+    cpu.reset();
+    cpu
+}
+
+fn bench_program(c: &mut Criterion, name: &str, code: &[Word], setup: impl Fn(&mut Cpu<Ram>)) {
+    for &cache in [false, true].iter() {
+        let mut cpu = cpu_with(code);
+        cpu.set_decode_cache(cache);
+        let name = if cache { format!("{} (decode cache)", name) } else { name.to_string() };
+        c.bench_function(&name, |b| b.iter(|| {
+            setup(&mut cpu);
+            cpu.set_pc(CODE);
+            cpu.run_cycles(CYCLES).unwrap()
+        }));
+    }
+}
+
+// Register-only ALU instructions: measures fetch and dispatch.
+fn dispatch(c: &mut Criterion) {
+    let code = [
+        0x7001,  // moveq #1,d0
+        0xd081,  // add.l d1,d0
+        0x9480,  // sub.l d0,d2
+        0xe38b,  // lsl.l #1,d3
+        0xc841,  // and.w d1,d4
+        0x8a82,  // or.l d2,d5
+        0x4686,  // not.l d6
+        0x60f0,  // bra.s start
+    ];
+    bench_program(c, "dispatch", &code, |_| {});
+}
+
+// One instruction per addressing mode.
+fn ea_decoding(c: &mut Criterion) {
+    let code = [
+        0x2218,  // move.l (a0)+,d1
+        0x3429, 0x0004,  // move.w 4(a1),d2
+        0xd6b2, 0x0000,  // add.l 0(a2,d0.w),d3
+        0x2838, 0x2000,  // move.l $2000.w,d4
+        0x3b41, 0x0010,  // move.w d1,16(a5)
+        0x4be8, 0x0008,  // lea 8(a0),a5
+        0x2c3c, 0x1234, 0x5678,  // move.l #$12345678,d6
+        0x41f9, 0x0000, 0x4000,  // lea $4000.l,a0
+        0x60dc,  // bra.s start
+    ];
+    bench_program(c, "ea_decoding", &code, |cpu| {
+        cpu.set_a(0, 0x4000);
+        cpu.set_a(1, 0x3000);
+        cpu.set_a(2, 0x3100);
+        cpu.set_a(5, 0x5000);
+    });
+}
+
+// The classic DBRA copy loop, 16KB per round.
+fn memcpy(c: &mut Criterion) {
+    let code = [
+        0x41f9, 0x0000, 0x8000,  // lea $8000.l,a0
+        0x43f9, 0x0001, 0x0000,  // lea $10000.l,a1
+        0x303c, 0x0fff,  // move.w #$fff,d0
+        0x22d8,  // loop: move.l (a0)+,(a1)+
+        0x51c8, 0xfffc,  // dbra d0,loop
+        0x60e8,  // bra.s start
+    ];
+    bench_program(c, "memcpy", &code, |_| {});
+}
+
+criterion_group!(benches, dispatch, ea_decoding, memcpy);
+criterion_main!(benches);
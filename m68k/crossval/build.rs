@@ -0,0 +1,27 @@
+// Compile Musashi from MUSASHI_DIR, a checkout of https://github.com/kstenerud/Musashi
+// on which `make` has been run once to generate m68kops.c/m68kops.h.
+
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=MUSASHI_DIR");
+    let dir = match env::var_os("MUSASHI_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => panic!("Set MUSASHI_DIR to a Musashi checkout with the generated m68kops.c"),
+    };
+    let mut build = cc::Build::new();
+    build.include(&dir).warnings(false);
+    if !dir.join("m68kops.c").exists() {
+        panic!("{}/m68kops.c not found: run make in the Musashi directory first", dir.display());
+    }
+    for file in ["m68kcpu.c", "m68kops.c", "m68kdasm.c", "softfloat/softfloat.c"].iter() {
+        let path = dir.join(file);
+        // softfloat only exists in versions with the FPU.
+        if path.exists() {
+            build.file(&path);
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+    build.compile("musashi");
+}
@@ -0,0 +1,257 @@
+// Run this CPU core and Musashi in lockstep on the same memory image, comparing
+// registers, flags and memory writes after every instruction.
+//
+//   m68k-crossval --random [count] [seed]
+//       Random instruction streams from random register states.
+//   m68k-crossval <image> <load-address> <pc> [steps]
+//       A binary image, e.g. a ROM, from the given PC (hex addresses).
+
+mod musashi;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+
+use m68k::disasm::disasm;
+use m68k::types::{Adr, Byte, Long, Word};
+use m68k::{BusTrait, BusView, Cpu, CpuError};
+
+use self::musashi::Musashi;
+
+const RAM_SIZE: usize = 0x100000;  // Mirrored over the 24-bit address space.
+const CODE: Adr = 0x1000;
+const HANDLER: Adr = 0x0800;  // Where all exception vectors point.
+const STACK: Adr = 0x10000;
+const PROGRAM_WORDS: usize = 16;
+const PROGRAM_STEPS: usize = 8;
+
+// The registers both cores can report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct State {
+    pub d: [Long; 8],
+    pub a: [Adr; 8],
+    pub pc: Adr,
+    pub sr: Word,
+    pub usp: Adr,
+    pub ssp: Adr,
+}
+
+struct Ram {
+    mem: Vec<Byte>,
+    writes: Vec<(Adr, Byte)>,
+}
+
+impl BusTrait for Ram {
+    fn read16(&self, adr: Adr) -> Word {
+        Word::from_be_bytes([self.mem[adr as usize & (RAM_SIZE - 1)], self.mem[(adr as usize + 1) & (RAM_SIZE - 1)]])
+    }
+
+    fn write16(&mut self, adr: Adr, value: Word) {
+        let [hi, lo] = value.to_be_bytes();
+        self.write8(adr, hi);
+        self.write8(adr + 1, lo);
+    }
+
+    fn write8(&mut self, adr: Adr, value: Byte) {
+        let adr = adr & (RAM_SIZE as Adr - 1);
+        self.mem[adr as usize] = value;
+        self.writes.push((adr, value));
+    }
+}
+
+struct Lockstep {
+    cpu: Cpu<Ram>,
+    musashi: Musashi,
+}
+
+impl Lockstep {
+    fn new(ram: Vec<Byte>) -> Self {
+        let musashi = Musashi::new(ram.clone());
+        let mut cpu = Cpu::new(Ram { mem: ram, writes: Vec::new() });
+        cpu.reset();
+        Self { cpu, musashi }
+    }
+
+    fn state(&self) -> State {
+        let cpu = &self.cpu;
+        State {
+            d: std::array::from_fn(|i| cpu.d(i)),
+            a: std::array::from_fn(|i| cpu.a(i)),
+            pc: cpu.pc(),
+            sr: cpu.sr(),
+            usp: cpu.usp(),
+            ssp: cpu.ssp(),
+        }
+    }
+
+    fn set_state(&mut self, state: &State) {
+        self.cpu.set_sr(state.sr);
+        for i in 0..8 {
+            self.cpu.set_d(i, state.d[i]);
+            self.cpu.set_a(i, state.a[i]);
+        }
+        self.cpu.set_pc(state.pc);
+        self.musashi.set_state(state);
+    }
+
+    // Execute one instruction on both cores; Ok(false) when they disagree.
+    fn step(&mut self) -> Result<bool, CpuError> {
+        let pc = self.cpu.pc();
+        let (_, mnemonic) = disasm(&BusView(self.cpu.bus()), pc);
+        let before = self.state();
+        self.cpu.step()?;
+        self.musashi.step();
+        let ours = self.state();
+        let theirs = self.musashi.state();
+        let our_writes = last_writes(std::mem::take(&mut self.cpu.bus_mut().writes));
+        let their_writes = last_writes(self.musashi.take_writes());
+        if ours == theirs && our_writes == their_writes {
+            return Ok(true);
+        }
+        println!("Mismatch at {:06x}: {}", pc, mnemonic);
+        report(&before, &ours, &theirs);
+        if our_writes != their_writes {
+            println!("  writes: ours {:x?}", our_writes);
+            println!("          musashi {:x?}", their_writes);
+        }
+        Ok(false)
+    }
+}
+
+// Final value of each written byte: the cores may split and order writes differently.
+fn last_writes(writes: Vec<(Adr, Byte)>) -> BTreeMap<Adr, Byte> {
+    writes.into_iter().collect()
+}
+
+fn ccr(sr: Word) -> String {
+    "XNZVC".chars().enumerate()
+        .map(|(i, c)| if (sr & (0x10 >> i)) != 0 { c } else { '-' })
+        .collect()
+}
+
+fn report(before: &State, ours: &State, theirs: &State) {
+    let long = |name: String, b: Long, o: Long, t: Long| {
+        if o != t {
+            println!("  {:>3}: before {:08x}, ours {:08x}, musashi {:08x}", name, b, o, t);
+        }
+    };
+    for i in 0..8 {
+        long(format!("d{}", i), before.d[i], ours.d[i], theirs.d[i]);
+    }
+    for i in 0..8 {
+        long(format!("a{}", i), before.a[i], ours.a[i], theirs.a[i]);
+    }
+    long("pc".to_string(), before.pc, ours.pc, theirs.pc);
+    long("usp".to_string(), before.usp, ours.usp, theirs.usp);
+    long("ssp".to_string(), before.ssp, ours.ssp, theirs.ssp);
+    if ours.sr != theirs.sr {
+        println!("   sr: before {:04x} {}, ours {:04x} {}, musashi {:04x} {}",
+                 before.sr, ccr(before.sr), ours.sr, ccr(ours.sr), theirs.sr, ccr(theirs.sr));
+    }
+}
+
+// Memory with the reset vectors set up and every exception vector pointing at HANDLER.
+fn memory() -> Vec<Byte> {
+    let mut ram = vec![0; RAM_SIZE];
+    ram[0..4].copy_from_slice(&STACK.to_be_bytes());
+    ram[4..8].copy_from_slice(&CODE.to_be_bytes());
+    for vector in 2..256 {
+        ram[vector * 4..vector * 4 + 4].copy_from_slice(&HANDLER.to_be_bytes());
+    }
+    ram
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 32) as u32
+    }
+}
+
+fn run_random(count: usize, seed: u64) {
+    let mut rng = XorShift(seed.max(1));
+    let (mut steps, mut mismatches, mut unimplemented) = (0, 0, 0);
+    for _ in 0..count {
+        let mut ram = memory();
+        for i in 0..PROGRAM_WORDS {
+            let adr = CODE as usize + i * 2;
+            ram[adr..adr + 2].copy_from_slice(&(rng.next() as Word).to_be_bytes());
+        }
+        let mut lockstep = Lockstep::new(ram);
+        let state = State {
+            d: std::array::from_fn(|_| rng.next()),
+            a: std::array::from_fn(|i| if i == 7 { STACK } else { rng.next() & 0xffffff }),
+            pc: CODE,
+            sr: 0x2700 | (rng.next() as Word & 0x1f),
+            usp: 0,
+            ssp: STACK,
+        };
+        lockstep.set_state(&state);
+        for _ in 0..PROGRAM_STEPS {
+            match lockstep.step() {
+                Ok(true) => steps += 1,
+                Ok(false) => {
+                    mismatches += 1;
+                    break;
+                },
+                Err(_) => {
+                    unimplemented += 1;
+                    break;
+                },
+            }
+        }
+    }
+    println!("{} programs, {} instructions agreed, {} mismatches, {} stopped on CpuError",
+             count, steps, mismatches, unimplemented);
+}
+
+fn run_image(path: &str, load: Adr, pc: Adr, steps: usize) -> Result<(), Box<dyn Error>> {
+    let image = fs::read(path)?;
+    let mut ram = memory();
+    for (i, &byte) in image.iter().enumerate() {
+        ram[(load as usize + i) & (RAM_SIZE - 1)] = byte;
+    }
+    let mut lockstep = Lockstep::new(ram);
+    let mut state = lockstep.state();
+    state.pc = pc;
+    lockstep.set_state(&state);
+    for step in 0..steps {
+        match lockstep.step() {
+            Ok(true) => {},
+            Ok(false) => {
+                println!("Diverged after {} instructions", step);
+                return Ok(());
+            },
+            Err(err) => {
+                println!("Stopped after {} instructions: {}", step, err);
+                return Ok(());
+            },
+        }
+    }
+    println!("{} instructions agreed", steps);
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("--random") => {
+            let count = args.get(1).map_or(Ok(10000), |s| s.parse())?;
+            let seed = args.get(2).map_or(Ok(1), |s| s.parse())?;
+            run_random(count, seed);
+            Ok(())
+        },
+        Some(path) if args.len() >= 3 => {
+            let load = Adr::from_str_radix(&args[1], 16)?;
+            let pc = Adr::from_str_radix(&args[2], 16)?;
+            let steps = args.get(3).map_or(Ok(100000), |s| s.parse())?;
+            run_image(path, load, pc, steps)
+        },
+        _ => Err("Usage: --random [count] [seed] | <image> <load-address> <pc> [steps]".into()),
+    }
+}
@@ -0,0 +1,124 @@
+// FFI to the Musashi core. Musashi is a single global CPU that calls back into the
+// m68k_read/write_memory_* functions below, so only one Musashi may exist at a time.
+
+use std::cell::RefCell;
+use std::os::raw::{c_int, c_uint, c_void};
+
+use m68k::types::{Adr, Byte, Word};
+
+use super::State;
+
+const M68K_CPU_TYPE_68000: c_uint = 1;
+
+// m68k_register_t
+const REG_D0: c_int = 0;
+const REG_A0: c_int = 8;
+const REG_PC: c_int = 16;
+const REG_SR: c_int = 17;
+const REG_USP: c_int = 19;
+const REG_ISP: c_int = 20;
+
+extern "C" {
+    fn m68k_init();
+    fn m68k_set_cpu_type(cpu_type: c_uint);
+    fn m68k_pulse_reset();
+    fn m68k_execute(num_cycles: c_int) -> c_int;
+    fn m68k_get_reg(context: *mut c_void, reg: c_int) -> c_uint;
+    fn m68k_set_reg(reg: c_int, value: c_uint);
+}
+
+struct Memory {
+    ram: Vec<Byte>,
+    writes: Vec<(Adr, Byte)>,
+}
+
+thread_local! {
+    static MEMORY: RefCell<Memory> = const { RefCell::new(Memory { ram: Vec::new(), writes: Vec::new() }) };
+}
+
+fn read(adr: c_uint, size: usize) -> c_uint {
+    MEMORY.with(|mem| {
+        let mem = mem.borrow();
+        (0..size).fold(0, |value, i| (value << 8) | mem.ram[(adr as usize + i) & (mem.ram.len() - 1)] as c_uint)
+    })
+}
+
+fn write(adr: c_uint, size: usize, value: c_uint) {
+    MEMORY.with(|mem| {
+        let mut mem = mem.borrow_mut();
+        let mask = mem.ram.len() - 1;
+        for i in 0..size {
+            let byte = (value >> ((size - 1 - i) * 8)) as Byte;
+            mem.ram[(adr as usize + i) & mask] = byte;
+            mem.writes.push((((adr as usize + i) & mask) as Adr, byte));
+        }
+    })
+}
+
+#[no_mangle]
+extern "C" fn m68k_read_memory_8(adr: c_uint) -> c_uint { read(adr, 1) }
+#[no_mangle]
+extern "C" fn m68k_read_memory_16(adr: c_uint) -> c_uint { read(adr, 2) }
+#[no_mangle]
+extern "C" fn m68k_read_memory_32(adr: c_uint) -> c_uint { read(adr, 4) }
+#[no_mangle]
+extern "C" fn m68k_read_disassembler_8(adr: c_uint) -> c_uint { read(adr, 1) }
+#[no_mangle]
+extern "C" fn m68k_read_disassembler_16(adr: c_uint) -> c_uint { read(adr, 2) }
+#[no_mangle]
+extern "C" fn m68k_read_disassembler_32(adr: c_uint) -> c_uint { read(adr, 4) }
+#[no_mangle]
+extern "C" fn m68k_write_memory_8(adr: c_uint, value: c_uint) { write(adr, 1, value) }
+#[no_mangle]
+extern "C" fn m68k_write_memory_16(adr: c_uint, value: c_uint) { write(adr, 2, value) }
+#[no_mangle]
+extern "C" fn m68k_write_memory_32(adr: c_uint, value: c_uint) { write(adr, 4, value) }
+
+pub struct Musashi;
+
+impl Musashi {
+    // Reset a 68000 on a copy of `ram`, whose length must be a power of two.
+    pub fn new(ram: Vec<Byte>) -> Self {
+        MEMORY.with(|mem| *mem.borrow_mut() = Memory { ram, writes: Vec::new() });
+        unsafe {
+            m68k_init();
+            m68k_set_cpu_type(M68K_CPU_TYPE_68000);
+            m68k_pulse_reset();
+        }
+        Musashi
+    }
+
+    // Execute one instruction, or take one exception.
+    pub fn step(&mut self) -> usize {
+        unsafe { m68k_execute(1) as usize }
+    }
+
+    pub fn state(&self) -> State {
+        let reg = |r| unsafe { m68k_get_reg(std::ptr::null_mut(), r) };
+        State {
+            d: std::array::from_fn(|i| reg(REG_D0 + i as c_int)),
+            a: std::array::from_fn(|i| reg(REG_A0 + i as c_int)),
+            pc: reg(REG_PC),
+            sr: reg(REG_SR) as Word,
+            usp: reg(REG_USP),
+            ssp: reg(REG_ISP),
+        }
+    }
+
+    // SR first: changing S swaps the stack pointers.
+    pub fn set_state(&mut self, state: &State) {
+        unsafe {
+            m68k_set_reg(REG_SR, state.sr as c_uint);
+            for i in 0..8 {
+                m68k_set_reg(REG_D0 + i as c_int, state.d[i]);
+                m68k_set_reg(REG_A0 + i as c_int, state.a[i]);
+            }
+            m68k_set_reg(REG_PC, state.pc);
+        }
+    }
+
+    // Bytes written since the last call, in order.
+    pub fn take_writes(&mut self) -> Vec<(Adr, Byte)> {
+        MEMORY.with(|mem| std::mem::take(&mut mem.borrow_mut().writes))
+    }
+}
@@ -0,0 +1,188 @@
+// Runner for the SingleStepTests 680x0 vectors (https://github.com/SingleStepTests/680x0):
+// each test sets up a CPU state and memory, executes one instruction and checks the
+// final state and memory.
+//
+//   cargo run --release -p m68k --example single_step -- [-v] [--cycles] <file-or-dir>...
+//
+// Files are the suite's .json or .json.gz files; directories are searched for them.
+
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+use m68k::types::{Adr, Byte, Long, Word};
+use m68k::{BusTrait, Cpu, Registers};
+
+const FLAG_S: Word = 1 << 13;
+
+#[derive(Deserialize)]
+struct Test {
+    name: String,
+    initial: State,
+    #[serde(rename = "final")]
+    expected: State,
+    length: usize,  // Clock cycles.
+}
+
+#[derive(Deserialize)]
+struct State {
+    d0: Long, d1: Long, d2: Long, d3: Long, d4: Long, d5: Long, d6: Long, d7: Long,
+    a0: Adr, a1: Adr, a2: Adr, a3: Adr, a4: Adr, a5: Adr, a6: Adr,
+    usp: Adr,
+    ssp: Adr,
+    sr: Word,
+    pc: Adr,
+    #[serde(default)]
+    prefetch: Vec<Word>,
+    ram: Vec<(Adr, Byte)>,
+}
+
+impl State {
+    fn registers(&self) -> Registers {
+        let a7 = if (self.sr & FLAG_S) != 0 { self.ssp } else { self.usp };
+        Registers {
+            d: [self.d0, self.d1, self.d2, self.d3, self.d4, self.d5, self.d6, self.d7],
+            a: [self.a0, self.a1, self.a2, self.a3, self.a4, self.a5, self.a6, a7],
+            pc: self.pc,
+            sr: self.sr,
+            usp: self.usp,
+            ssp: self.ssp,
+            ..Registers::default()
+        }
+    }
+}
+
+// Only the bytes a test lists exist; everything else reads as 0.
+struct SparseBus {
+    mem: HashMap<Adr, Byte>,
+}
+
+impl BusTrait for SparseBus {
+    fn read8(&self, adr: Adr) -> Byte {
+        self.mem.get(&(adr & 0xffffff)).copied().unwrap_or(0)
+    }
+
+    fn read16(&self, adr: Adr) -> Word {
+        ((self.read8(adr) as Word) << 8) | self.read8(adr + 1) as Word
+    }
+
+    fn write8(&mut self, adr: Adr, value: Byte) {
+        self.mem.insert(adr & 0xffffff, value);
+    }
+
+    fn write16(&mut self, adr: Adr, value: Word) {
+        self.write8(adr, (value >> 8) as Byte);
+        self.write8(adr + 1, value as Byte);
+    }
+}
+
+// Run one test and return the differences from the expected state.
+fn run(test: &Test, cycles: bool) -> Vec<String> {
+    let initial = &test.initial;
+    let mut mem: HashMap<Adr, Byte> = initial.ram.iter().copied().collect();
+    for (i, word) in initial.prefetch.iter().enumerate() {
+        let adr = initial.pc + i as Adr * 2;
+        mem.insert(adr, (word >> 8) as Byte);
+        mem.insert(adr + 1, *word as Byte);
+    }
+    let mut cpu = Cpu::new(SparseBus { mem });
+    cpu.set_registers(initial.registers());
+    let info = match cpu.step() {
+        Ok(info) => info,
+        Err(err) => return vec![err.to_string()],
+    };
+
+    let mut diffs = Vec::new();
+    let expected = test.expected.registers();
+    let actual = cpu.registers();
+    for i in 0..8 {
+        if actual.d[i] != expected.d[i] {
+            diffs.push(format!("d{}: {:08x}, expected {:08x}", i, actual.d[i], expected.d[i]));
+        }
+    }
+    for i in 0..7 {
+        if actual.a[i] != expected.a[i] {
+            diffs.push(format!("a{}: {:08x}, expected {:08x}", i, actual.a[i], expected.a[i]));
+        }
+    }
+    for &(name, value, want) in [("usp", cpu.usp(), test.expected.usp), ("ssp", cpu.ssp(), test.expected.ssp), ("pc", cpu.pc(), expected.pc)].iter() {
+        if value != want {
+            diffs.push(format!("{}: {:08x}, expected {:08x}", name, value, want));
+        }
+    }
+    if actual.sr != expected.sr {
+        diffs.push(format!("sr: {:04x}, expected {:04x}", actual.sr, expected.sr));
+    }
+    for &(adr, want) in test.expected.ram.iter() {
+        let value = cpu.bus().read8(adr);
+        if value != want {
+            diffs.push(format!("({:06x}): {:02x}, expected {:02x}", adr, value, want));
+        }
+    }
+    if cycles && info.cycles != test.length {
+        diffs.push(format!("cycles: {}, expected {}", info.cycles, test.length));
+    }
+    diffs
+}
+
+fn load(path: &Path) -> Result<Vec<Test>, Box<dyn Error>> {
+    let mut json = Vec::new();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        GzDecoder::new(File::open(path)?).read_to_end(&mut json)?;
+    } else {
+        json = fs::read(path)?;
+    }
+    Ok(serde_json::from_slice(&json)?)
+}
+
+fn test_files(path: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    files.retain(|f| f.to_str().is_some_and(|s| s.ends_with(".json") || s.ends_with(".json.gz")));
+    files.sort();
+    Ok(files)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v");
+    let cycles = args.iter().any(|a| a == "--cycles");
+    let paths: Vec<&String> = args.iter().filter(|a| !a.starts_with('-')).collect();
+    if paths.is_empty() {
+        return Err("Usage: single_step [-v] [--cycles] <file-or-dir>...".into());
+    }
+
+    let (mut total, mut passed) = (0, 0);
+    for path in paths {
+        for file in test_files(Path::new(path))? {
+            let tests = load(&file)?;
+            let mut failed = 0;
+            for test in tests.iter() {
+                let diffs = run(test, cycles);
+                if !diffs.is_empty() {
+                    failed += 1;
+                    // Show the first failure of each file, or all of them with -v.
+                    if verbose || failed == 1 {
+                        println!("  {}: {}", test.name, diffs.join(", "));
+                    }
+                }
+            }
+            let file_passed = tests.len() - failed;
+            println!("{}: {}/{} passed", file.display(), file_passed, tests.len());
+            total += tests.len();
+            passed += file_passed;
+        }
+    }
+    println!("Total: {}/{} passed", passed, total);
+    Ok(())
+}
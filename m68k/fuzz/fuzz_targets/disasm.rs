@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use m68k::disasm::Disassembler;
+use m68k::BusView;
+use m68k_fuzz::FuzzBus;
+
+// Disassemble a random instruction stream from start to end.
+fuzz_target!(|data: &[u8]| {
+    let bus = FuzzBus::new(data, 0);
+    let view = BusView(&bus);
+    for (adr, inst) in Disassembler::new(&view).iter(0, data.len() as u32) {
+        let _ = inst.to_string();
+        let size = inst.length;
+        assert!(size >= 2 && size % 2 == 0 && size <= 22, "Bad size {} at {:06x}", size, adr);
+    }
+});
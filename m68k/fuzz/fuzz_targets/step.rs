@@ -0,0 +1,40 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use m68k::disasm::disasm;
+use m68k::{BusView, Cpu};
+use m68k_fuzz::FuzzBus;
+
+const CODE: u32 = 0x1000;
+const MAX_STEPS: usize = 256;
+
+// Execute a random instruction stream: the first 64 bytes seed D0-D7/A0-A7, the rest
+// is code. Each instruction must take as many bytes as the disassembler says.
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 64 {
+        return;
+    }
+    let (regs, code) = data.split_at(64);
+    let mut cpu = Cpu::new(FuzzBus::new(code, CODE));
+    cpu.reset();
+    for i in 0..8 {
+        let value = |n: usize| u32::from_be_bytes([regs[n * 4], regs[n * 4 + 1], regs[n * 4 + 2], regs[n * 4 + 3]]);
+        cpu.set_d(i, value(i));
+        cpu.set_a(i, value(i + 8));
+    }
+    cpu.set_pc(CODE);
+    for _ in 0..MAX_STEPS {
+        let pc = cpu.pc();
+        let size = if pc <= 0xffffe0 { Some(disasm(&BusView(cpu.bus()), pc).0) } else { None };
+        let info = match cpu.step() {
+            Ok(info) => info,
+            Err(_) => break,  // Unimplemented or halted: reported, not a crash.
+        };
+        if let Some(size) = size {
+            if info.size != 0 && !info.exception {
+                assert_eq!(size, info.size, "Length mismatch at {:06x}: {:04x}", pc, info.op);
+            }
+        }
+    }
+});
@@ -0,0 +1,38 @@
+use m68k::BusTrait;
+use m68k::types::{Adr, Word};
+
+const RAM_SIZE: usize = 0x10000;
+
+// RAM-only bus for fuzzing: 64KB mirrored over the 24-bit address space of the
+// 68000. An access above it means the CPU didn't mask an address.
+pub struct FuzzBus {
+    mem: Vec<u8>,
+}
+
+impl FuzzBus {
+    // `data` is loaded at `adr`, the rest of memory is zero.
+    pub fn new(data: &[u8], adr: Adr) -> Self {
+        let mut mem = vec![0; RAM_SIZE];
+        for (i, &b) in data.iter().take(RAM_SIZE).enumerate() {
+            mem[(adr as usize + i) & (RAM_SIZE - 1)] = b;
+        }
+        Self { mem }
+    }
+
+    fn index(adr: Adr) -> usize {
+        assert!(adr <= 0xffffff, "Out of range: {:08x}", adr);
+        adr as usize & (RAM_SIZE - 1)
+    }
+}
+
+impl BusTrait for FuzzBus {
+    fn read16(&self, adr: Adr) -> Word {
+        Word::from_be_bytes([self.mem[Self::index(adr)], self.mem[Self::index(adr + 1)]])
+    }
+
+    fn write16(&mut self, adr: Adr, value: Word) {
+        let [hi, lo] = value.to_be_bytes();
+        self.mem[Self::index(adr)] = hi;
+        self.mem[Self::index(adr + 1)] = lo;
+    }
+}
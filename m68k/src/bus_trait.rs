@@ -0,0 +1,86 @@
+use super::types::{Byte, Word, Long, Adr};
+
+// Response of the interrupt acknowledge cycle.
+pub enum InterruptAck {
+    Autovector,  // VPA asserted: the CPU uses the autovector of the level.
+    Vector(Byte),  // The device put its vector number on the data bus.
+    Spurious,  // Nobody responded (bus error): the spurious interrupt vector.
+}
+
+pub trait BusTrait {
+    fn reset(&mut self) {}
+
+    // The RESET instruction asserts the external reset line: reinitialize the
+    // attached devices, but leave memory contents and the CPU alone.
+    fn reset_devices(&mut self) {}
+
+    // The 68000 has a 16-bit data bus: word accesses at even addresses are the
+    // primitive bus cycles, and the other sizes are built from them by default.
+    // From the 68020 on, `adr` can be odd for misaligned operands.
+    fn read16(&self, adr: Adr) -> Word;
+    fn write16(&mut self, adr: Adr, value: Word);
+
+    // Report (and clear) whether an access since the last call hit an unmapped
+    // region; the CPU turns it into a bus error exception.
+    fn take_bus_error(&self) -> bool { false }
+
+    // Highest interrupt level (0 = none, 1~7) currently requested by devices on the bus.
+    fn interrupt_level(&self) -> Word { 0 }
+
+    // Interrupt acknowledge cycle for an accepted level, asking the bus for the vector.
+    fn acknowledge_interrupt(&mut self, _level: Word) -> InterruptAck { InterruptAck::Autovector }
+
+    // Extra clocks per bus cycle for the device at `adr`, before it acknowledges the access.
+    fn wait_states(&self, _adr: Adr) -> usize { 0 }
+
+    // Let device timers run for `cycles` clocks of elapsed CPU time.
+    fn advance(&mut self, _cycles: usize) {}
+
+    // Clocks until a device may next change its interrupt request, if one is counting down.
+    fn next_event(&self) -> Option<usize> { None }
+
+    fn read8(&self, adr: Adr) -> Byte {
+        let word = self.read16(adr & !1);
+        if (adr & 1) == 0 { (word >> 8) as Byte } else { word as Byte }
+    }
+
+    // Read-modify-write of the containing word; buses with registers that react
+    // to reads or writes should override this with a real byte strobe.
+    fn write8(&mut self, adr: Adr, value: Byte) {
+        let word = self.read16(adr & !1);
+        let word = if (adr & 1) == 0 {
+            (word & 0x00ff) | ((value as Word) << 8)
+        } else {
+            (word & 0xff00) | value as Word
+        };
+        self.write16(adr & !1, word);
+    }
+
+    fn read32(&self, adr: Adr) -> Long {
+        let hi = self.read16(adr) as Long;
+        let lo = self.read16(adr + 2) as Long;
+        (hi << 16) | lo
+    }
+
+    fn write32(&mut self, adr: Adr, value: Long) {
+        self.write16(adr,     (value >> 16) as Word);
+        self.write16(adr + 2,  value        as Word);
+    }
+}
+
+// Read-only view of memory for the disassembler and debuggers: peeking has no side effects,
+// and gives None where nothing can be read, e.g. unmapped addresses or device registers.
+pub trait MemoryView {
+    fn peek16(&self, adr: Adr) -> Option<Word>;
+}
+
+// View through the bus itself, for buses without a side effect free view of their own;
+// reads that raise a bus error are unreadable.
+pub struct BusView<'a, BusT: BusTrait>(pub &'a BusT);
+
+impl<BusT: BusTrait> MemoryView for BusView<'_, BusT> {
+    fn peek16(&self, adr: Adr) -> Option<Word> {
+        let value = self.0.read16(adr);
+        if self.0.take_bus_error() { None } else { Some(value) }
+    }
+}
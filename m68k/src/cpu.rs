@@ -0,0 +1,2189 @@
+use std::collections::HashSet;
+
+use serde::{Serialize, Deserialize};
+
+use super::bus_trait::{BusTrait, BusView, InterruptAck};
+use super::error::CpuError;
+use super::registers::Registers;
+use super::disasm::{decode, unlisted_instruction};
+#[cfg(feature = "jit")]
+use super::jit::{Jit, JitContext};
+use super::opcode::{Opcode, INST, OPCODE_COUNT};
+use super::trace::{TraceSink, TraceEvent, StdoutTrace, diff_registers};
+use super::util::{get_branch_offset, conv07to18, brief_extension, bcd_add, bcd_sub, shift, ShiftKind};
+use super::types::{Byte, Word, Long, SByte, SWord, SLong, Adr};
+
+mod handlers;
+
+use self::handlers::Handler;
+
+const SP: usize = 7;  // Stack pointer = A7 register.
+
+const FLAG_C: Word = 1 << 0;
+const FLAG_V: Word = 1 << 1;
+const FLAG_Z: Word = 1 << 2;
+const FLAG_N: Word = 1 << 3;
+const FLAG_X: Word = 1 << 4;
+const FLAG_S: Word = 1 << 13;
+const FLAG_T: Word = 1 << 15;
+const SR_MASK: Word = 0xa71f;  // T, S, I2-I0 and XNZVC; the other bits always read as 0.
+
+const BUS_ERROR_VECTOR: Word = 2;
+const ADDRESS_ERROR_VECTOR: Word = 3;
+const ILLEGAL_INSTRUCTION_VECTOR: Word = 4;
+const ZERO_DIVIDE_VECTOR: Word = 5;
+const CHK_VECTOR: Word = 6;
+const PRIVILEGE_VIOLATION_VECTOR: Word = 8;
+const TRACE_VECTOR: Word = 9;
+const LINE_A_VECTOR: Word = 10;
+const LINE_F_VECTOR: Word = 11;
+const FORMAT_ERROR_VECTOR: Word = 14;
+const AUTOVECTOR_START: Word = 24;  // Spurious interrupt, then levels 1~7.
+const TRAP_VECTOR_START: Word = 32;
+
+const DECODE_CACHE_SIZE: usize = 4096;  // Entries, direct mapped by word address.
+const DECODED_WORDS: usize = 5;  // Longest 68000 instruction; longer ones aren't cached.
+const NO_ENTRY: Adr = 1;  // Odd, so never the address of an instruction.
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub enum CpuModel {
+    M68000,
+    M68010,  // Adds VBR, MOVEC/MOVES/RTD, format words in exception frames and loop mode.
+    M68020,  // Adds 32-bit addressing, scaled/memory-indirect indexing, MULL/DIVL, bit fields and CAS.
+    M68030,  // Same instruction set as the 68020 here; the MMU and caches are not emulated.
+}
+
+// Called for $Fxxx opcodes with PC just past the opcode word; returns true
+// if the instruction was emulated, otherwise the line-F exception is taken.
+pub type LineFHook<BusT> = Box<dyn FnMut(&mut Cpu<BusT>, Word) -> bool + Send>;
+
+// Called with the instruction address and opcode before or after each instruction;
+// returning true stops execution with CpuError::Stopped.
+pub type ExecHook<BusT> = Box<dyn FnMut(&mut Cpu<BusT>, Adr, Word) -> bool + Send>;
+
+// Notified of each data read/write the CPU performs; instruction fetches are not reported.
+pub type MemoryObserver = Box<dyn FnMut(&MemoryAccess) + Send>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub pc: Adr,  // Address of the instruction making the access.
+    pub adr: Adr,
+    pub size: usize,  // 1, 2 or 4 bytes.
+    pub value: Long,
+    pub write: bool,
+}
+
+// Group 0 exception cause, recorded by the memory helpers and taken after the instruction.
+#[derive(Clone, Copy)]
+struct Fault {
+    vector: Word,
+    adr: Adr,
+    write: bool,
+    fetch: bool,  // Instruction fetch, or data access?
+}
+
+// An instruction as decoded on its first execution at `adr`: the handler, and the opcode
+// and extension words it fetched. Cycles are still counted by the handler, as they can
+// depend on the operand values.
+struct Decoded<BusT> {
+    adr: Adr,  // NO_ENTRY when free.
+    handler: Handler<BusT>,
+    len: usize,  // Words fetched, the opcode included.
+    words: [Word; DECODED_WORDS],
+}
+
+// Not derived, which would require BusT: Clone.
+impl<BusT> Clone for Decoded<BusT> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<BusT> Copy for Decoded<BusT> {}
+
+// What a single step did, for debuggers and tracing frontends.
+// `size` is 0 when no instruction was executed: an interrupt was taken, or the CPU idled after STOP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepInfo {
+    pub pc: Adr,  // Address of the instruction.
+    pub op: Word,
+    pub size: usize,  // Instruction length in bytes, including extension words.
+    pub cycles: usize,  // Clock cycles consumed, including wait states.
+    pub branch: bool,  // PC did not fall through to the next instruction.
+    pub exception: bool,  // An exception or interrupt was taken.
+}
+
+// Saved state covers the machine only: per-instruction scratch values and
+// everything installed by the host (hooks, breakpoints, trace sink) are skipped.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "BusT: Serialize", deserialize = "BusT: Deserialize<'de>"))]
+pub struct Cpu<BusT> {
+    regs: Registers,
+    bus: BusT,
+    model: CpuModel,
+    adr_mask: Adr,  // The 68000/68010 only drive 24 address lines.
+    #[serde(skip)]
+    inst_adr: Adr,  // Address of the instruction being executed.
+    stopped: bool,  // Executed STOP, waiting for an interrupt.
+    halted: bool,  // Double bus fault: only a reset brings the CPU back.
+    instructions: u64,  // Executed instructions and consumed cycles since reset_counters,
+    total_cycles: u64,  // saved with the state so that replays stay in step.
+    #[serde(skip)]
+    fault: Option<Fault>,
+    #[serde(skip)]
+    error: Option<CpuError>,  // Recorded by the helpers, returned after the instruction.
+    irq_lines: Byte,  // Interrupt requests asserted through the Cpu API, bit n = level n.
+    irq_vectors: [Option<Byte>; 8],  // Vector supplied by the requesting device, or autovector.
+    nmi_taken: bool,  // Level 7 is edge triggered: taken once until the level drops.
+    #[serde(skip)]
+    line_f_hook: Option<LineFHook<BusT>>,
+    #[serde(skip)]
+    pre_exec_hook: Option<ExecHook<BusT>>,
+    #[serde(skip)]
+    post_exec_hook: Option<ExecHook<BusT>>,
+    #[serde(skip)]
+    mem_observer: Option<MemoryObserver>,
+    #[serde(skip)]
+    breakpoints: HashSet<Adr>,
+    #[serde(skip)]
+    resuming: Option<Adr>,  // Stopped before the instruction here: don't stop there again.
+    #[serde(skip)]
+    cycles: usize,  // Clock cycles consumed by the current instruction or exception.
+    #[serde(skip)]
+    exception_taken: bool,  // Set when an exception frame is pushed during the current step.
+    #[serde(skip)]
+    fetch_end: Adr,  // Address just past the last instruction stream word fetched.
+    #[serde(skip)]
+    refetch: bool,  // Reading the operand of a read-modify-write, before writing it.
+    #[serde(skip)]
+    trace_sink: Option<Box<dyn TraceSink>>,
+    prefetch: bool,  // Accuracy option: emulate the two-word prefetch queue.
+    queue: [Option<(Adr, Word)>; 2],  // Prefetched (address, word) following the last fetch.
+    #[serde(skip)]
+    decode_cache: Vec<Decoded<BusT>>,  // Executed instructions by address; empty when disabled.
+    #[serde(skip)]
+    replay: Option<usize>,  // Cache entry of the instruction being executed.
+    #[serde(skip)]
+    recording: Option<Decoded<BusT>>,  // Or the entry being filled in by its first execution.
+    #[cfg(feature = "jit")]
+    #[serde(skip)]
+    jit: Option<Box<Jit>>,
+}
+
+impl<BusT: BusTrait> Cpu<BusT> {
+    pub fn new(bus: BusT) -> Self {
+        let regs = Registers::new();
+        Self {
+            regs,
+            bus,
+            model: CpuModel::M68000,
+            adr_mask: 0x00ffffff,
+            inst_adr: 0,
+            stopped: false,
+            halted: false,
+            instructions: 0,
+            total_cycles: 0,
+            fault: None,
+            error: None,
+            irq_lines: 0,
+            irq_vectors: [None; 8],
+            nmi_taken: false,
+            line_f_hook: None,
+            pre_exec_hook: None,
+            post_exec_hook: None,
+            mem_observer: None,
+            breakpoints: HashSet::new(),
+            resuming: None,
+            cycles: 0,
+            exception_taken: false,
+            fetch_end: 0,
+            refetch: false,
+            trace_sink: None,
+            prefetch: false,
+            queue: [None; 2],
+            decode_cache: Vec::new(),
+            replay: None,
+            recording: None,
+            #[cfg(feature = "jit")]
+            jit: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.bus.reset();
+        self.regs.sr = FLAG_S | 0x0700;
+        self.stopped = false;
+        self.halted = false;
+        self.nmi_taken = false;
+        self.regs.vbr = 0;
+        self.queue = [None; 2];
+        self.invalidate_decode_cache();
+        self.regs.a[SP] = self.read32(0x000000);
+        self.regs.pc = self.read32(0x000004);
+    }
+
+    pub fn set_model(&mut self, model: CpuModel) {
+        self.model = model;
+        self.adr_mask = if model >= CpuModel::M68020 { 0xffffffff } else { 0x00ffffff };
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    // Instructions executed so far; exception processing and idling while stopped
+    // are not instructions, but their cycles count in cycle_count.
+    pub fn instruction_count(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn cycle_count(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn reset_counters(&mut self) {
+        self.instructions = 0;
+        self.total_cycles = 0;
+    }
+
+    pub fn pc(&self) -> Adr {
+        self.regs.pc
+    }
+
+    pub fn set_pc(&mut self, pc: Adr) {
+        self.regs.pc = pc;
+        self.queue = [None; 2];
+    }
+
+    pub fn d(&self, n: usize) -> Long {
+        self.regs.d[n]
+    }
+
+    pub fn set_d(&mut self, n: usize, value: Long) {
+        self.regs.d[n] = value;
+    }
+
+    // A7 is the stack pointer of the current mode; see usp/ssp for the other one.
+    pub fn a(&self, n: usize) -> Adr {
+        self.regs.a[n]
+    }
+
+    pub fn set_a(&mut self, n: usize, value: Adr) {
+        self.regs.a[n] = value;
+    }
+
+    pub fn sr(&self) -> Word {
+        self.regs.sr
+    }
+
+    pub fn usp(&self) -> Adr {
+        if (self.regs.sr & FLAG_S) != 0 { self.regs.usp } else { self.regs.a[SP] }
+    }
+
+    pub fn ssp(&self) -> Adr {
+        if (self.regs.sr & FLAG_S) != 0 { self.regs.a[SP] } else { self.regs.ssp }
+    }
+
+    // Snapshot of the whole register file, e.g. for a debugger view or to restore later.
+    pub fn registers(&self) -> Registers {
+        self.regs.clone()
+    }
+
+    pub fn set_registers(&mut self, regs: Registers) {
+        self.regs = regs;
+        self.regs.sr &= SR_MASK;
+        self.queue = [None; 2];
+    }
+
+    // Disassemble every instruction to stdout; slow, so off by default.
+    pub fn set_trace(&mut self, enable: bool) {
+        self.trace_sink = if enable { Some(Box::new(StdoutTrace)) } else { None };
+    }
+
+    // Send trace events to `sink` instead, e.g. a trace file or a debugger UI.
+    pub fn set_trace_sink(&mut self, sink: Option<Box<dyn TraceSink>>) {
+        self.trace_sink = sink;
+    }
+
+    pub fn bus(&self) -> &BusT {
+        &self.bus
+    }
+
+    pub fn bus_mut(&mut self) -> &mut BusT {
+        &mut self.bus
+    }
+
+    // Take over the machine state of `saved`, e.g. a deserialized save state,
+    // keeping the hooks, breakpoints and trace sink installed on this one.
+    pub fn restore(&mut self, mut saved: Self) {
+        saved.line_f_hook = self.line_f_hook.take();
+        saved.pre_exec_hook = self.pre_exec_hook.take();
+        saved.post_exec_hook = self.post_exec_hook.take();
+        saved.mem_observer = self.mem_observer.take();
+        saved.breakpoints = std::mem::take(&mut self.breakpoints);
+        saved.trace_sink = self.trace_sink.take();
+        saved.set_decode_cache(!self.decode_cache.is_empty());
+        #[cfg(feature = "jit")]
+        {
+            saved.jit = self.jit.take();
+            saved.invalidate_decode_cache();
+        }
+        *self = saved;
+    }
+
+    // With the prefetch queue enabled, the two words following the last instruction
+    // fetch are read ahead: overwriting them has no effect until the queue is refilled
+    // by a jump or an exception, as some copy-protection and demo code relies on.
+    pub fn set_prefetch(&mut self, enable: bool) {
+        self.prefetch = enable;
+        self.queue = [None; 2];
+    }
+
+    // Remember each executed instruction, decoded: its handler, and the opcode and
+    // extension words, served without going through the bus on the next visit (the bus
+    // cycles are still counted). Writes by the CPU invalidate the entries they hit; a host
+    // changing memory behind its back (DMA, bank switching) must call invalidate_decode_cache.
+    // Not used while the prefetch queue is emulated.
+    pub fn set_decode_cache(&mut self, enable: bool) {
+        self.decode_cache = if enable { vec![Self::no_entry(); DECODE_CACHE_SIZE] } else { Vec::new() };
+        self.replay = None;
+        self.recording = None;
+    }
+
+    pub fn invalidate_decode_cache(&mut self) {
+        for entry in self.decode_cache.iter_mut() {
+            entry.adr = NO_ENTRY;
+        }
+        self.recording = None;
+        #[cfg(feature = "jit")]
+        if let Some(jit) = self.jit.as_mut() {
+            jit.flush();
+        }
+    }
+
+    // Experimental: run straight-line register code and DBRA loops as native code
+    // compiled on the fly. Only used by run_cycles, and only while no trace, hook,
+    // breakpoint or prefetch emulation needs to see single instructions. A compiled
+    // block runs to its end before interrupts are checked. Code written by the CPU is
+    // recompiled; other changes to memory need invalidate_decode_cache as above.
+    // Returns whether the JIT is active, which it can't be on an unsupported host.
+    #[cfg(feature = "jit")]
+    pub fn set_jit(&mut self, enable: bool) -> bool {
+        self.jit = if enable { Jit::new().map(Box::new) } else { None };
+        self.jit.is_some()
+    }
+
+    // Install a handler for line-F opcodes, e.g. an FPU or accelerator board.
+    pub fn set_line_f_hook(&mut self, hook: Option<LineFHook<BusT>>) {
+        self.line_f_hook = hook;
+    }
+
+    // Hooks around every executed instruction, for tracing, profiling or HLE intercepts.
+    // A stop requested before an instruction leaves it unexecuted; running again resumes there.
+    pub fn set_pre_exec_hook(&mut self, hook: Option<ExecHook<BusT>>) {
+        self.pre_exec_hook = hook;
+    }
+
+    pub fn set_post_exec_hook(&mut self, hook: Option<ExecHook<BusT>>) {
+        self.post_exec_hook = hook;
+    }
+
+    // Stop with CpuError::Breakpoint before executing the instruction at `adr`.
+    pub fn add_breakpoint(&mut self, adr: Adr) {
+        self.breakpoints.insert(adr);
+    }
+
+    pub fn remove_breakpoint(&mut self, adr: Adr) -> bool {
+        self.breakpoints.remove(&adr)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    // Watch memory accesses, e.g. for watchpoints or I/O logging. Costs a None check when not installed.
+    pub fn set_memory_observer(&mut self, observer: Option<MemoryObserver>) {
+        self.mem_observer = observer;
+    }
+
+    // Current interrupt priority mask (0~7); levels at or below it are blocked, except 7.
+    pub fn interrupt_mask(&self) -> Word {
+        (self.regs.sr >> 8) & 7
+    }
+
+    // Assert/deassert an interrupt request line (level 1~7).
+    pub fn assert_interrupt(&mut self, level: Word) {
+        self.irq_lines |= 1 << level;
+        self.irq_vectors[level as usize] = None;
+    }
+
+    // Like assert_interrupt, but the device answers the acknowledge cycle with its own vector number.
+    pub fn assert_vectored_interrupt(&mut self, level: Word, vector: Byte) {
+        self.irq_lines |= 1 << level;
+        self.irq_vectors[level as usize] = Some(vector);
+    }
+
+    pub fn deassert_interrupt(&mut self, level: Word) {
+        self.irq_lines &= !(1 << level);
+        self.irq_vectors[level as usize] = None;
+    }
+
+    // Run until at least `cycles` clock cycles are consumed, and return the actual count.
+    // The last instruction may overrun the budget. Devices on the bus are advanced along,
+    // and while stopped the CPU skips straight to the next device event.
+    pub fn run_cycles(&mut self, cycles: usize) -> Result<usize, CpuError> {
+        let mut executed = 0;
+        while executed < cycles {
+            executed += self.tick(cycles - executed, true)?.cycles;
+        }
+        Ok(executed)
+    }
+
+    // Execute a single instruction, or take a pending interrupt, and report what happened.
+    // While stopped, the CPU idles until the next device event instead.
+    pub fn step(&mut self) -> Result<StepInfo, CpuError> {
+        let idle = self.bus.next_event().unwrap_or(4);
+        self.tick(idle.max(1), false)
+    }
+
+    // `batch`: the caller doesn't look at single instructions, so a JIT block may run.
+    #[cfg_attr(not(feature = "jit"), allow(unused_variables))]
+    fn tick(&mut self, idle: usize, batch: bool) -> Result<StepInfo, CpuError> {
+        if self.halted {
+            return Err(CpuError::Halted { pc: self.regs.pc });
+        }
+        let pc = self.regs.pc;
+        let mut info = StepInfo { pc, op: 0, size: 0, cycles: 0, branch: false, exception: false };
+        self.cycles = 0;
+        self.exception_taken = false;
+        if !self.check_interrupt() {
+            if self.stopped {
+                self.cycles = self.bus.next_event().map_or(idle, |n| n.clamp(1, idle));
+            } else {
+                // An interrupt taken since the stop moves PC, so the handler is checked too.
+                let resuming = self.resuming.take() == Some(pc);
+                if !self.breakpoints.is_empty() && !resuming && self.breakpoints.contains(&pc) {
+                    self.resuming = Some(pc);
+                    return Err(CpuError::Breakpoint { pc });
+                }
+                if self.pre_exec_hook.is_some() && !resuming {
+                    let op = self.bus.read16(pc & self.adr_mask);
+                    self.bus.take_bus_error();
+                    if self.call_exec_hook(true, pc, op) {
+                        self.resuming = Some(pc);
+                        return Err(CpuError::Stopped { pc });
+                    }
+                }
+                #[cfg(feature = "jit")]
+                if batch && self.jit.is_some() {
+                    if let Some((op, end, count)) = self.run_jit(pc) {
+                        info.op = op;
+                        info.size = end.wrapping_sub(pc) as usize;
+                        info.branch = self.regs.pc != end;
+                        self.bus.advance(self.cycles);
+                        info.cycles = self.cycles;
+                        self.instructions += count as u64;
+                        self.total_cycles += self.cycles as u64;
+                        return Ok(info);
+                    }
+                }
+                let traced = if self.trace_sink.is_some() { Some(self.trace_before(pc)) } else { None };
+                self.fetch_end = pc;
+                let result = self.execute_one();
+                if let Some(mut event) = traced {
+                    event.changes = diff_registers(&event.regs, &self.regs);
+                    if let Some(sink) = self.trace_sink.as_mut() {
+                        sink.trace(&event);
+                    }
+                }
+                info.op = result?;
+                info.size = self.fetch_end.wrapping_sub(pc) as usize;
+                info.branch = self.regs.pc != self.fetch_end;
+            }
+        }
+        info.exception = self.exception_taken;
+        info.branch |= info.exception;
+        self.bus.advance(self.cycles);
+        info.cycles = self.cycles;
+        if info.size != 0 {
+            self.instructions += 1;
+        }
+        self.total_cycles += self.cycles as u64;
+        if info.size != 0 && self.call_exec_hook(false, pc, info.op) {
+            return Err(CpuError::Stopped { pc: self.regs.pc });
+        }
+        Ok(info)
+    }
+
+    // Run the compiled block at `pc`, if there is one and nothing needs to observe
+    // the single instructions. Returns its first opcode, end address and instruction count.
+    #[cfg(feature = "jit")]
+    fn run_jit(&mut self, pc: Adr) -> Option<(Word, Adr, usize)> {
+        if self.trace_sink.is_some() || self.pre_exec_hook.is_some() || self.post_exec_hook.is_some()
+            || !self.breakpoints.is_empty() || self.prefetch
+            || (self.regs.sr & FLAG_T) != 0 || (pc & 1) != 0 || pc != (pc & self.adr_mask) {
+            return None;
+        }
+        let bus = &self.bus;
+        let (func, op, end, count) = self.jit.as_mut()?.block(pc, |adr| {
+            let word = bus.read16(adr);
+            if bus.take_bus_error() { None } else { Some((word, bus.wait_states(adr))) }
+        })?;
+        let mut ctx = JitContext { d: self.regs.d, sr: self.regs.sr as Long, pc, cycles: 0 };
+        // Safe: compiled blocks only access the context passed in.
+        unsafe { func(&mut ctx) };
+        self.regs.d = ctx.d;
+        self.regs.sr = ctx.sr as Word;
+        self.regs.pc = ctx.pc;
+        self.cycles += ctx.cycles as usize;
+        Some((op, end, count))
+    }
+
+    // Trace event for the instruction at `pc`, but for the register changes.
+    fn trace_before(&mut self, pc: Adr) -> TraceEvent {
+        let inst = decode(&BusView(&self.bus), pc);
+        let words = (0..inst.length as Adr / 2).map(|i| self.bus.read16(pc + i * 2)).collect();
+        self.bus.take_bus_error();
+        TraceEvent {
+            pc, words, mnemonic: inst.to_string(), comment: inst.annotation(Some(&self.regs)),
+            regs: self.regs.clone(), changes: Vec::new(),
+        }
+    }
+
+    fn call_exec_hook(&mut self, pre: bool, pc: Adr, op: Word) -> bool {
+        let hook = if pre { self.pre_exec_hook.take() } else { self.post_exec_hook.take() };
+        match hook {
+            Some(mut hook) => {
+                let stop = hook(self, pc, op);
+                if pre { self.pre_exec_hook = Some(hook); } else { self.post_exec_hook = Some(hook); }
+                stop
+            },
+            None => false,
+        }
+    }
+
+    // Execute the instruction at PC and return its opcode.
+    fn execute_one(&mut self) -> Result<Word, CpuError> {
+        let startadr = self.regs.pc;
+        if (startadr & 1) != 0 {
+            let fault = Fault {vector: ADDRESS_ERROR_VECTOR, adr: startadr, write: false, fetch: true};
+            self.group0_exception(fault, 0);
+            return Ok(0);
+        }
+        let trace = (self.regs.sr & FLAG_T) != 0;  // Sampled before the instruction.
+        self.inst_adr = startadr;
+        let (op, handler) = self.fetch_decoded(startadr);
+        self.regs.pc += 2;
+        handler(self, startadr, op);
+        self.replay = None;
+        if let Some(decoded) = self.recording.take() {
+            if self.fault.is_none() && self.error.is_none() {
+                self.decode_cache[decode_index(decoded.adr)] = decoded;
+            }
+        }
+        if let Some(err) = self.error.take() {
+            self.fault = None;
+            self.regs.pc = startadr;
+            return Err(err);
+        }
+        if let Some(fault) = self.fault.take() {
+            self.group0_exception(fault, op);
+        } else if trace {
+            self.exception(TRACE_VECTOR);
+        }
+        Ok(op)
+    }
+
+    // Sampled between instructions: take the highest pending level if above the mask.
+    fn check_interrupt(&mut self) -> bool {
+        let lines = (self.irq_lines & 0xfe) as Word;
+        let level = if lines != 0 { 15 - lines.leading_zeros() as Word } else { 0 };
+        let level = level.max(self.bus.interrupt_level() & 7);
+        if level < 7 {
+            self.nmi_taken = false;
+        }
+        let accept = if level == 7 { !self.nmi_taken } else { level > self.interrupt_mask() };
+        if !accept {
+            return false;
+        }
+        if level == 7 {
+            self.nmi_taken = true;
+        }
+        self.stopped = false;
+        let vector = if (self.irq_lines & (1 << level)) != 0 {
+            match self.irq_vectors[level as usize] {
+                Some(vector) => vector as Word,
+                None => AUTOVECTOR_START + level,
+            }
+        } else {
+            match self.bus.acknowledge_interrupt(level) {
+                InterruptAck::Autovector => AUTOVECTOR_START + level,
+                InterruptAck::Vector(vector) => vector as Word,
+                InterruptAck::Spurious => AUTOVECTOR_START,
+            }
+        };
+        self.cycles += 14;  // Interrupt acknowledge cycle.
+        self.exception(vector);
+        self.regs.sr = (self.regs.sr & !0x0700) | (level << 8);
+        if let Some(fault) = self.fault.take() {
+            self.group0_exception(fault, 0);
+        }
+        true
+    }
+
+    // The opcode at `adr` and its handler, from the decode cache if it holds the instruction.
+    // Otherwise the words it fetches are recorded, to be cached once it completes.
+    fn fetch_decoded(&mut self, adr: Adr) -> (Word, Handler<BusT>) {
+        if !self.decode_cache.is_empty() && !self.prefetch {
+            let tag = adr & self.adr_mask;
+            let i = decode_index(tag);
+            if self.decode_cache[i].adr == tag {
+                self.replay = Some(i);
+                return (self.fetch16(adr), self.decode_cache[i].handler);
+            }
+            self.recording = Some(Decoded { adr: tag, ..Self::no_entry() });
+        }
+        let op = self.fetch16(adr);
+        let handler = Self::HANDLERS[INST[op as usize].op as usize];
+        if let Some(decoded) = self.recording.as_mut() {
+            decoded.handler = handler;
+        }
+        (op, handler)
+    }
+
+    fn no_entry() -> Decoded<BusT> {
+        Decoded { adr: NO_ENTRY, handler: Self::HANDLERS[0], len: 0, words: [0; DECODED_WORDS] }
+    }
+
+    fn check_cond(&self, cc: Word) -> bool {
+        let sr = self.regs.sr;
+        let c = (sr & FLAG_C) != 0;
+        let v = (sr & FLAG_V) != 0;
+        let z = (sr & FLAG_Z) != 0;
+        let n = (sr & FLAG_N) != 0;
+        match cc & 0x0f {
+            0x0 => true,        // T
+            0x1 => false,       // F
+            0x2 => !c && !z,    // HI
+            0x3 => c || z,      // LS
+            0x4 => !c,          // CC
+            0x5 => c,           // CS
+            0x6 => !z,          // NE
+            0x7 => z,           // EQ
+            0x8 => !v,          // VC
+            0x9 => v,           // VS
+            0xa => !n,          // PL
+            0xb => n,           // MI
+            0xc => n == v,      // GE
+            0xd => n != v,      // LT
+            0xe => !z && n == v,  // GT
+            _   => z || n != v,   // LE
+        }
+    }
+
+    fn bcond(&mut self, op: Word, cond: bool) {
+        let (ofs, sz) = get_branch_offset(op, &mut self.bus, self.regs.pc);
+        self.fetch_end = self.regs.pc + sz;
+        self.cycles += if cond { 6 } else { 4 + sz as usize * 2 };
+        self.regs.pc = if cond { (self.regs.pc as SLong).wrapping_add(ofs) as Adr } else { self.regs.pc + sz };
+    }
+
+    // Test a bit of a data register (modulo 32) or a memory byte (modulo 8),
+    // setting Z from its old value and writing back `f(value, mask)` if given.
+    fn bit_op(&mut self, dt: usize, di: usize, bit: Long, f: Option<fn(Long, Long) -> Long>) {
+        let zero = if dt == 0 {
+            let val = self.regs.d[di];
+            let mask = 1 << (bit & 31);
+            if let Some(f) = f {
+                self.regs.d[di] = f(val, mask);
+            }
+            (val & mask) == 0
+        } else {
+            let val = self.read_source8_incpc(dt, di, f.is_none()) as Long;
+            let mask = 1 << (bit & 7);
+            if let Some(f) = f {
+                self.write_destination8(dt, di, f(val, mask) as Byte);
+            }
+            (val & mask) == 0
+        };
+        self.regs.sr = (self.regs.sr & !FLAG_Z) | (if zero {FLAG_Z} else {0});
+    }
+
+    // Transfer multiple registers. The mask is reversed (bit 0 = A7) for -(An),
+    // otherwise registers are transferred in D0-D7/A0-A7 order.
+    fn movem(&mut self, op: Word) {
+        let n = (op & 7) as usize;
+        let mode = ((op >> 3) & 7) as usize;
+        let long = (op & 0x0040) != 0;
+        let size = if long {4} else {2};
+        let bits = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        if (op & 0x0400) == 0 {  // Registers to memory.
+            if mode == 4 {
+                let mut p = self.regs.a[n];
+                for i in 0..16 {
+                    if (bits & (1 << i)) != 0 {
+                        let val = if i < 8 { self.regs.a[7 - i] } else { self.regs.d[15 - i] };
+                        p = p.wrapping_sub(size);
+                        if long { self.write32(p, val); } else { self.write16(p, val as Word); }
+                    }
+                }
+                self.regs.a[n] = p;
+            } else {
+                let mut p = self.control_adr(mode, n);
+                for i in 0..16 {
+                    if (bits & (1 << i)) != 0 {
+                        let val = if i < 8 { self.regs.d[i] } else { self.regs.a[i - 8] };
+                        if long { self.write32(p, val); } else { self.write16(p, val as Word); }
+                        p = p.wrapping_add(size);
+                    }
+                }
+            }
+        } else {  // Memory to registers, words are sign-extended.
+            let mut p = if mode == 3 { self.regs.a[n] } else { self.control_adr(mode, n) };
+            for i in 0..16 {
+                if (bits & (1 << i)) != 0 {
+                    let val = if long { self.read32(p) } else { self.read16(p) as SWord as SLong as Long };
+                    if i < 8 { self.regs.d[i] = val; } else { self.regs.a[i - 8] = val; }
+                    p = p.wrapping_add(size);
+                }
+            }
+            if mode == 3 {
+                self.regs.a[n] = p;
+            }
+        }
+    }
+
+    // Two-operand ALU instruction: <ea>, Dn or Dn, <ea> depending on bit 8.
+    fn alu(&mut self, op: Word, f: fn(&mut Self, u32, Long, Long) -> Long) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let ri = ((op >> 9) & 7) as usize;
+        let bits = 8 << ((op >> 6) & 3);
+        if (op & 0x0100) == 0 {  // <ea>, Dn
+            if bits == 32 { self.cycles += if st <= 1 || (st == 7 && si == 4) { 4 } else { 2 }; }
+            let src = self.read_source(bits, st, si, true);
+            let res = f(self, bits, src, self.regs.d[ri]);
+            self.write_destination(bits, 0, ri, res);
+        } else {  // Dn, <ea>
+            let dst = self.read_source(bits, st, si, false);
+            let res = f(self, bits, self.regs.d[ri], dst);
+            self.write_destination(bits, st, si, res);
+        }
+    }
+
+    // ADDQ/SUBQ: an address register is always updated as a whole, without touching the flags.
+    fn quick(&mut self, op: Word, f: fn(&mut Self, u32, Long, Long) -> Long) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let v = conv07to18(op >> 9) as Long;
+        if dt == 1 {
+            self.cycles += 4;
+            let sr = self.regs.sr;
+            self.regs.a[di] = f(self, 32, v, self.regs.a[di]);
+            self.regs.sr = sr;
+        } else {
+            let bits = 8 << ((op >> 6) & 3);
+            if bits == 32 && dt == 0 { self.cycles += 4; }
+            let dst = self.read_source(bits, dt, di, false);
+            let res = f(self, bits, v, dst);
+            self.write_destination(bits, dt, di, res);
+        }
+    }
+
+    // Immediate ALU instruction: #imm, <ea>.
+    fn alu_imm(&mut self, op: Word, f: fn(&mut Self, u32, Long, Long) -> Long) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let bits = 8 << ((op >> 6) & 3);
+        let src = self.read_imm(bits);
+        if bits == 32 && dt == 0 { self.cycles += 4; }
+        let dst = self.read_source(bits, dt, di, false);
+        let res = f(self, bits, src, dst);
+        self.write_destination(bits, dt, di, res);
+    }
+
+    // Byte and word immediates occupy one extension word, long ones two.
+    fn read_imm(&mut self, bits: u32) -> Long {
+        let value = if bits == 32 {
+            self.fetch32(self.regs.pc)
+        } else {
+            self.fetch16(self.regs.pc) as Long & size_mask(bits)
+        };
+        self.regs.pc += if bits == 32 {4} else {2};
+        value
+    }
+
+    fn add_val(&mut self, bits: u32, src: Long, dst: Long) -> Long {
+        let mask = size_mask(bits);
+        let (src, dst) = (src & mask, dst & mask);
+        let res = dst.wrapping_add(src) & mask;
+        let carry = (dst as u64) + (src as u64) > (mask as u64);
+        let overflow = ((src ^ res) & (dst ^ res)) >> (bits - 1) & 1 != 0;
+        self.set_arith_sr(res, bits, carry, overflow, carry);
+        res
+    }
+
+    fn sub_val(&mut self, bits: u32, src: Long, dst: Long) -> Long {
+        let mask = size_mask(bits);
+        let (src, dst) = (src & mask, dst & mask);
+        let res = dst.wrapping_sub(src) & mask;
+        let borrow = src > dst;
+        let overflow = ((src ^ dst) & (res ^ dst)) >> (bits - 1) & 1 != 0;
+        self.set_arith_sr(res, bits, borrow, overflow, borrow);
+        res
+    }
+
+    // Like sub_val, but leaves X untouched and discards the result.
+    fn cmp_val(&mut self, bits: u32, src: Long, dst: Long) {
+        let mask = size_mask(bits);
+        let (src, dst) = (src & mask, dst & mask);
+        let res = dst.wrapping_sub(src) & mask;
+        let overflow = ((src ^ dst) & (res ^ dst)) >> (bits - 1) & 1 != 0;
+        self.set_cmp_sr(src > dst, res == 0, overflow, (res >> (bits - 1)) != 0);
+    }
+
+    fn and_val(&mut self, bits: u32, src: Long, dst: Long) -> Long {
+        let res = src & dst & size_mask(bits);
+        self.set_and_sr(res == 0, (res >> (bits - 1)) != 0);
+        res
+    }
+
+    fn or_val(&mut self, bits: u32, src: Long, dst: Long) -> Long {
+        let res = (src | dst) & size_mask(bits);
+        self.set_and_sr(res == 0, (res >> (bits - 1)) != 0);
+        res
+    }
+
+    fn eor_val(&mut self, bits: u32, src: Long, dst: Long) -> Long {
+        let res = (src ^ dst) & size_mask(bits);
+        self.set_and_sr(res == 0, (res >> (bits - 1)) != 0);
+        res
+    }
+
+    // Shift/rotate a data register, by an immediate (1~8) or register (modulo 64) count.
+    fn shift_reg(&mut self, op: Word) {
+        let di = (op & 7) as usize;
+        let count = if (op & 0x0020) != 0 {
+            self.regs.d[((op >> 9) & 7) as usize] & 63
+        } else {
+            conv07to18(op >> 9) as Long
+        };
+        let bits = 8 << ((op >> 6) & 3);
+        self.cycles += if bits == 32 { 4 } else { 2 } + count as usize * 2;
+        let val = self.regs.d[di];
+        let x = (self.regs.sr & FLAG_X) != 0;
+        let (res, carry, overflow, ext) = shift(ShiftKind::from_bits(op >> 3), (op & 0x0100) != 0, bits, val, count, x);
+        self.regs.d[di] = match bits {
+            8 => replace_byte(val, res as Byte),
+            16 => replace_word(val, res as Word),
+            _ => res,
+        };
+        self.set_arith_sr(res, bits, carry, overflow, ext);
+    }
+
+    // Shift/rotate a memory word by one bit.
+    fn shift_mem(&mut self, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let val = self.read_source16_incpc(dt, di, false);
+        let x = (self.regs.sr & FLAG_X) != 0;
+        let (res, carry, overflow, ext) = shift(ShiftKind::from_bits(op >> 9), (op & 0x0100) != 0, 16, val as Long, 1, x);
+        self.write_destination16(dt, di, res as Word);
+        self.set_arith_sr(res, 16, carry, overflow, ext);
+    }
+
+    fn set_arith_sr(&mut self, res: Long, bits: u32, carry: bool, overflow: bool, ext: bool) {
+        let mut ccr = 0;
+        if ext                         { ccr |= FLAG_X; }
+        if (res >> (bits - 1)) & 1 != 0 { ccr |= FLAG_N; }
+        if res == 0                    { ccr |= FLAG_Z; }
+        if overflow                    { ccr |= FLAG_V; }
+        if carry                       { ccr |= FLAG_C; }
+        self.regs.sr = (self.regs.sr & !(FLAG_X | FLAG_N | FLAG_Z | FLAG_V | FLAG_C)) | ccr;
+    }
+
+    fn bcd_op(&mut self, op: Word, f: fn(Byte, Byte, bool) -> (Byte, bool, bool)) {
+        let si = (op & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let x = (self.regs.sr & FLAG_X) != 0;
+        if (op & 0x0008) == 0 {  // Dy, Dx
+            let (res, carry, overflow) = f(self.regs.d[di] as Byte, self.regs.d[si] as Byte, x);
+            self.regs.d[di] = replace_byte(self.regs.d[di], res);
+            self.set_bcd_sr(res, carry, overflow);
+        } else {  // -(Ay), -(Ax)
+            self.regs.a[si] = self.regs.a[si].wrapping_sub(byte_step(si));
+            let src = self.read8(self.regs.a[si]);
+            self.regs.a[di] = self.regs.a[di].wrapping_sub(byte_step(di));
+            let dst = self.read8(self.regs.a[di]);
+            let (res, carry, overflow) = f(dst, src, x);
+            self.write8(self.regs.a[di], res);
+            self.set_bcd_sr(res, carry, overflow);
+        }
+    }
+
+    // All writes of the whole SR go through here, so that changes of the
+    // interrupt mask are observed in one place.
+    // Switching between supervisor and user mode also swaps the active A7 (SSP/USP).
+    pub fn set_sr(&mut self, value: Word) {
+        let value = value & SR_MASK;
+        if ((value ^ self.regs.sr) & FLAG_S) != 0 {
+            if (value & FLAG_S) != 0 {
+                self.regs.usp = self.regs.a[SP];
+                self.regs.a[SP] = self.regs.ssp;
+            } else {
+                self.regs.ssp = self.regs.a[SP];
+                self.regs.a[SP] = self.regs.usp;
+            }
+        }
+        self.regs.sr = value;
+    }
+
+    // MULU.L/MULS.L: 32 x 32 bits into Dl, or into Dh:Dl for the 64-bit form.
+    fn mul_long(&mut self, op: Word) {
+        let ext = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        let src = self.read_source32(((op >> 3) & 7) as usize, (op & 7) as usize);
+        let dl = ((ext >> 12) & 7) as usize;
+        let dh = (ext & 7) as usize;
+        let signed = (ext & 0x0800) != 0;
+        let res = if signed {
+            (self.regs.d[dl] as SLong as i64).wrapping_mul(src as SLong as i64) as u64
+        } else {
+            self.regs.d[dl] as u64 * src as u64
+        };
+        let mut ccr = 0;
+        if (ext & 0x0400) != 0 {
+            self.regs.d[dh] = (res >> 32) as Long;
+            if res == 0         { ccr |= FLAG_Z; }
+            if (res >> 63) != 0 { ccr |= FLAG_N; }
+        } else {
+            let lo = res as Long;
+            let overflow = if signed { lo as SLong as i64 as u64 != res } else { (res >> 32) != 0 };
+            if lo == 0          { ccr |= FLAG_Z; }
+            if (lo >> 31) != 0  { ccr |= FLAG_N; }
+            if overflow         { ccr |= FLAG_V; }
+        }
+        self.regs.d[dl] = res as Long;
+        self.regs.sr = (self.regs.sr & !(FLAG_N | FLAG_Z | FLAG_V | FLAG_C)) | ccr;
+    }
+
+    // DIVU.L/DIVS.L: Dq (or Dr:Dq for the 64-bit form) / <ea>, remainder into Dr
+    // unless it is the same register. On overflow only V is set.
+    fn div_long(&mut self, op: Word) {
+        let ext = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        let divisor = self.read_source32(((op >> 3) & 7) as usize, (op & 7) as usize);
+        let dq = ((ext >> 12) & 7) as usize;
+        let dr = (ext & 7) as usize;
+        if divisor == 0 {
+            self.exception(ZERO_DIVIDE_VECTOR);
+            return;
+        }
+        let wide = (ext & 0x0400) != 0;
+        let dividend = if wide { ((self.regs.d[dr] as u64) << 32) | self.regs.d[dq] as u64 } else { self.regs.d[dq] as u64 };
+        let result = if (ext & 0x0800) != 0 {
+            let dividend = if wide { dividend as i64 } else { dividend as Long as SLong as i64 };
+            let divisor = divisor as SLong as i64;
+            let q = dividend.wrapping_div(divisor);
+            if q == q as SLong as i64 { Some((q as Long, dividend.wrapping_rem(divisor) as Long)) } else { None }
+        } else {
+            let q = dividend / divisor as u64;
+            if q == q as Long as u64 { Some((q as Long, (dividend % divisor as u64) as Long)) } else { None }
+        };
+        let mut ccr = 0;
+        match result {
+            Some((q, rem)) => {
+                if dr != dq { self.regs.d[dr] = rem; }
+                self.regs.d[dq] = q;
+                if q == 0         { ccr |= FLAG_Z; }
+                if (q >> 31) != 0 { ccr |= FLAG_N; }
+            },
+            None => {
+                ccr |= FLAG_V;
+            },
+        }
+        self.regs.sr = (self.regs.sr & !(FLAG_N | FLAG_Z | FLAG_V | FLAG_C)) | ccr;
+    }
+
+    // BFxxx: a field of 1~32 bits, `offset` bits from the MSB of a data register
+    // (wrapping around) or of the byte at <ea> (offset from a register is signed).
+    fn bitfield(&mut self, op: Word) {
+        let ext = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        let mode = ((op >> 3) & 7) as usize;
+        let n = (op & 7) as usize;
+        let rn = ((ext >> 12) & 7) as usize;
+        let offset = if (ext & 0x0800) != 0 { self.regs.d[((ext >> 6) & 7) as usize] as SLong } else { ((ext >> 6) & 31) as SLong };
+        let width = if (ext & 0x0020) != 0 { self.regs.d[(ext & 7) as usize] } else { ext as Long };
+        let width = (width.wrapping_sub(1) & 31) + 1;
+        let mask = size_mask(width);
+
+        let (adr, bit, nbytes, mut window) = if mode == 0 {
+            (0, 0, 0, self.regs.d[n].rotate_left(offset as u32 & 31) as u64)
+        } else {
+            let base = self.control_adr(mode, n);
+            let adr = (base as SLong).wrapping_add(offset >> 3) as Adr;
+            let bit = (offset & 7) as u32;
+            let nbytes = (bit + width).div_ceil(8);
+            let mut window = 0;
+            for i in 0..nbytes {
+                window |= (self.read8(adr.wrapping_add(i)) as u64) << (56 - 8 * i);
+            }
+            (adr, bit, nbytes, window)
+        };
+        let shift = if mode == 0 { 32 - width } else { 64 - bit - width };
+        let field = (window >> shift) as Long & mask;
+
+        let kind = (op >> 8) & 7;
+        let flags = if kind == 7 { self.regs.d[rn] & mask } else { field };
+        let new = match kind {
+            1 => { self.regs.d[rn] = field; None },  // BFEXTU
+            2 => Some(!field & mask),  // BFCHG
+            3 => { self.regs.d[rn] = ((field << (32 - width)) as SLong >> (32 - width)) as Long; None },  // BFEXTS
+            4 => Some(0),  // BFCLR
+            5 => {  // BFFFO
+                let zeros = if field == 0 { width } else { field.leading_zeros() - (32 - width) };
+                self.regs.d[rn] = (offset as Long).wrapping_add(zeros);
+                None
+            },
+            6 => Some(mask),  // BFSET
+            7 => Some(flags),  // BFINS
+            _ => None,  // BFTST
+        };
+        if let Some(new) = new {
+            window = (window & !((mask as u64) << shift)) | ((new as u64) << shift);
+            if mode == 0 {
+                self.regs.d[n] = (window as Long).rotate_right(offset as u32 & 31);
+            } else {
+                for i in 0..nbytes {
+                    self.write8(adr.wrapping_add(i), (window >> (56 - 8 * i)) as Byte);
+                }
+            }
+        }
+        self.set_and_sr(flags == 0, (flags >> (width - 1)) & 1 != 0);
+    }
+
+    // CAS: if <ea> equals Dc, store Du there, otherwise load it into Dc.
+    fn cas(&mut self, op: Word) {
+        let ext = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        let bits = 4 << ((op >> 9) & 3);
+        let adr = self.alterable_adr(bits, ((op >> 3) & 7) as usize, (op & 7) as usize);
+        let dst = self.read_mem(bits, adr);
+        let dc = (ext & 7) as usize;
+        let du = ((ext >> 6) & 7) as usize;
+        let cmp = self.regs.d[dc] & size_mask(bits);
+        self.cmp_val(bits, cmp, dst);
+        if cmp == dst {
+            self.write_mem(bits, adr, self.regs.d[du]);
+        } else {
+            self.write_destination(bits, 0, dc, dst);
+        }
+    }
+
+    // CAS2: both operands must match their compare registers to be updated together.
+    fn cas2(&mut self, op: Word) {
+        let ext1 = self.fetch16(self.regs.pc);
+        let ext2 = self.fetch16(self.regs.pc + 2);
+        self.regs.pc += 4;
+        let bits = if (op & 0x0200) != 0 { 32 } else { 16 };
+        let adr1 = self.ext_reg(ext1);
+        let adr2 = self.ext_reg(ext2);
+        let dst1 = self.read_mem(bits, adr1);
+        let dst2 = self.read_mem(bits, adr2);
+        let (dc1, dc2) = ((ext1 & 7) as usize, (ext2 & 7) as usize);
+        let cmp1 = self.regs.d[dc1] & size_mask(bits);
+        let cmp2 = self.regs.d[dc2] & size_mask(bits);
+        self.cmp_val(bits, cmp1, dst1);
+        if cmp1 == dst1 {
+            self.cmp_val(bits, cmp2, dst2);
+        }
+        if cmp1 == dst1 && cmp2 == dst2 {
+            self.write_mem(bits, adr1, self.regs.d[((ext1 >> 6) & 7) as usize]);
+            self.write_mem(bits, adr2, self.regs.d[((ext2 >> 6) & 7) as usize]);
+        } else {
+            self.write_destination(bits, 0, dc1, dst1);
+            self.write_destination(bits, 0, dc2, dst2);
+        }
+    }
+
+    // General register selected by bits 15~12 of an extension word.
+    fn ext_reg(&self, ext: Word) -> Long {
+        let rn = ((ext >> 12) & 7) as usize;
+        if (ext & 0x8000) != 0 {self.regs.a[rn]} else {self.regs.d[rn]}
+    }
+
+    // Address of a memory alterable operand, applying (An)+/-(An).
+    fn alterable_adr(&mut self, bits: u32, mode: usize, n: usize) -> Adr {
+        let size = if bits == 8 { byte_step(n) } else { bits / 8 };
+        match mode {
+            3 => {
+                let adr = self.regs.a[n];
+                self.regs.a[n] = adr.wrapping_add(size);
+                adr
+            },
+            4 => {
+                self.regs.a[n] = self.regs.a[n].wrapping_sub(size);
+                self.regs.a[n]
+            },
+            _ => self.control_adr(mode, n),
+        }
+    }
+
+    fn read_mem(&mut self, bits: u32, adr: Adr) -> Long {
+        match bits {
+            8 => self.read8(adr) as Long,
+            16 => self.read16(adr) as Long,
+            _ => self.read32(adr),
+        }
+    }
+
+    fn write_mem(&mut self, bits: u32, adr: Adr, value: Long) {
+        match bits {
+            8 => self.write8(adr, value as Byte),
+            16 => self.write16(adr, value as Word),
+            _ => self.write32(adr, value),
+        }
+    }
+
+    // Bytes of an exception frame left after SR, PC and the format word, if the
+    // format is valid for the model. The faulted bus cycle of a bus/address error
+    // frame is not rerun.
+    fn frame_extra(&self, format: Word) -> Option<Adr> {
+        match format {
+            0 => Some(0),
+            2 if self.model >= CpuModel::M68020 => Some(4),
+            8 if self.model == CpuModel::M68010 => Some(50),
+            0xa if self.model >= CpuModel::M68020 => Some(24),
+            _ => None,
+        }
+    }
+
+    // Instructions added by later models are illegal on earlier ones.
+    fn check_model(&mut self, startadr: Adr, model: CpuModel) -> bool {
+        if self.model >= model {
+            true
+        } else {
+            self.regs.pc = startadr;
+            self.exception(ILLEGAL_INSTRUCTION_VECTOR);
+            false
+        }
+    }
+
+    // TST and CMPI take An, PC relative and immediate operands only from the 68020 on.
+    fn check_68020_ea(&mut self, startadr: Adr, mode: usize, n: usize) -> bool {
+        if mode == 1 || (mode == 7 && n >= 2) {
+            self.check_model(startadr, CpuModel::M68020)
+        } else {
+            true
+        }
+    }
+
+    // MOVEC: bit 0 of the opcode selects general register -> control register.
+    fn movec(&mut self, startadr: Adr, op: Word) {
+        let ext = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        let rn = ((ext >> 12) & 7) as usize;
+        let da = (ext & 0x8000) != 0;
+        let cr = ext & 0x0fff;
+        let valid = match cr {
+            0x000 | 0x001 | 0x800 | 0x801 => true,
+            0x002 | 0x802 => self.model >= CpuModel::M68020,  // CACR, CAAR
+            _ => false,
+        };
+        if !valid {
+            self.regs.pc = startadr;
+            self.exception(ILLEGAL_INSTRUCTION_VECTOR);
+            return;
+        }
+        if (op & 1) == 0 {
+            let value = match cr {
+                0x000 => self.regs.sfc as Long,
+                0x001 => self.regs.dfc as Long,
+                0x002 => self.regs.cacr,
+                0x800 => self.regs.usp,
+                0x801 => self.regs.vbr,
+                _ => self.regs.caar,
+            };
+            if da { self.regs.a[rn] = value; } else { self.regs.d[rn] = value; }
+        } else {
+            let value = if da {self.regs.a[rn]} else {self.regs.d[rn]};
+            match cr {
+                0x000 => self.regs.sfc = (value & 7) as Byte,
+                0x001 => self.regs.dfc = (value & 7) as Byte,
+                0x002 => self.regs.cacr = value,
+                0x800 => self.regs.usp = value,
+                0x801 => self.regs.vbr = value,
+                _ => self.regs.caar = value,
+            }
+        }
+    }
+
+    // MOVES: the bus has no function code lines, so SFC/DFC do not change the access.
+    fn moves(&mut self, startadr: Adr, op: Word) {
+        let ei = (op & 7) as usize;
+        let et = ((op >> 3) & 7) as usize;
+        if et < 2 {
+            self.regs.pc = startadr;
+            self.exception(ILLEGAL_INSTRUCTION_VECTOR);
+            return;
+        }
+        let ext = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        let rn = ((ext >> 12) & 7) as usize;
+        let da = (ext & 0x8000) != 0;
+        let bits = 8 << ((op >> 6) & 3);
+        if (ext & 0x0800) != 0 {  // Rn, <ea>
+            let value = if da {self.regs.a[rn]} else {self.regs.d[rn]};
+            self.write_destination(bits, et, ei, value);
+        } else {  // <ea>, Rn
+            let value = self.read_source(bits, et, ei, true);
+            if da {
+                self.regs.a[rn] = match bits {
+                    8 => value as Byte as SByte as SLong as Long,
+                    16 => value as Word as SWord as SLong as Long,
+                    _ => value,
+                };
+            } else {
+                self.regs.d[rn] = match bits {
+                    8 => replace_byte(self.regs.d[rn], value as Byte),
+                    16 => replace_word(self.regs.d[rn], value as Word),
+                    _ => value,
+                };
+            }
+        }
+    }
+
+    // Raise a privilege violation unless the CPU is in supervisor mode.
+    fn check_supervisor(&mut self, startadr: Adr) -> bool {
+        if (self.regs.sr & FLAG_S) != 0 {
+            true
+        } else {
+            self.regs.pc = startadr;
+            self.exception(PRIVILEGE_VIOLATION_VECTOR);
+            false
+        }
+    }
+
+    // Group 1/2 exception: push the short frame (SR, PC) in supervisor mode.
+    // A pending group 0 fault aborts the instruction, so it takes precedence.
+    fn exception(&mut self, vector: Word) {
+        if self.fault.is_some() || self.error.is_some() {
+            return;
+        }
+        let sr = self.regs.sr;
+        self.exception_taken = true;
+        self.cycles += 10;  // Internal processing and refilling the prefetch at the handler.
+        self.set_sr((sr | FLAG_S) & !FLAG_T);
+        if self.model >= CpuModel::M68020 && (vector == ZERO_DIVIDE_VECTOR || vector == CHK_VECTOR || vector == TRACE_VECTOR) {
+            self.push32(self.inst_adr);  // Format 2 also records the instruction address.
+            self.push16(0x2000 | (vector << 2));
+        } else if self.model != CpuModel::M68000 {
+            self.push16(vector << 2);  // Format 0, vector offset.
+        }
+        self.push32(self.regs.pc);
+        self.push16(sr);
+        self.regs.pc = self.read32(self.regs.vbr.wrapping_add(vector as Adr * 4));
+    }
+
+    // Address/bus error: push the long frame (status word, access address, opcode, SR, PC).
+    // The 68010 instead pushes a 29-word format $8 frame after the format word,
+    // and the 68020/68030 a 16-word format $A (short bus cycle fault) frame.
+    fn group0_exception(&mut self, fault: Fault, op: Word) {
+        let sr = self.regs.sr;
+        self.exception_taken = true;
+        self.set_sr((sr | FLAG_S) & !FLAG_T);
+        let mut fc = if fault.fetch {2} else {1};  // FC1/FC0: program or data.
+        if (sr & FLAG_S) != 0 { fc |= 4; }
+        match self.model {
+            CpuModel::M68000 => {
+                let mut status = fc;
+                if !fault.fetch { status |= 0x08; }  // I/N: not an instruction.
+                if !fault.write { status |= 0x10; }
+                self.push32(self.regs.pc);
+                self.push16(sr);
+                self.push16(op);
+                self.push32(fault.adr);
+                self.push16(status);
+            },
+            CpuModel::M68010 => {
+                let mut ssw = fc | if fault.fetch {0x2000} else {0x1000};  // IF/DF
+                if !fault.write { ssw |= 0x0100; }  // RW
+                for _ in 0..16 {
+                    self.push16(0);  // Internal information.
+                }
+                self.push16(op);  // Instruction input buffer.
+                for _ in 0..5 {
+                    self.push16(0);  // Data input/output buffers.
+                }
+                self.push32(fault.adr);
+                self.push16(ssw);
+                self.push16(0x8000 | (fault.vector << 2));
+                self.push32(self.regs.pc);
+                self.push16(sr);
+            },
+            _ => {
+                let mut ssw = fc | if fault.fetch {0x4000} else {0x0100};  // FB/DF
+                if !fault.write { ssw |= 0x0040; }  // RW
+                self.push32(0);  // Internal registers.
+                self.push32(0);  // Data output buffer.
+                self.push32(0);  // Internal registers.
+                self.push32(fault.adr);  // Data cycle fault address.
+                self.push16(0);  // Instruction pipe stage B.
+                self.push16(op);  // Instruction pipe stage C.
+                self.push16(ssw);
+                self.push16(0);  // Internal register.
+                self.push16(0xa000 | (fault.vector << 2));
+                self.push32(self.regs.pc);
+                self.push16(sr);
+            },
+        }
+        self.regs.pc = self.read32(self.regs.vbr.wrapping_add(fault.vector as Adr * 4));
+        // Another bus or address error while stacking the frame, fetching the vector,
+        // or at the (odd) handler address is a double bus fault, which halts the CPU.
+        if self.fault.take().is_some() || (self.regs.pc & 1) != 0 {
+            self.halted = true;
+        }
+    }
+
+    fn push16(&mut self, value: Word) {
+        let sp = self.regs.a[SP].wrapping_sub(2);
+        self.regs.a[SP] = sp;
+        self.write16(sp, value);
+    }
+
+    fn push32(&mut self, value: Long) {
+        let sp = self.regs.a[SP].wrapping_sub(4);
+        self.regs.a[SP] = sp;
+        self.write32(sp, value);
+    }
+
+    fn pop16(&mut self) -> Word {
+        let oldsp = self.regs.a[SP];
+        self.regs.a[SP] = oldsp.wrapping_add(2);
+        self.read16(oldsp)
+    }
+
+    fn pop32(&mut self) -> Long {
+        let oldsp = self.regs.a[SP];
+        self.regs.a[SP] = oldsp.wrapping_add(4);
+        self.read32(oldsp)
+    }
+
+    fn read_source8(&mut self, src: usize, m: usize) -> Byte {
+        self.read_source8_incpc(src, m, true)
+    }
+    fn read_source8_incpc(&mut self, src: usize, m: usize, incpc: bool) -> Byte {
+        self.refetch = !incpc;
+        let value = self.read_operand8(src, m, incpc);
+        self.refetch = false;
+        value
+    }
+    fn read_operand8(&mut self, src: usize, m: usize, incpc: bool) -> Byte {
+        match src {
+            0 => {  // move.l Dm, xx
+                self.regs.d[m] as u8
+            },
+            2 => {  // move.b (Am), xx
+                let adr = self.regs.a[m];
+                self.read8(adr)
+            },
+            3 => {  // move.b (Am)+, xx
+                let adr = self.regs.a[m];
+                if incpc { self.regs.a[m] = adr.wrapping_add(byte_step(m)); }
+                self.read8(adr)
+            },
+            4 => {  // move.b -(Am), xx
+                self.cycles += 2;
+                let adr = self.regs.a[m].wrapping_sub(byte_step(m));
+                if incpc { self.regs.a[m] = adr; }
+                self.read8(adr)
+            },
+            5 => {  // move.b (123, Am), xx
+                let ofs = self.fetch16(self.regs.pc) as SWord;
+                if incpc { self.regs.pc += 2; }
+                self.read8((self.regs.a[m] as SLong + ofs as SLong) as Adr)
+            },
+            6 => {  // Memory Indirect Pre-indexed: move.b xx, (123, An, Dx)
+                let adr = self.indexed_adr(self.regs.a[m], incpc);
+                self.read8(adr)
+            },
+            7 => {  // Misc.
+                match m {
+                    0 => {  // move.b $XXXX.w, xx
+                        let adr = self.fetch16(self.regs.pc) as SWord as SLong as Adr;
+                        if incpc { self.regs.pc += 2; }
+                        self.read8(adr)
+                    },
+                    1 => {  // move.b $XXXXXXXX.l, xx
+                        let adr = self.fetch32(self.regs.pc);
+                        if incpc { self.regs.pc += 4; }
+                        self.read8(adr)
+                    },
+                    2 => {  // move.b (123, PC), xx
+                        let pc = self.regs.pc;
+                        let ofs = self.fetch16(pc) as SWord;
+                        if incpc { self.regs.pc += 2; }
+                        self.read8((pc as SLong).wrapping_add(ofs as SLong) as Adr)
+                    },
+                    3 => {  // move.b (123, PC, Dx), xx
+                        let pc = self.regs.pc;
+                        let adr = self.indexed_adr(pc, incpc);
+                        self.read8(adr)
+                    },
+                    4 if incpc => {  // move.b #$XXXX, xx
+                        let value = self.fetch16(self.regs.pc);
+                        self.regs.pc += 2;
+                        (value & 0xff) as u8
+                    },
+                    _ => {
+                        self.unimplemented_mode(7, m);
+                        0
+                    },
+                }
+            },
+            _ => {
+                self.unimplemented_mode(src, m);
+                0
+            },
+        }
+    }
+
+    fn read_source16(&mut self, src: usize, m: usize) -> Word {
+        self.read_source16_incpc(src, m, true)
+    }
+    fn read_source16_incpc(&mut self, src: usize, m: usize, incpc: bool) -> Word {
+        self.refetch = !incpc;
+        let value = self.read_operand16(src, m, incpc);
+        self.refetch = false;
+        value
+    }
+    fn read_operand16(&mut self, src: usize, m: usize, incpc: bool) -> Word {
+        match src {
+            0 => {  // move.w Dm, xx
+                self.regs.d[m] as u16
+            },
+            1 => {  // move.w Am, xx
+                self.regs.a[m] as u16
+            },
+            2 => {  // move.w (Am), xx
+                let adr = self.regs.a[m];
+                self.read16(adr)
+            },
+            3 => {  // move.w (Am)+, xx
+                let adr = self.regs.a[m];
+                if incpc { self.regs.a[m] = adr.wrapping_add(2); }
+                self.read16(adr)
+            },
+            4 => {  // move.w -(Am), xx
+                self.cycles += 2;
+                let adr = self.regs.a[m].wrapping_sub(2);
+                if incpc { self.regs.a[m] = adr; }
+                self.read16(adr)
+            },
+            5 => {  // move.w (123, Am), xx
+                let ofs = self.fetch16(self.regs.pc) as SWord;
+                if incpc { self.regs.pc += 2; }
+                self.read16((self.regs.a[m] as SLong + ofs as SLong) as Adr)
+            },
+            6 => {  // Memory Indirect Pre-indexed: move.w xx, (123, An, Dx)
+                let adr = self.indexed_adr(self.regs.a[m], incpc);
+                self.read16(adr)
+            },
+            7 => {  // Misc.
+                match m {
+                    0 => {  // move.w $XXXX.w, xx
+                        let adr = self.fetch16(self.regs.pc) as SWord as SLong as Adr;
+                        if incpc { self.regs.pc += 2; }
+                        self.read16(adr)
+                    },
+                    1 => {  // move.w $XXXXXXXX.l, xx
+                        let adr = self.fetch32(self.regs.pc);
+                        if incpc { self.regs.pc += 4; }
+                        self.read16(adr)
+                    },
+                    2 => {  // move.w (123, PC), xx
+                        let pc = self.regs.pc;
+                        let ofs = self.fetch16(pc) as SWord;
+                        if incpc { self.regs.pc += 2; }
+                        self.read16((pc as SLong).wrapping_add(ofs as SLong) as Adr)
+                    },
+                    3 => {  // move.w (123, PC, Dx), xx
+                        let pc = self.regs.pc;
+                        let adr = self.indexed_adr(pc, incpc);
+                        self.read16(adr)
+                    },
+                    4 => {  // move.w #$XXXX, xx
+                        if incpc {
+                            let value = self.fetch16(self.regs.pc);
+                            self.regs.pc += 2;
+                            value
+                        } else {
+                            self.regs.sr
+                        }
+                    },
+                    _ => {
+                        self.unimplemented_mode(7, m);
+                        0
+                    },
+                }
+            },
+            _ => {
+                self.unimplemented_mode(src, m);
+                0
+            },
+        }
+    }
+
+    fn read_source32(&mut self, src: usize, m: usize) -> Long {
+        self.read_source32_incpc(src, m, true)
+    }
+    fn read_source32_incpc(&mut self, src: usize, m: usize, incpc: bool) -> Long {
+        self.refetch = !incpc;
+        let value = self.read_operand32(src, m, incpc);
+        self.refetch = false;
+        value
+    }
+    fn read_operand32(&mut self, src: usize, m: usize, incpc: bool) -> Long {
+        match src {
+            0 => {  // move.l Dm, xx
+                self.regs.d[m]
+            },
+            1 => {  // move.l Am, xx
+                self.regs.a[m]
+            },
+            2 => {  // move.l (Am), xx
+                let adr = self.regs.a[m];
+                self.read32(adr)
+            },
+            3 => {  // move.l (Am)+, xx
+                let adr = self.regs.a[m];
+                if incpc { self.regs.a[m] = adr.wrapping_add(4); }
+                self.read32(adr)
+            },
+            4 => {  // move.l -(Am), xx
+                self.cycles += 2;
+                let adr = self.regs.a[m].wrapping_sub(4);
+                if incpc { self.regs.a[m] = adr; }
+                self.read32(adr)
+            },
+            5 => {  // move.l (123, Am), xx
+                let ofs = self.fetch16(self.regs.pc) as SWord;
+                if incpc { self.regs.pc += 2; }
+                self.read32((self.regs.a[m] as SLong + ofs as SLong) as Adr)
+            },
+            6 => {  // Memory Indirect Pre-indexed: move.l xx, (123, An, Dx)
+                let adr = self.indexed_adr(self.regs.a[m], incpc);
+                self.read32(adr)
+            },
+            7 => {  // Misc.
+                match m {
+                    0 => {  // move.l $XXXX.w, xx
+                        let adr = self.fetch16(self.regs.pc) as SWord as SLong as Adr;
+                        if incpc { self.regs.pc += 2; }
+                        self.read32(adr)
+                    },
+                    1 => {  // move.l $XXXXXXXX.l, xx
+                        let adr = self.fetch32(self.regs.pc);
+                        if incpc { self.regs.pc += 4; }
+                        self.read32(adr)
+                    },
+                    2 => {  // move.l (123, PC), xx
+                        let pc = self.regs.pc;
+                        let ofs = self.fetch16(pc) as SWord;
+                        if incpc { self.regs.pc += 2; }
+                        self.read32((pc as SLong).wrapping_add(ofs as SLong) as Adr)
+                    },
+                    3 => {  // move.l (123, PC, Dx), xx
+                        let pc = self.regs.pc;
+                        let adr = self.indexed_adr(pc, incpc);
+                        self.read32(adr)
+                    },
+                    4 if incpc => {  // move.l #$XXXX, xx
+                        let value = self.fetch32(self.regs.pc);
+                        self.regs.pc += 4;
+                        value
+                    },
+                    _ => {
+                        self.unimplemented_mode(7, m);
+                        0
+                    },
+                }
+            },
+            _ => {
+                self.unimplemented_mode(src, m);
+                0
+            },
+        }
+    }
+
+    fn read_source(&mut self, bits: u32, src: usize, m: usize, incpc: bool) -> Long {
+        match bits {
+            8 => self.read_source8_incpc(src, m, incpc) as Long,
+            16 => self.read_source16_incpc(src, m, incpc) as Long,
+            _ => self.read_source32_incpc(src, m, incpc),
+        }
+    }
+
+    fn write_destination(&mut self, bits: u32, dst: usize, n: usize, value: Long) {
+        match bits {
+            8 => self.write_destination8(dst, n, value as Byte),
+            16 => self.write_destination16(dst, n, value as Word),
+            _ => self.write_destination32(dst, n, value),
+        }
+    }
+
+    fn write_destination8(&mut self, dst: usize, n: usize, value: Byte) {
+        match dst {
+            0 => {
+                self.regs.d[n] = replace_byte(self.regs.d[n], value);
+            },
+            2 => {  // move.b xx, (An)
+                self.write8(self.regs.a[n], value);
+            },
+            3 => {
+                let adr = self.regs.a[n];
+                self.write8(adr, value);
+                self.regs.a[n] = adr.wrapping_add(byte_step(n));
+            },
+            4 => {
+                let adr = self.regs.a[n].wrapping_sub(byte_step(n));
+                self.regs.a[n] = adr;
+                self.write8(adr, value);
+            },
+            5 => {  // move.b xx, (123, An)
+                let ofs = self.fetch16(self.regs.pc) as SWord;
+                self.regs.pc += 2;
+                self.write8((self.regs.a[n] as SLong + ofs as SLong) as Adr, value);
+            },
+            6 => {  // Memory Indirect Pre-indexed: move.b xx, (123, An, Dx)
+                let adr = self.indexed_adr(self.regs.a[n], true);
+                self.write8(adr, value);
+            },
+            7 => {
+                match n {
+                    0 => {
+                        let d = self.fetch16(self.regs.pc) as SWord as SLong as Adr;
+                        self.regs.pc += 2;
+                        self.write8(d, value);
+                    },
+                    1 => {
+                        let d = self.fetch32(self.regs.pc);
+                        self.regs.pc += 4;
+                        self.write8(d, value);
+                    },
+                    _ => {
+                        self.unimplemented_mode(7, n);
+                    },
+                }
+            },
+            _ => {
+                self.unimplemented_mode(dst, n);
+            },
+        }
+    }
+
+    fn write_destination16(&mut self, dst: usize, n: usize, value: Word) {
+        match dst {
+            0 => {
+                self.regs.d[n] = replace_word(self.regs.d[n], value);
+            },
+            1 => {
+                self.regs.a[n] = replace_word(self.regs.a[n], value);
+            },
+            2 => {  // move.w xx, (An)
+                self.write16(self.regs.a[n], value);
+            },
+            3 => {
+                let adr = self.regs.a[n];
+                self.write16(adr, value);
+                self.regs.a[n] = adr.wrapping_add(2);
+            },
+            4 => {
+                let adr = self.regs.a[n].wrapping_sub(2);
+                self.regs.a[n] = adr;
+                self.write16(adr, value);
+            },
+            5 => {  // move.w xx, (123, An)
+                let ofs = self.fetch16(self.regs.pc) as SWord;
+                self.regs.pc += 2;
+                self.write16((self.regs.a[n] as SLong + ofs as SLong) as Adr, value);
+            },
+            6 => {  // Memory Indirect Pre-indexed: move.w xx, (123, An, Dx)
+                let adr = self.indexed_adr(self.regs.a[n], true);
+                self.write16(adr, value);
+            },
+            7 => {
+                match n {
+                    0 => {
+                        let d = self.fetch16(self.regs.pc) as SWord as SLong as Adr;
+                        self.regs.pc += 2;
+                        self.write16(d, value);
+                    },
+                    1 => {
+                        let d = self.fetch32(self.regs.pc);
+                        self.regs.pc += 4;
+                        self.write16(d, value);
+                    },
+                    4 => {
+                        self.set_sr(value);
+                    },
+                    _ => {
+                        self.unimplemented_mode(7, n);
+                    },
+                }
+            },
+            _ => {
+                self.unimplemented_mode(dst, n);
+            },
+        }
+    }
+
+    fn write_destination32(&mut self, dst: usize, n: usize, value: Long) {
+        match dst {
+            0 => {
+                self.regs.d[n] = value;
+            },
+            1 => {
+                self.regs.a[n] = value;
+            },
+            2 => {  // move.l xx, (An)
+                self.write32(self.regs.a[n], value);
+            },
+            3 => {
+                let adr = self.regs.a[n];
+                self.write32(adr, value);
+                self.regs.a[n] = adr.wrapping_add(4);
+            },
+            4 => {
+                let adr = self.regs.a[n].wrapping_sub(4);
+                self.regs.a[n] = adr;
+                self.write32(adr, value);
+            },
+            5 => {  // move.l xx, (123, An)
+                let ofs = self.fetch16(self.regs.pc) as SWord;
+                self.regs.pc += 2;
+                self.write32((self.regs.a[n] as SLong + ofs as SLong) as Adr, value);
+            },
+            6 => {  // Memory Indirect Pre-indexed: move.l xx, (123, An, Dx)
+                let adr = self.indexed_adr(self.regs.a[n], true);
+                self.write32(adr, value);
+            },
+            7 => {
+                match n {
+                    0 => {
+                        let d = self.fetch16(self.regs.pc) as SWord as SLong as Adr;
+                        self.regs.pc += 2;
+                        self.write32(d, value);
+                    },
+                    1 => {
+                        let d = self.fetch32(self.regs.pc);
+                        self.regs.pc += 4;
+                        self.write32(d, value);
+                    },
+                    _ => {
+                        self.unimplemented_mode(7, n);
+                    },
+                }
+            },
+            _ => {
+                self.unimplemented_mode(dst, n);
+            },
+        }
+    }
+
+    // Effective address for control addressing modes: (An), (d16,An), (d8,An,Xn), abs.w, abs.l and PC relative.
+    fn control_adr(&mut self, mode: usize, n: usize) -> Adr {
+        match mode {
+            2 => {  // (An)
+                self.regs.a[n]
+            },
+            5 => {  // (d16, An)
+                let ofs = self.fetch16(self.regs.pc) as SWord;
+                self.regs.pc += 2;
+                (self.regs.a[n] as SLong).wrapping_add(ofs as SLong) as Adr
+            },
+            6 => {  // (d8, An, Xn)
+                self.indexed_adr(self.regs.a[n], true)
+            },
+            7 => {
+                match n {
+                    0 => {  // $xxxx.w
+                        let adr = self.fetch16(self.regs.pc) as SWord as SLong as Adr;
+                        self.regs.pc += 2;
+                        adr
+                    },
+                    1 => {  // $xxxxxxxx.l
+                        let adr = self.fetch32(self.regs.pc);
+                        self.regs.pc += 4;
+                        adr
+                    },
+                    2 => {  // (d16, PC)
+                        let pc = self.regs.pc;
+                        let ofs = self.fetch16(pc) as SWord;
+                        self.regs.pc += 2;
+                        (pc as SLong).wrapping_add(ofs as SLong) as Adr
+                    },
+                    3 => {  // (d8, PC, Xn)
+                        let pc = self.regs.pc;
+                        self.indexed_adr(pc, true)
+                    },
+                    _ => {
+                        self.unimplemented_mode(7, n);
+                        0
+                    },
+                }
+            },
+            _ => {
+                self.unimplemented_mode(mode, n);
+                0
+            },
+        }
+    }
+
+    fn unimplemented_mode(&mut self, mode: usize, reg: usize) {
+        if self.error.is_none() {
+            self.error = Some(CpuError::UnimplementedMode { pc: self.inst_adr, mode, reg });
+        }
+    }
+
+    fn indexed_adr(&mut self, base: Adr, incpc: bool) -> Adr {
+        let extension = self.fetch16(self.regs.pc);
+        if incpc { self.regs.pc += 2; }
+        self.cycles += 2;  // Adding the index.
+        let (disp, da, xr, xl) = brief_extension(extension);
+        let x = if da {self.regs.a[xr]} else {self.regs.d[xr]};
+        let mut index = if xl {x as SLong} else {x as SWord as SLong};
+        if self.model >= CpuModel::M68020 {
+            index <<= (extension >> 9) & 3;
+            if (extension & 0x0100) != 0 {
+                return self.full_extension(base, extension, index, incpc);
+            }
+        }
+        (base as SLong).wrapping_add(disp).wrapping_add(index) as Adr
+    }
+
+    // 68020 full extension word: base and index suppression, base and outer
+    // displacements, and memory indirection before or after adding the index.
+    fn full_extension(&mut self, base: Adr, extension: Word, index: SLong, incpc: bool) -> Adr {
+        let mut pc = if incpc { self.regs.pc } else { self.regs.pc + 2 };
+        let base = if (extension & 0x0080) != 0 { 0 } else { base as SLong };
+        let index = if (extension & 0x0040) != 0 { 0 } else { index };
+        let bd = self.displacement((extension >> 4) & 3, &mut pc);
+        let adr = if (extension & 7) == 0 {
+            base.wrapping_add(bd).wrapping_add(index) as Adr
+        } else {
+            let od = self.displacement(extension & 3, &mut pc);
+            if (extension & 4) == 0 {  // Pre-indexed
+                let ptr = self.read32(base.wrapping_add(bd).wrapping_add(index) as Adr);
+                (ptr as SLong).wrapping_add(od) as Adr
+            } else {  // Post-indexed
+                let ptr = self.read32(base.wrapping_add(bd) as Adr);
+                (ptr as SLong).wrapping_add(index).wrapping_add(od) as Adr
+            }
+        };
+        if incpc { self.regs.pc = pc; }
+        adr
+    }
+
+    // Null (0, 1), word (2) or long (3) displacement of a full extension word.
+    fn displacement(&mut self, size: Word, pc: &mut Adr) -> SLong {
+        match size {
+            2 => {
+                let d = self.fetch16(*pc) as SWord as SLong;
+                *pc += 2;
+                d
+            },
+            3 => {
+                let d = self.fetch32(*pc) as SLong;
+                *pc += 4;
+                d
+            },
+            _ => 0,
+        }
+    }
+
+    fn set_cmp_sr(&mut self, borrow: bool, eq: bool, overflow: bool, neg: bool) {
+        let mut ccr = 0;
+        if borrow   { ccr |= FLAG_C; }
+        if eq       { ccr |= FLAG_Z; }
+        if overflow { ccr |= FLAG_V; }
+        if neg      { ccr |= FLAG_N; }
+        self.regs.sr = (self.regs.sr & !(FLAG_N | FLAG_Z | FLAG_V | FLAG_C)) | ccr;
+    }
+
+    fn set_and_sr(&mut self, zero: bool, neg: bool) {
+        let mut ccr = 0;
+        if zero { ccr |= FLAG_Z; }
+        if neg  { ccr |= FLAG_N; }
+        self.regs.sr = (self.regs.sr & !(FLAG_N | FLAG_Z | FLAG_V | FLAG_C)) | ccr;
+    }
+
+    // Z is only cleared, so that multi-precision BCD chains keep it sticky.
+    fn set_bcd_sr(&mut self, res: Byte, carry: bool, overflow: bool) {
+        let mut sr = self.regs.sr & !(FLAG_X | FLAG_N | FLAG_V | FLAG_C);
+        if carry           { sr |= FLAG_X | FLAG_C; }
+        if overflow        { sr |= FLAG_V; }
+        if (res & 0x80) != 0 { sr |= FLAG_N; }
+        if res != 0        { sr &= !FLAG_Z; }
+        self.regs.sr = sr;
+    }
+
+    fn set_tst_sr(&mut self, zero: bool, neg: bool) {
+        let mut ccr = 0;
+        if zero { ccr |= FLAG_Z; }
+        if neg  { ccr |= FLAG_N; }
+        self.regs.sr = (self.regs.sr & !(FLAG_V | FLAG_C | FLAG_Z | FLAG_N)) | ccr;
+    }
+
+    // Each bus cycle takes 4 clocks plus the wait states of the device, a long access two of them.
+    fn bus_cycles(&mut self, adr: Adr, n: usize) {
+        self.cycles += n * (4 + self.bus.wait_states(adr));
+    }
+
+    fn read8(&mut self, adr: Adr) -> Byte {
+        let adr = adr & self.adr_mask;
+        self.bus_cycles(adr, 1);
+        let value = self.bus.read8(adr);
+        self.check_bus_error(adr, false);
+        self.observe(adr, 1, value as Long, false);
+        value
+    }
+
+    fn read16(&mut self, adr: Adr) -> Word {
+        let adr = adr & self.adr_mask;
+        let value = self.raw_read16(adr);
+        if (adr & 1) == 0 || self.model >= CpuModel::M68020 {  // Not aborted by an address error.
+            self.observe(adr, 2, value as Long, false);
+        }
+        value
+    }
+
+    // Word read without notifying the memory observer, for instruction fetches.
+    fn raw_read16(&mut self, adr: Adr) -> Word {
+        let adr = adr & self.adr_mask;
+        self.bus_cycles(adr, 1);
+        if self.misaligned(adr, false) {
+            return 0;
+        }
+        let value = self.bus.read16(adr);
+        self.check_bus_error(adr, false);
+        value
+    }
+
+    fn read32(&mut self, adr: Adr) -> Long {
+        let adr = adr & self.adr_mask;
+        self.bus_cycles(adr, 2);
+        if self.misaligned(adr, false) {
+            return 0;
+        }
+        let value = self.bus.read32(adr);
+        self.check_bus_error(adr, false);
+        self.observe(adr, 4, value, false);
+        value
+    }
+
+    // Once a fault is pending, the rest of the instruction must not modify memory.
+    fn write8(&mut self, adr: Adr, value: Byte) {
+        let adr = adr & self.adr_mask;
+        self.bus_cycles(adr, 1);
+        if self.fault.is_none() && self.error.is_none() {
+            self.bus.write8(adr, value);
+            self.check_bus_error(adr, true);
+            self.invalidate_decoded(adr, 1);
+            self.observe(adr, 1, value as Long, true);
+        }
+    }
+
+    fn write16(&mut self, adr: Adr, value: Word) {
+        let adr = adr & self.adr_mask;
+        self.bus_cycles(adr, 1);
+        if !self.misaligned(adr, true) && self.fault.is_none() && self.error.is_none() {
+            self.bus.write16(adr, value);
+            self.check_bus_error(adr, true);
+            self.invalidate_decoded(adr, 2);
+            self.observe(adr, 2, value as Long, true);
+        }
+    }
+
+    fn write32(&mut self, adr: Adr, value: Long) {
+        let adr = adr & self.adr_mask;
+        self.bus_cycles(adr, 2);
+        if !self.misaligned(adr, true) && self.fault.is_none() && self.error.is_none() {
+            self.bus.write32(adr, value);
+            self.check_bus_error(adr, true);
+            self.invalidate_decoded(adr, 4);
+            self.observe(adr, 4, value, true);
+        }
+    }
+
+    fn observe(&mut self, adr: Adr, size: usize, value: Long, write: bool) {
+        if let Some(observer) = self.mem_observer.as_mut() {
+            observer(&MemoryAccess { pc: self.inst_adr, adr, size, value, write });
+        }
+    }
+
+    // Read from the instruction stream, served from the prefetch queue when enabled.
+    fn fetch16(&mut self, adr: Adr) -> Word {
+        if let Some(value) = self.replayed(adr) {
+            if !self.refetch {
+                self.bus_cycles(adr & self.adr_mask, 1);
+                self.fetch_end = adr.wrapping_add(2);
+            }
+            return value;
+        }
+        if self.refetch {
+            // A read-modify-write operand is addressed twice; its extension words are
+            // fetched, faulting and charged, only by the write pass that advances PC.
+            let value = self.queued(adr).unwrap_or_else(|| self.bus.read16(adr & self.adr_mask));
+            self.bus.take_bus_error();
+            self.record(adr, value);
+            return value;
+        }
+        if !self.prefetch {
+            self.fetch_end = adr.wrapping_add(2);
+            let value = self.raw_read16(adr);
+            self.record(adr, value);
+            return value;
+        }
+        self.fetch_end = adr.wrapping_add(2);
+        let value = match self.queued(adr) {
+            Some(value) => value,
+            None => self.raw_read16(adr),
+        };
+        let (adr2, adr4) = (adr.wrapping_add(2), adr.wrapping_add(4));
+        let next = self.queued(adr2).or_else(|| self.read_ahead(adr2));
+        let ahead = self.read_ahead(adr4);
+        self.queue = [next.map(|w| (adr2, w)), ahead.map(|w| (adr4, w))];
+        value
+    }
+
+    // Word `adr` of the instruction being executed, if it comes from the decode cache.
+    fn replayed(&self, adr: Adr) -> Option<Word> {
+        let decoded = &self.decode_cache[self.replay?];
+        let k = (adr.wrapping_sub(self.inst_adr) >> 1) as usize;
+        // Not if the instruction has just overwritten itself.
+        if decoded.adr == self.inst_adr & self.adr_mask && (adr & 1) == 0 && k < decoded.len {
+            Some(decoded.words[k])
+        } else {
+            None
+        }
+    }
+
+    // Words fetched by an instruction on its first execution, which must follow one another.
+    fn record(&mut self, adr: Adr, value: Word) {
+        let decoded = match self.recording.as_mut() {
+            Some(decoded) => decoded,
+            None => return,
+        };
+        let k = (adr.wrapping_sub(self.inst_adr) >> 1) as usize;
+        if k == decoded.len && k < DECODED_WORDS {
+            decoded.words[k] = value;
+            decoded.len += 1;
+        } else if k > decoded.len {
+            self.recording = None;
+        }
+    }
+
+    // A write to `size` bytes at `adr` drops the cached instructions it overlaps.
+    fn invalidate_decoded(&mut self, adr: Adr, size: Adr) {
+        #[cfg(feature = "jit")]
+        if let Some(jit) = self.jit.as_mut() {
+            jit.invalidate(adr, size);
+        }
+        if self.decode_cache.is_empty() {
+            return;
+        }
+        let first = adr & !1;
+        let words = (adr.wrapping_add(size - 1) & !1).wrapping_sub(first) / 2 + 1;
+        for w in 0..words {
+            let a = first.wrapping_add(2 * w);
+            for k in 0..DECODED_WORDS {
+                let start = a.wrapping_sub(2 * k as Adr);
+                let entry = &mut self.decode_cache[decode_index(start)];
+                if entry.adr == start && k < entry.len {
+                    entry.adr = NO_ENTRY;
+                }
+            }
+            if let Some(decoded) = self.recording.as_ref() {
+                if a.wrapping_sub(decoded.adr) < 2 * DECODED_WORDS as Adr {
+                    self.recording = None;
+                }
+            }
+        }
+    }
+
+    fn fetch32(&mut self, adr: Adr) -> Long {
+        let hi = self.fetch16(adr) as Long;
+        (hi << 16) | self.fetch16(adr.wrapping_add(2)) as Long
+    }
+
+    fn queued(&self, adr: Adr) -> Option<Word> {
+        self.queue.iter().flatten().find(|(a, _)| *a == adr).map(|(_, w)| *w)
+    }
+
+    // A prefetch beyond the end of valid memory is dropped: it only faults if executed.
+    fn read_ahead(&mut self, adr: Adr) -> Option<Word> {
+        let adr = adr & self.adr_mask;
+        self.bus_cycles(adr, 1);
+        let value = self.bus.read16(adr);
+        if self.bus.take_bus_error() { None } else { Some(value) }
+    }
+
+    fn check_bus_error(&mut self, adr: Adr, write: bool) {
+        if self.bus.take_bus_error() && self.fault.is_none() && self.error.is_none() {
+            self.fault = Some(Fault {vector: BUS_ERROR_VECTOR, adr, write, fetch: false});
+        }
+    }
+
+    // Word and long accesses must be even; record an address error otherwise.
+    // From the 68020 on, only instruction fetches have to be word aligned.
+    fn misaligned(&mut self, adr: Adr, write: bool) -> bool {
+        if (adr & 1) == 0 || self.model >= CpuModel::M68020 {
+            return false;
+        }
+        if self.fault.is_none() {
+            self.fault = Some(Fault {vector: ADDRESS_ERROR_VECTOR, adr, write, fetch: false});
+        }
+        true
+    }
+}
+
+#[test]
+fn test_shift_byte() {
+    let b: Byte = 0xa5;  // 0b10100101
+    assert_eq!(0x28 as Byte, b << 3);
+    assert_eq!(0x29 as Byte, b >> 2);
+}
+
+fn decode_index(adr: Adr) -> usize {
+    (adr >> 1) as usize & (DECODE_CACHE_SIZE - 1)
+}
+
+fn size_mask(bits: u32) -> Long {
+    ((1u64 << bits) - 1) as Long
+}
+
+// (An)+ and -(An) step of a byte access: A7 moves by 2 to stay word aligned.
+fn byte_step(n: usize) -> Adr {
+    if n == SP { 2 } else { 1 }
+}
+
+fn replace_byte(x: Long, b: Byte) -> Long {
+    (x & 0xffffff00) | (b as Long)
+}
+
+#[test]
+fn test_replace_byte() {
+    assert_eq!(0x123456ab, replace_byte(0x12345678, 0xab));
+}
+
+fn replace_word(x: Long, w: Word) -> Long {
+    (x & 0xffff0000) | (w as Long)
+}
+
+#[test]
+fn test_replace_word() {
+    assert_eq!(0x1234abcd, replace_word(0x12345678, 0xabcd));
+}
@@ -0,0 +1,1032 @@
+// Instruction handlers, one per decoded opcode, called through the dispatch table.
+use super::*;
+
+pub(super) type Handler<BusT> = fn(&mut Cpu<BusT>, Adr, Word);
+
+impl<BusT: BusTrait> Cpu<BusT> {
+    pub(super) const HANDLERS: [Handler<BusT>; OPCODE_COUNT] = Self::handlers();
+
+    // Opcodes without an entry are decoded but not implemented yet.
+    const fn handlers() -> [Handler<BusT>; OPCODE_COUNT] {
+        let mut h = [Self::op_unimplemented as Handler<BusT>; OPCODE_COUNT];
+        h[Opcode::Nop as usize] = Self::op_nop;
+        h[Opcode::MoveByte as usize] = Self::op_move_byte;
+        h[Opcode::MoveWord as usize] = Self::op_move_word;
+        h[Opcode::MoveLong as usize] = Self::op_move_long;
+        h[Opcode::MoveaWord as usize] = Self::op_movea_word;
+        h[Opcode::MoveaLong as usize] = Self::op_movea_long;
+        h[Opcode::Moveq as usize] = Self::op_moveq;
+        h[Opcode::Movep as usize] = Self::op_movep;
+        h[Opcode::MovemFrom as usize] = Self::op_movem;
+        h[Opcode::MovemTo as usize] = Self::op_movem;
+        h[Opcode::MoveToSrIm as usize] = Self::op_move_to_sr_im;
+        h[Opcode::MoveToSr as usize] = Self::op_move_to_sr;
+        h[Opcode::MoveFromSr as usize] = Self::op_move_from_sr;
+        h[Opcode::MoveFromCcr as usize] = Self::op_move_from_ccr;
+        h[Opcode::Movec as usize] = Self::op_movec;
+        h[Opcode::Moves as usize] = Self::op_moves;
+        h[Opcode::MoveToUsp as usize] = Self::op_move_to_usp;
+        h[Opcode::MoveFromUsp as usize] = Self::op_move_from_usp;
+        h[Opcode::LeaDirect as usize] = Self::op_lea_direct;
+        h[Opcode::LeaOffset as usize] = Self::op_lea_offset;
+        h[Opcode::LeaOffsetD as usize] = Self::op_lea_offset_d;
+        h[Opcode::LeaOffsetPc as usize] = Self::op_lea_offset_pc;
+        h[Opcode::Pea as usize] = Self::op_pea;
+        h[Opcode::ClrByte as usize] = Self::op_clr_byte;
+        h[Opcode::ClrWord as usize] = Self::op_clr_word;
+        h[Opcode::ClrLong as usize] = Self::op_clr_long;
+        h[Opcode::Swap as usize] = Self::op_swap;
+        h[Opcode::ExgD as usize] = Self::op_exg_d;
+        h[Opcode::ExgA as usize] = Self::op_exg_a;
+        h[Opcode::ExgDA as usize] = Self::op_exg_d_a;
+        h[Opcode::CmpByte as usize] = Self::op_cmp_byte;
+        h[Opcode::CmpWord as usize] = Self::op_cmp_word;
+        h[Opcode::CmpLong as usize] = Self::op_cmp_long;
+        h[Opcode::CmpiByte as usize] = Self::op_cmpi;
+        h[Opcode::CmpiWord as usize] = Self::op_cmpi;
+        h[Opcode::CmpiLong as usize] = Self::op_cmpi;
+        h[Opcode::CmpaLong as usize] = Self::op_cmpa_long;
+        h[Opcode::CmpmByte as usize] = Self::op_cmpm_byte;
+        h[Opcode::Chk as usize] = Self::op_chk;
+        h[Opcode::TstByte as usize] = Self::op_tst_byte;
+        h[Opcode::TstWord as usize] = Self::op_tst_word;
+        h[Opcode::TstLong as usize] = Self::op_tst_long;
+        h[Opcode::Btst as usize] = Self::op_btst;
+        h[Opcode::BtstIm as usize] = Self::op_btst_im;
+        h[Opcode::Bchg as usize] = Self::op_bchg;
+        h[Opcode::BchgIm as usize] = Self::op_bchg_im;
+        h[Opcode::Bclr as usize] = Self::op_bclr;
+        h[Opcode::BclrIm as usize] = Self::op_bclr_im;
+        h[Opcode::Bset as usize] = Self::op_bset;
+        h[Opcode::BsetIm as usize] = Self::op_bset_im;
+        h[Opcode::AddByte as usize] = Self::op_add;
+        h[Opcode::AddWord as usize] = Self::op_add;
+        h[Opcode::AddLong as usize] = Self::op_add;
+        h[Opcode::AddEaByte as usize] = Self::op_add;
+        h[Opcode::AddEaWord as usize] = Self::op_add;
+        h[Opcode::AddEaLong as usize] = Self::op_add;
+        h[Opcode::AddiByte as usize] = Self::op_addi;
+        h[Opcode::AddiWord as usize] = Self::op_addi;
+        h[Opcode::AddiLong as usize] = Self::op_addi;
+        h[Opcode::AddaLong as usize] = Self::op_adda_long;
+        h[Opcode::AddqByte as usize] = Self::op_addq;
+        h[Opcode::AddqWord as usize] = Self::op_addq;
+        h[Opcode::AddqLong as usize] = Self::op_addq;
+        h[Opcode::SubByte as usize] = Self::op_sub;
+        h[Opcode::SubWord as usize] = Self::op_sub;
+        h[Opcode::SubLong as usize] = Self::op_sub;
+        h[Opcode::SubEaByte as usize] = Self::op_sub;
+        h[Opcode::SubEaWord as usize] = Self::op_sub;
+        h[Opcode::SubEaLong as usize] = Self::op_sub;
+        h[Opcode::SubiByte as usize] = Self::op_subi;
+        h[Opcode::SubiWord as usize] = Self::op_subi;
+        h[Opcode::SubiLong as usize] = Self::op_subi;
+        h[Opcode::SubaLong as usize] = Self::op_suba_long;
+        h[Opcode::SubqByte as usize] = Self::op_subq;
+        h[Opcode::SubqWord as usize] = Self::op_subq;
+        h[Opcode::SubqLong as usize] = Self::op_subq;
+        h[Opcode::MuluWord as usize] = Self::op_mulu_word;
+        h[Opcode::MulLong as usize] = Self::op_mul_long;
+        h[Opcode::DivLong as usize] = Self::op_div_long;
+        h[Opcode::Abcd as usize] = Self::op_abcd;
+        h[Opcode::Sbcd as usize] = Self::op_sbcd;
+        h[Opcode::Nbcd as usize] = Self::op_nbcd;
+        h[Opcode::AndByte as usize] = Self::op_and;
+        h[Opcode::AndWord as usize] = Self::op_and;
+        h[Opcode::AndLong as usize] = Self::op_and;
+        h[Opcode::AndEaByte as usize] = Self::op_and;
+        h[Opcode::AndEaWord as usize] = Self::op_and;
+        h[Opcode::AndEaLong as usize] = Self::op_and;
+        h[Opcode::AndiByte as usize] = Self::op_andi;
+        h[Opcode::AndiWord as usize] = Self::op_andi;
+        h[Opcode::AndiLong as usize] = Self::op_andi;
+        h[Opcode::AndiCcr as usize] = Self::op_andi_ccr;
+        h[Opcode::AndiSr as usize] = Self::op_andi_sr;
+        h[Opcode::OrByte as usize] = Self::op_or;
+        h[Opcode::OrWord as usize] = Self::op_or;
+        h[Opcode::OrLong as usize] = Self::op_or;
+        h[Opcode::OrEaByte as usize] = Self::op_or;
+        h[Opcode::OrEaWord as usize] = Self::op_or;
+        h[Opcode::OrEaLong as usize] = Self::op_or;
+        h[Opcode::OriByte as usize] = Self::op_ori;
+        h[Opcode::OriWord as usize] = Self::op_ori;
+        h[Opcode::OriLong as usize] = Self::op_ori;
+        h[Opcode::OriCcr as usize] = Self::op_ori_ccr;
+        h[Opcode::OriSr as usize] = Self::op_ori_sr;
+        h[Opcode::EorByte as usize] = Self::op_eor_byte;
+        h[Opcode::EoriByte as usize] = Self::op_eori;
+        h[Opcode::EoriWord as usize] = Self::op_eori;
+        h[Opcode::EoriLong as usize] = Self::op_eori;
+        h[Opcode::EoriCcr as usize] = Self::op_eori_ccr;
+        h[Opcode::EoriSr as usize] = Self::op_eori_sr;
+        h[Opcode::NotByte as usize] = Self::op_not_byte;
+        h[Opcode::NotWord as usize] = Self::op_not_word;
+        h[Opcode::NotLong as usize] = Self::op_not_long;
+        h[Opcode::AslImByte as usize] = Self::op_shift_reg;
+        h[Opcode::AslImWord as usize] = Self::op_shift_reg;
+        h[Opcode::AslImLong as usize] = Self::op_shift_reg;
+        h[Opcode::AslByte as usize] = Self::op_shift_reg;
+        h[Opcode::AslWord as usize] = Self::op_shift_reg;
+        h[Opcode::AslLong as usize] = Self::op_shift_reg;
+        h[Opcode::AsrImByte as usize] = Self::op_shift_reg;
+        h[Opcode::AsrImWord as usize] = Self::op_shift_reg;
+        h[Opcode::AsrImLong as usize] = Self::op_shift_reg;
+        h[Opcode::AsrByte as usize] = Self::op_shift_reg;
+        h[Opcode::AsrWord as usize] = Self::op_shift_reg;
+        h[Opcode::AsrLong as usize] = Self::op_shift_reg;
+        h[Opcode::LslImByte as usize] = Self::op_shift_reg;
+        h[Opcode::LslImWord as usize] = Self::op_shift_reg;
+        h[Opcode::LslImLong as usize] = Self::op_shift_reg;
+        h[Opcode::LslByte as usize] = Self::op_shift_reg;
+        h[Opcode::LslWord as usize] = Self::op_shift_reg;
+        h[Opcode::LslLong as usize] = Self::op_shift_reg;
+        h[Opcode::LsrImByte as usize] = Self::op_shift_reg;
+        h[Opcode::LsrImWord as usize] = Self::op_shift_reg;
+        h[Opcode::LsrImLong as usize] = Self::op_shift_reg;
+        h[Opcode::LsrByte as usize] = Self::op_shift_reg;
+        h[Opcode::LsrWord as usize] = Self::op_shift_reg;
+        h[Opcode::LsrLong as usize] = Self::op_shift_reg;
+        h[Opcode::RoxlImByte as usize] = Self::op_shift_reg;
+        h[Opcode::RoxlImWord as usize] = Self::op_shift_reg;
+        h[Opcode::RoxlImLong as usize] = Self::op_shift_reg;
+        h[Opcode::RoxlByte as usize] = Self::op_shift_reg;
+        h[Opcode::RoxlWord as usize] = Self::op_shift_reg;
+        h[Opcode::RoxlLong as usize] = Self::op_shift_reg;
+        h[Opcode::RoxrImByte as usize] = Self::op_shift_reg;
+        h[Opcode::RoxrImWord as usize] = Self::op_shift_reg;
+        h[Opcode::RoxrImLong as usize] = Self::op_shift_reg;
+        h[Opcode::RoxrByte as usize] = Self::op_shift_reg;
+        h[Opcode::RoxrWord as usize] = Self::op_shift_reg;
+        h[Opcode::RoxrLong as usize] = Self::op_shift_reg;
+        h[Opcode::RolImByte as usize] = Self::op_shift_reg;
+        h[Opcode::RolImWord as usize] = Self::op_shift_reg;
+        h[Opcode::RolImLong as usize] = Self::op_shift_reg;
+        h[Opcode::RolByte as usize] = Self::op_shift_reg;
+        h[Opcode::RolWord as usize] = Self::op_shift_reg;
+        h[Opcode::RolLong as usize] = Self::op_shift_reg;
+        h[Opcode::RorImByte as usize] = Self::op_shift_reg;
+        h[Opcode::RorImWord as usize] = Self::op_shift_reg;
+        h[Opcode::RorImLong as usize] = Self::op_shift_reg;
+        h[Opcode::RorByte as usize] = Self::op_shift_reg;
+        h[Opcode::RorWord as usize] = Self::op_shift_reg;
+        h[Opcode::RorLong as usize] = Self::op_shift_reg;
+        h[Opcode::Bftst as usize] = Self::op_bitfield;
+        h[Opcode::Bfextu as usize] = Self::op_bitfield;
+        h[Opcode::Bfchg as usize] = Self::op_bitfield;
+        h[Opcode::Bfexts as usize] = Self::op_bitfield;
+        h[Opcode::Bfclr as usize] = Self::op_bitfield;
+        h[Opcode::Bfffo as usize] = Self::op_bitfield;
+        h[Opcode::Bfset as usize] = Self::op_bitfield;
+        h[Opcode::Bfins as usize] = Self::op_bitfield;
+        h[Opcode::Cas as usize] = Self::op_cas;
+        h[Opcode::Cas2 as usize] = Self::op_cas2;
+        h[Opcode::AslMem as usize] = Self::op_shift_mem;
+        h[Opcode::AsrMem as usize] = Self::op_shift_mem;
+        h[Opcode::LslMem as usize] = Self::op_shift_mem;
+        h[Opcode::LsrMem as usize] = Self::op_shift_mem;
+        h[Opcode::RoxlMem as usize] = Self::op_shift_mem;
+        h[Opcode::RoxrMem as usize] = Self::op_shift_mem;
+        h[Opcode::RolMem as usize] = Self::op_shift_mem;
+        h[Opcode::RorMem as usize] = Self::op_shift_mem;
+        h[Opcode::ExtWord as usize] = Self::op_ext_word;
+        h[Opcode::Bra as usize] = Self::op_bra;
+        h[Opcode::Bhi as usize] = Self::op_bhi;
+        h[Opcode::Bls as usize] = Self::op_bls;
+        h[Opcode::Bcc as usize] = Self::op_bcc;
+        h[Opcode::Bcs as usize] = Self::op_bcs;
+        h[Opcode::Bne as usize] = Self::op_bne;
+        h[Opcode::Beq as usize] = Self::op_beq;
+        h[Opcode::Bvc as usize] = Self::op_bvc;
+        h[Opcode::Bvs as usize] = Self::op_bvs;
+        h[Opcode::Bpl as usize] = Self::op_bpl;
+        h[Opcode::Bmi as usize] = Self::op_bmi;
+        h[Opcode::Bge as usize] = Self::op_bge;
+        h[Opcode::Blt as usize] = Self::op_blt;
+        h[Opcode::Bgt as usize] = Self::op_bgt;
+        h[Opcode::Ble as usize] = Self::op_ble;
+        h[Opcode::Dbcc as usize] = Self::op_dbcc;
+        h[Opcode::Scc as usize] = Self::op_scc;
+        h[Opcode::Bsr as usize] = Self::op_bsr;
+        h[Opcode::Jsr as usize] = Self::op_jsr;
+        h[Opcode::Jmp as usize] = Self::op_jmp;
+        h[Opcode::Rts as usize] = Self::op_rts;
+        h[Opcode::Rtd as usize] = Self::op_rtd;
+        h[Opcode::Stop as usize] = Self::op_stop;
+        h[Opcode::Rte as usize] = Self::op_rte;
+        h[Opcode::Trap as usize] = Self::op_trap;
+        h[Opcode::Reset as usize] = Self::op_reset;
+        h[Opcode::LineA as usize] = Self::op_line_a;
+        h[Opcode::LineF as usize] = Self::op_line_f;
+        h[Opcode::Illegal as usize] = Self::op_illegal;
+        h[Opcode::Unknown as usize] = Self::op_unknown;
+        h
+    }
+
+    fn op_nop(&mut self, _startadr: Adr, _op: Word) {
+        // Waste cycles.
+    }
+
+    fn op_move_byte(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let dt = ((op >> 6) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source8(st, si);
+        self.write_destination8(dt, di, src);
+
+        let mut ccr = 0;
+        if src == 0          { ccr |= FLAG_Z; }
+        if (src & 0x80) != 0 { ccr |= FLAG_N; }
+        self.regs.sr = (self.regs.sr & !(FLAG_C | FLAG_V | FLAG_Z | FLAG_N)) | ccr;
+    }
+
+    fn op_move_word(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let dt = ((op >> 6) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source16(st, si);
+        self.write_destination16(dt, di, src);
+
+        let mut ccr = 0;
+        if src == 0            { ccr |= FLAG_Z; }
+        if (src & 0x8000) != 0 { ccr |= FLAG_N; }
+        self.regs.sr = (self.regs.sr & !(FLAG_C | FLAG_V | FLAG_Z | FLAG_N)) | ccr;
+    }
+
+    fn op_move_long(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let dt = ((op >> 6) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source32(st, si);
+        self.write_destination32(dt, di, src);
+
+        let mut ccr = 0;
+        if src == 0                { ccr |= FLAG_Z; }
+        if (src & 0x80000000) != 0 { ccr |= FLAG_N; }
+        self.regs.sr = (self.regs.sr & !(FLAG_C | FLAG_V | FLAG_Z | FLAG_N)) | ccr;
+    }
+
+    fn op_movea_word(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source16(st, si) as SWord;
+        self.regs.a[di] = src as SLong as Long;  // Sign-extended, flags unaffected.
+    }
+
+    fn op_movea_long(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        self.regs.a[di] = self.read_source32(st, si);
+    }
+
+    fn op_moveq(&mut self, _startadr: Adr, op: Word) {
+        let v = op & 0xff;
+        let di = (op >> 9) & 7;
+        let src = if v < 0x80 { v as i16 } else { -256 + v as i16 };
+        self.regs.d[di as usize] = (src as i32) as u32;
+
+        let mut ccr = 0;
+        if src == 0 { ccr |= FLAG_Z; }
+        if src < 0  { ccr |= FLAG_N; }
+        self.regs.sr = (self.regs.sr & !(FLAG_C | FLAG_V | FLAG_Z | FLAG_N)) | ccr;
+    }
+
+    fn op_movep(&mut self, _startadr: Adr, op: Word) {
+        let ai = (op & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let ofs = self.fetch16(self.regs.pc) as SWord;
+        self.regs.pc += 2;
+        let adr = (self.regs.a[ai] as SLong).wrapping_add(ofs as SLong) as Adr;
+        let n = if (op & 0x0040) != 0 { 4 } else { 2 };
+        if (op & 0x0080) != 0 {  // Register to memory.
+            let val = self.regs.d[di];
+            for i in 0..n {
+                self.write8(adr.wrapping_add(i * 2), (val >> ((n - 1 - i) * 8)) as Byte);
+            }
+        } else {  // Memory to register.
+            let mut val = 0;
+            for i in 0..n {
+                val = (val << 8) | self.read8(adr.wrapping_add(i * 2)) as Long;
+            }
+            self.regs.d[di] = if n == 4 { val } else { replace_word(self.regs.d[di], val as Word) };
+        }
+    }
+
+    fn op_movem(&mut self, _startadr: Adr, op: Word) {
+        self.movem(op);
+    }
+
+    fn op_move_to_sr_im(&mut self, startadr: Adr, _op: Word) {
+        if self.check_supervisor(startadr) {
+            let sr = self.fetch16(self.regs.pc);
+            self.regs.pc += 2;
+            self.set_sr(sr);
+        }
+    }
+
+    fn op_move_to_sr(&mut self, startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        if self.check_supervisor(startadr) {
+            let sr = self.read_source16(st, si);
+            self.set_sr(sr);
+        }
+    }
+
+    fn op_move_from_sr(&mut self, startadr: Adr, op: Word) {
+        // Privileged from the 68010 on, so that a virtual machine monitor can trap it.
+        if self.model == CpuModel::M68000 || self.check_supervisor(startadr) {
+            let di = (op & 7) as usize;
+            let dt = ((op >> 3) & 7) as usize;
+            self.write_destination16(dt, di, self.regs.sr);
+        }
+    }
+
+    fn op_move_from_ccr(&mut self, startadr: Adr, op: Word) {
+        if self.check_model(startadr, CpuModel::M68010) {
+            let di = (op & 7) as usize;
+            let dt = ((op >> 3) & 7) as usize;
+            self.write_destination16(dt, di, self.regs.sr & 0xff);
+        }
+    }
+
+    fn op_movec(&mut self, startadr: Adr, op: Word) {
+        if self.check_model(startadr, CpuModel::M68010) && self.check_supervisor(startadr) {
+            self.movec(startadr, op);
+        }
+    }
+
+    fn op_moves(&mut self, startadr: Adr, op: Word) {
+        if self.check_model(startadr, CpuModel::M68010) && self.check_supervisor(startadr) {
+            self.moves(startadr, op);
+        }
+    }
+
+    fn op_move_to_usp(&mut self, startadr: Adr, op: Word) {
+        if self.check_supervisor(startadr) {
+            let si = (op & 7) as usize;
+            self.regs.usp = self.regs.a[si];
+        }
+    }
+
+    fn op_move_from_usp(&mut self, startadr: Adr, op: Word) {
+        if self.check_supervisor(startadr) {
+            let di = (op & 7) as usize;
+            self.regs.a[di] = self.regs.usp;
+        }
+    }
+
+    fn op_lea_direct(&mut self, _startadr: Adr, op: Word) {
+        let di = ((op >> 9) & 7) as usize;
+        let value = self.fetch32(self.regs.pc);
+        self.regs.pc += 4;
+        self.regs.a[di] = value;
+    }
+
+    fn op_lea_offset(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let ofs = self.fetch16(self.regs.pc) as SWord;
+        self.regs.pc += 2;
+        self.regs.a[di] = (self.regs.a[si] as SLong + ofs as SLong) as Long;
+    }
+
+    fn op_lea_offset_d(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        self.regs.a[di] = self.indexed_adr(self.regs.a[si], true);
+    }
+
+    fn op_lea_offset_pc(&mut self, _startadr: Adr, op: Word) {
+        let di = ((op >> 9) & 7) as usize;
+        let pc = self.regs.pc;  // Displacement is relative to the extension word.
+        let ofs = self.fetch16(pc) as SWord;
+        self.regs.pc += 2;
+        self.regs.a[di] = (pc as SLong + ofs as SLong) as Long;
+    }
+
+    fn op_pea(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let adr = self.control_adr(st, si);
+        self.push32(adr);
+    }
+
+    fn op_clr_byte(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        self.write_destination8(dt, di, 0);
+    }
+
+    fn op_clr_word(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        self.write_destination16(dt, di, 0);
+    }
+
+    fn op_clr_long(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        self.write_destination32(dt, di, 0);
+    }
+
+    fn op_swap(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let v = self.regs.d[di];
+        self.regs.d[di] = v.rotate_right(16);
+    }
+
+    fn op_exg_d(&mut self, _startadr: Adr, op: Word) {
+        let yi = (op & 7) as usize;
+        let xi = ((op >> 9) & 7) as usize;
+        self.regs.d.swap(xi, yi);
+    }
+
+    fn op_exg_a(&mut self, _startadr: Adr, op: Word) {
+        let yi = (op & 7) as usize;
+        let xi = ((op >> 9) & 7) as usize;
+        self.regs.a.swap(xi, yi);
+    }
+
+    fn op_exg_d_a(&mut self, _startadr: Adr, op: Word) {
+        let yi = (op & 7) as usize;
+        let xi = ((op >> 9) & 7) as usize;
+        std::mem::swap(&mut self.regs.d[xi], &mut self.regs.a[yi]);
+    }
+
+    fn op_cmp_byte(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source8(st, si);
+        let dst = self.read_source8(0, di);
+        let res = dst.wrapping_sub(src);
+        self.set_cmp_sr(dst < src, dst == src, (((src ^ dst) & (res ^ dst)) & 0x80) != 0, (res & 0x80) != 0);
+    }
+
+    fn op_cmp_word(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source16(st, si);
+        let dst = self.read_source16(0, di);
+        let res = dst.wrapping_sub(src);
+        self.set_cmp_sr(dst < src, dst == src, (((src ^ dst) & (res ^ dst)) & 0x8000) != 0, (res & 0x8000) != 0);
+    }
+
+    fn op_cmp_long(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source32(st, si);
+        let dst = self.read_source32(0, di);
+        let res = dst.wrapping_sub(src);
+        self.set_cmp_sr(dst < src, dst == src, (((src ^ dst) & (res ^ dst)) & 0x80000000) != 0, (res & 0x80000000) != 0);
+    }
+
+    fn op_cmpi(&mut self, startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        if !self.check_68020_ea(startadr, dt, di) {
+            return;
+        }
+        let bits = 8 << ((op >> 6) & 3);
+        let src = self.read_imm(bits);
+        let dst = self.read_source(bits, dt, di, true);
+        self.cmp_val(bits, src, dst);
+    }
+
+    fn op_cmpa_long(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source32(st, si);
+        let dst = self.read_source32(1, di);
+        let res = dst.wrapping_sub(src);
+        self.set_cmp_sr(dst < src, dst == src, (((src ^ dst) & (res ^ dst)) & 0x80000000) != 0, (res & 0x80000000) != 0);
+    }
+
+    fn op_cmpm_byte(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let dst = self.read8(self.regs.a[di]);
+        let src = self.read8(self.regs.a[si]);
+        self.regs.a[si] = self.regs.a[si].wrapping_add(byte_step(si));
+        self.regs.a[di] = self.regs.a[di].wrapping_add(byte_step(di));
+        let res = dst.wrapping_sub(src);
+        self.set_cmp_sr(dst < src, dst == src, (((src ^ dst) & (res ^ dst)) & 0x80) != 0, (res & 0x80) != 0);
+    }
+
+    fn op_chk(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let bound = self.read_source16(st, si) as SWord;
+        let val = self.regs.d[di] as SWord;
+        let mut sr = self.regs.sr & !(FLAG_Z | FLAG_V | FLAG_C);
+        if val == 0 { sr |= FLAG_Z; }
+        self.regs.sr = sr;
+        if val < 0 || val > bound {
+            self.regs.sr = if val < 0 { sr | FLAG_N } else { sr & !FLAG_N };
+            self.exception(CHK_VECTOR);
+        }
+    }
+
+    fn op_tst_byte(&mut self, startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        if !self.check_68020_ea(startadr, st, si) {
+            return;
+        }
+        let val = self.read_source8(st, si) as SByte;
+        self.set_tst_sr(val == 0, val < 0);
+    }
+
+    fn op_tst_word(&mut self, startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        if !self.check_68020_ea(startadr, st, si) {
+            return;
+        }
+        let val = self.read_source16(st, si) as SWord;
+        self.set_tst_sr(val == 0, val < 0);
+    }
+
+    fn op_tst_long(&mut self, startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        if !self.check_68020_ea(startadr, st, si) {
+            return;
+        }
+        let val = self.read_source32(st, si) as SLong;
+        self.set_tst_sr(val == 0, val < 0);
+    }
+
+    fn op_btst(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let si = ((op >> 9) & 7) as usize;
+        self.bit_op(dt, di, self.regs.d[si], None);
+    }
+
+    fn op_btst_im(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let bit = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        self.bit_op(dt, di, bit as Long, None);
+    }
+
+    fn op_bchg(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let si = ((op >> 9) & 7) as usize;
+        self.bit_op(dt, di, self.regs.d[si], Some(|val, mask| val ^ mask));
+    }
+
+    fn op_bchg_im(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let bit = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        self.bit_op(dt, di, bit as Long, Some(|val, mask| val ^ mask));
+    }
+
+    fn op_bclr(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let si = ((op >> 9) & 7) as usize;
+        self.bit_op(dt, di, self.regs.d[si], Some(|val, mask| val & !mask));
+    }
+
+    fn op_bclr_im(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let bit = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        self.bit_op(dt, di, bit as Long, Some(|val, mask| val & !mask));
+    }
+
+    fn op_bset(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let si = ((op >> 9) & 7) as usize;
+        self.bit_op(dt, di, self.regs.d[si], Some(|val, mask| val | mask));
+    }
+
+    fn op_bset_im(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let bit = self.fetch16(self.regs.pc);
+        self.regs.pc += 2;
+        self.bit_op(dt, di, bit as Long, Some(|val, mask| val | mask));
+    }
+
+    fn op_add(&mut self, _startadr: Adr, op: Word) {
+        self.alu(op, Self::add_val);
+    }
+
+    fn op_addi(&mut self, _startadr: Adr, op: Word) {
+        self.alu_imm(op, Self::add_val);
+    }
+
+    fn op_adda_long(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source32(st, si);
+        self.regs.a[di] = self.regs.a[di].wrapping_add(src);
+    }
+
+    fn op_addq(&mut self, _startadr: Adr, op: Word) {
+        self.quick(op, Self::add_val);
+    }
+
+    fn op_sub(&mut self, _startadr: Adr, op: Word) {
+        self.alu(op, Self::sub_val);
+    }
+
+    fn op_subi(&mut self, _startadr: Adr, op: Word) {
+        self.alu_imm(op, Self::sub_val);
+    }
+
+    fn op_suba_long(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source32(st, si);
+        self.regs.a[di] = self.regs.a[di].wrapping_sub(src);
+    }
+
+    fn op_subq(&mut self, _startadr: Adr, op: Word) {
+        self.quick(op, Self::sub_val);
+    }
+
+    fn op_mulu_word(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let di = ((op >> 9) & 7) as usize;
+        let src = self.read_source16(st, si);
+        self.cycles += 34 + 2 * src.count_ones() as usize;
+        self.regs.d[di] = ((self.regs.d[di] as Word) as Long).wrapping_mul(src as Long);
+    }
+
+    fn op_mul_long(&mut self, startadr: Adr, op: Word) {
+        if self.check_model(startadr, CpuModel::M68020) {
+            self.mul_long(op);
+        }
+    }
+
+    fn op_div_long(&mut self, startadr: Adr, op: Word) {
+        if self.check_model(startadr, CpuModel::M68020) {
+            self.div_long(op);
+        }
+    }
+
+    fn op_abcd(&mut self, _startadr: Adr, op: Word) {
+        self.bcd_op(op, bcd_add);
+    }
+
+    fn op_sbcd(&mut self, _startadr: Adr, op: Word) {
+        self.bcd_op(op, bcd_sub);
+    }
+
+    fn op_nbcd(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let dst = self.read_source8_incpc(dt, di, false);
+        let (res, borrow, overflow) = bcd_sub(0, dst, (self.regs.sr & FLAG_X) != 0);
+        self.write_destination8(dt, di, res);
+        self.set_bcd_sr(res, borrow, overflow);
+    }
+
+    fn op_and(&mut self, _startadr: Adr, op: Word) {
+        self.alu(op, Self::and_val);
+    }
+
+    fn op_andi(&mut self, _startadr: Adr, op: Word) {
+        self.alu_imm(op, Self::and_val);
+    }
+
+    fn op_andi_ccr(&mut self, _startadr: Adr, _op: Word) {
+        let v = self.read_imm(8) as Word;
+        self.regs.sr &= 0xff00 | v;
+    }
+
+    fn op_andi_sr(&mut self, startadr: Adr, _op: Word) {
+        if self.check_supervisor(startadr) {
+            let v = self.read_imm(16) as Word;
+            self.set_sr(self.regs.sr & v);
+        }
+    }
+
+    fn op_or(&mut self, _startadr: Adr, op: Word) {
+        self.alu(op, Self::or_val);
+    }
+
+    fn op_ori(&mut self, _startadr: Adr, op: Word) {
+        self.alu_imm(op, Self::or_val);
+    }
+
+    fn op_ori_ccr(&mut self, _startadr: Adr, _op: Word) {
+        let v = self.read_imm(8) as Word;
+        self.regs.sr |= v & 0x1f;
+    }
+
+    fn op_ori_sr(&mut self, startadr: Adr, _op: Word) {
+        if self.check_supervisor(startadr) {
+            let v = self.read_imm(16) as Word;
+            self.set_sr(self.regs.sr | v);
+        }
+    }
+
+    fn op_eor_byte(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let si = ((op >> 9) & 7) as usize;
+        let dst = self.read_source8_incpc(dt, di, false);
+        self.write_destination8(dt, di, (self.regs.d[si] as Byte) ^ dst);
+        // TODO: Update all flags
+    }
+
+    fn op_eori(&mut self, _startadr: Adr, op: Word) {
+        self.alu_imm(op, Self::eor_val);
+    }
+
+    fn op_eori_ccr(&mut self, _startadr: Adr, _op: Word) {
+        let v = self.read_imm(8) as Word;
+        self.regs.sr ^= v & 0x1f;
+    }
+
+    fn op_eori_sr(&mut self, startadr: Adr, _op: Word) {
+        if self.check_supervisor(startadr) {
+            let v = self.read_imm(16) as Word;
+            self.set_sr(self.regs.sr ^ v);
+        }
+    }
+
+    fn op_not_byte(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let res = !self.read_source8_incpc(dt, di, false);
+        self.write_destination8(dt, di, res);
+        self.set_and_sr(res == 0, (res & 0x80) != 0);
+    }
+
+    fn op_not_word(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let res = !self.read_source16_incpc(dt, di, false);
+        self.write_destination16(dt, di, res);
+        self.set_and_sr(res == 0, (res & 0x8000) != 0);
+    }
+
+    fn op_not_long(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let res = !self.read_source32_incpc(dt, di, false);
+        self.write_destination32(dt, di, res);
+        self.set_and_sr(res == 0, (res & 0x80000000) != 0);
+    }
+
+    fn op_shift_reg(&mut self, _startadr: Adr, op: Word) {
+        self.shift_reg(op);
+    }
+
+    fn op_bitfield(&mut self, startadr: Adr, op: Word) {
+        if self.check_model(startadr, CpuModel::M68020) {
+            self.bitfield(op);
+        }
+    }
+
+    fn op_cas(&mut self, startadr: Adr, op: Word) {
+        if self.check_model(startadr, CpuModel::M68020) {
+            self.cas(op);
+        }
+    }
+
+    fn op_cas2(&mut self, startadr: Adr, op: Word) {
+        if self.check_model(startadr, CpuModel::M68020) {
+            self.cas2(op);
+        }
+    }
+
+    fn op_shift_mem(&mut self, _startadr: Adr, op: Word) {
+        self.shift_mem(op);
+    }
+
+    fn op_ext_word(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let src = self.regs.d[di];
+        self.regs.d[di] = replace_word(src, src as SByte as SWord as Word);
+    }
+
+    fn op_bra(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, true);
+    }
+
+    fn op_bhi(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & (FLAG_C | FLAG_Z)) == 0);
+    }
+
+    fn op_bls(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & (FLAG_C | FLAG_Z)) != 0);
+    }
+
+    fn op_bcc(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & FLAG_C) == 0);
+    }
+
+    fn op_bcs(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & FLAG_C) != 0);
+    }
+
+    fn op_bne(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & FLAG_Z) == 0);
+    }
+
+    fn op_beq(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & FLAG_Z) != 0);
+    }
+
+    fn op_bvc(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & FLAG_V) == 0);
+    }
+
+    fn op_bvs(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & FLAG_V) != 0);
+    }
+
+    fn op_bpl(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & FLAG_N) == 0);
+    }
+
+    fn op_bmi(&mut self, _startadr: Adr, op: Word) {
+        self.bcond(op, (self.regs.sr & FLAG_N) != 0);
+    }
+
+    fn op_bge(&mut self, _startadr: Adr, op: Word) {
+        let nv = self.regs.sr & (FLAG_N | FLAG_V);
+        self.bcond(op, nv == 0 || nv == (FLAG_N | FLAG_V));
+    }
+
+    fn op_blt(&mut self, _startadr: Adr, op: Word) {
+        let nv = self.regs.sr & (FLAG_N | FLAG_V);
+        self.bcond(op, nv == FLAG_N || nv == FLAG_V);
+    }
+
+    fn op_bgt(&mut self, _startadr: Adr, op: Word) {
+        let nv = self.regs.sr & (FLAG_N | FLAG_V);
+        self.bcond(op, (self.regs.sr & FLAG_Z) == 0 && (nv == 0 || nv == (FLAG_N | FLAG_V)));
+    }
+
+    fn op_ble(&mut self, _startadr: Adr, op: Word) {
+        let nv = self.regs.sr & (FLAG_N | FLAG_V);
+        self.bcond(op, (self.regs.sr & FLAG_Z) != 0 || nv == FLAG_N || nv == FLAG_V);
+    }
+
+    fn op_dbcc(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let ofs = self.fetch16(self.regs.pc) as SWord;
+        if self.check_cond(op >> 8) {
+            self.cycles += 4;
+            self.regs.pc += 2;
+        } else {
+            let l = self.regs.d[si];
+            let w = (l as u16).wrapping_sub(1);
+            self.regs.d[si] = replace_word(l, w);
+            self.cycles += if w != 0xffff { 2 } else { 6 };
+            if w != 0xffff && ofs == -4 && self.model != CpuModel::M68000 {
+                // Loop mode: a DBcc looping over a one-word instruction is not refetched.
+                self.cycles -= 8;
+            }
+            self.regs.pc = if w != 0xffff { (self.regs.pc as SLong).wrapping_add(ofs as SLong) as Adr } else { self.regs.pc + 2 }
+        }
+    }
+
+    fn op_scc(&mut self, _startadr: Adr, op: Word) {
+        let di = (op & 7) as usize;
+        let dt = ((op >> 3) & 7) as usize;
+        let value = if self.check_cond(op >> 8) { 0xff } else { 0x00 };
+        self.write_destination8(dt, di, value);
+    }
+
+    fn op_bsr(&mut self, startadr: Adr, op: Word) {
+        let (ofs, sz) = get_branch_offset(op, &mut self.bus, self.regs.pc);
+        self.regs.pc += sz;
+        self.fetch_end = self.regs.pc;
+        self.push32(self.regs.pc);
+        self.regs.pc = ((startadr + 2) as i32 + ofs) as u32;
+    }
+
+    fn op_jsr(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        let adr = self.control_adr(st, si);
+        self.push32(self.regs.pc);
+        self.regs.pc = adr;
+    }
+
+    fn op_jmp(&mut self, _startadr: Adr, op: Word) {
+        let si = (op & 7) as usize;
+        let st = ((op >> 3) & 7) as usize;
+        self.regs.pc = self.control_adr(st, si);
+    }
+
+    fn op_rts(&mut self, _startadr: Adr, _op: Word) {
+        self.regs.pc = self.pop32();
+    }
+
+    fn op_rtd(&mut self, startadr: Adr, _op: Word) {
+        if self.check_model(startadr, CpuModel::M68010) {
+            let disp = self.fetch16(self.regs.pc) as SWord;
+            self.regs.pc = self.pop32();
+            self.regs.a[SP] = (self.regs.a[SP] as SLong).wrapping_add(disp as SLong) as Adr;
+        }
+    }
+
+    fn op_stop(&mut self, startadr: Adr, _op: Word) {
+        if self.check_supervisor(startadr) {
+            let sr = self.fetch16(self.regs.pc);
+            self.regs.pc += 2;
+            self.set_sr(sr);
+            self.stopped = true;
+        }
+    }
+
+    fn op_rte(&mut self, startadr: Adr, _op: Word) {
+        if self.check_supervisor(startadr) {
+            // Pop the whole frame from the supervisor stack before SR may switch A7.
+            let sr = self.pop16();
+            self.regs.pc = self.pop32();
+            if self.model != CpuModel::M68000 {
+                let format = self.pop16() >> 12;
+                match self.frame_extra(format) {
+                    Some(extra) => self.regs.a[SP] = self.regs.a[SP].wrapping_add(extra),
+                    None => {
+                        self.regs.a[SP] = self.regs.a[SP].wrapping_sub(8);
+                        self.regs.pc = startadr;
+                        self.exception(FORMAT_ERROR_VECTOR);
+                        return;
+                    },
+                }
+            }
+            self.set_sr(sr);
+        }
+    }
+
+    fn op_trap(&mut self, _startadr: Adr, op: Word) {
+        let no = op & 0x000f;
+        self.exception(TRAP_VECTOR_START + no);
+    }
+
+    fn op_reset(&mut self, startadr: Adr, _op: Word) {
+        if self.check_supervisor(startadr) {
+            self.bus.reset_devices();
+            self.cycles += 128;  // The reset line is held for 124 clocks.
+        }
+    }
+
+    fn op_line_a(&mut self, startadr: Adr, _op: Word) {
+        // Stacked PC points to the opcode itself, so the handler can decode it.
+        self.regs.pc = startadr;
+        self.exception(LINE_A_VECTOR);
+    }
+
+    fn op_line_f(&mut self, startadr: Adr, op: Word) {
+        let handled = match self.line_f_hook.take() {
+            Some(mut hook) => {
+                let handled = hook(self, op);
+                self.line_f_hook = Some(hook);
+                handled
+            },
+            None => false,
+        };
+        if !handled {
+            self.regs.pc = startadr;
+            self.exception(LINE_F_VECTOR);
+        }
+    }
+
+    fn op_illegal(&mut self, startadr: Adr, _op: Word) {
+        self.regs.pc = startadr;
+        self.exception(ILLEGAL_INSTRUCTION_VECTOR);
+    }
+
+    // Only unassigned patterns are illegal; the rest are instructions the table lacks.
+    fn op_unknown(&mut self, startadr: Adr, op: Word) {
+        if unlisted_instruction(op, self.model >= CpuModel::M68020) {
+            self.op_unimplemented(startadr, op);
+        } else {
+            self.op_illegal(startadr, op);
+        }
+    }
+
+    fn op_unimplemented(&mut self, startadr: Adr, op: Word) {
+        self.error = Some(CpuError::UnknownOpcode { pc: startadr, op });
+    }
+}
@@ -0,0 +1,1466 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Write};
+
+use super::bus_trait::MemoryView;
+use super::opcode::{ea_allowed, Opcode, INST, EA_ALL, EA_ALTERABLE, EA_CONTROL, EA_DATA, EA_DATA_ALTERABLE};
+use super::registers::Registers;
+use super::util::{conv07to18, brief_extension};
+use super::types::{Byte, Word, Long, SByte, SWord, SLong, Adr};
+
+const BCC_NAMES: [&str; 16] = ["bra", "bsr", "bhi", "bls", "bcc", "bcs", "bne", "beq", "bvc", "bvs", "bpl", "bmi", "bge", "blt", "bgt", "ble"];
+const DBCC_NAMES: [&str; 16] = ["dbt", "dbra", "dbhi", "dbls", "dbcc", "dbcs", "dbne", "dbeq", "dbvc", "dbvs", "dbpl", "dbmi", "dbge", "dblt", "dbgt", "dble"];
+const SCC_NAMES: [&str; 16] = ["st", "sf", "shi", "sls", "scc", "scs", "sne", "seq", "svc", "svs", "spl", "smi", "sge", "slt", "sgt", "sle"];
+const TRAPCC_NAMES: [&str; 16] = ["trapt", "trapf", "traphi", "trapls", "trapcc", "trapcs", "trapne", "trapeq", "trapvc", "trapvs", "trappl", "trapmi", "trapge", "traplt", "trapgt", "traple"];
+
+// Register names, so that operands are written without formatting them.
+const DATA_REG_NAMES: [&str; 8] = ["D0", "D1", "D2", "D3", "D4", "D5", "D6", "D7"];
+const ADDR_REG_NAMES: [&str; 8] = ["A0", "A1", "A2", "A3", "A4", "A5", "A6", "A7"];
+
+const MOVE_NAMES: [&str; 8] = ["move", "movea", "move", "move", "move", "move", "move", "move"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Size {
+    Byte,
+    Word,
+    Long,
+}
+
+impl Size {
+    // From the usual 2-bit size field (00: byte, 01: word, 10: long).
+    fn from_bits(bits: Word) -> Size {
+        match bits & 3 {
+            0 => Size::Byte,
+            1 => Size::Word,
+            _ => Size::Long,
+        }
+    }
+
+    fn suffix(self) -> char {
+        match self {
+            Size::Byte => 'b',
+            Size::Word => 'w',
+            Size::Long => 'l',
+        }
+    }
+}
+
+// Base register of an indexed operand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    Addr(Word),
+    Pc(Adr),  // Address of the extension word, which the displacement is relative to.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexReg {
+    pub addr: bool,  // An instead of Dn.
+    pub reg: Word,
+    pub long: bool,
+    pub scale: Word,
+}
+
+// Memory indirection of the 68020 full extension format: ([bd,base,Xn],od) or ([bd,base],Xn,od).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryIndirect {
+    pub postindexed: bool,
+    pub outer: Option<SLong>,  // None when null.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Indexed {
+    pub base: Option<Base>,  // None when suppressed.
+    pub index: Option<IndexReg>,
+    pub disp: SLong,
+    pub indirect: Option<MemoryIndirect>,
+}
+
+// Bit field offset or width: a constant or a data register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitSpec {
+    Immediate(Word),
+    Register(Word),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    DataReg(Word),
+    AddrReg(Word),
+    Indirect(Word),  // (An)
+    PostInc(Word),  // (An)+
+    PreDec(Word),  // -(An)
+    Displacement(SWord, Word),  // (d16,An)
+    Indexed(Indexed),  // (d8,An,Xn) and the 68020 extension forms
+    AbsShort(Word),
+    AbsLong(Long),
+    PcDisplacement(SWord, Adr),  // (d16,PC) and the address it refers to
+    Immediate(Long),
+    SignedImmediate(SLong),
+    Quick(Word),  // Small constants of addq/subq and shift counts.
+    Target(Adr),  // Branch destination.
+    RegList(Word),  // movem: bit 0 is D0, bit 15 is A7.
+    Sr,
+    Ccr,
+    Usp,
+    ControlReg(Word),
+    DataIndirect(Word),  // (Dn), cas2 only.
+    Pair(Box<Operand>, Box<Operand>),  // Dh:Dl and the cas2 operand pairs.
+    BitField(Box<Operand>, BitSpec, BitSpec),
+    Constant(Word),  // dc.w
+    ByteConstant(Byte),  // dc.b
+    Invalid(usize, Word),  // Addressing mode the instruction doesn't allow.
+    Unreadable,  // dc.w of a word that couldn't be read.
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub mnemonic: &'static str,
+    pub size: Option<Size>,
+    pub operands: Vec<Operand>,
+    pub length: usize,  // In bytes, including extension words.
+    pub unreadable: bool,  // Some extension words couldn't be read and were taken as 0.
+}
+
+impl Instruction {
+    fn new(mnemonic: &'static str, size: Option<Size>, length: u32, operands: Vec<Operand>) -> Self {
+        Self { mnemonic, size, operands, length: length as usize, unreadable: false }
+    }
+
+    // Where a branch, jmp or jsr goes, when it is known without running the code.
+    pub fn branch_target(&self) -> Option<Adr> {
+        if self.unreadable {
+            return None;
+        }
+        match (self.mnemonic, self.operands.last()) {
+            (_, Some(Operand::Target(adr))) => Some(*adr),
+            ("jmp" | "jsr", Some(Operand::AbsShort(adr))) => Some(*adr as SWord as SLong as Adr),
+            ("jmp" | "jsr", Some(Operand::AbsLong(adr))) => Some(*adr),
+            ("jmp" | "jsr", Some(Operand::PcDisplacement(_, adr))) => Some(*adr),
+            _ => None,
+        }
+    }
+
+    // Comment with the addresses the operands refer to, e.g. "; $ff0124", given the register
+    // values before the instruction for the ones based on address registers.
+    pub fn annotation(&self, regs: Option<&Registers>) -> Option<String> {
+        let mut comment: Option<String> = None;
+        for adr in self.operands.iter().filter_map(|o| o.address(self.size, regs)) {
+            match comment.as_mut() {
+                Some(text) => write!(text, ", ${:x}", adr).unwrap(),
+                None => comment = Some(format!("; ${:x}", adr)),
+            }
+        }
+        comment
+    }
+
+    // Format with `symbol` naming the addresses in branch targets, absolute and PC-relative operands.
+    pub fn format_with(&self, symbol: &dyn Fn(Adr) -> Option<String>) -> String {
+        let mut text = String::new();
+        self.write_with(&mut text, symbol).unwrap();
+        text
+    }
+
+    // Same as `format_with`, appending to `out` instead: with a buffer reused across
+    // instructions and no symbols, nothing is allocated.
+    pub fn write_with(&self, out: &mut dyn Write, symbol: &dyn Fn(Adr) -> Option<String>) -> fmt::Result {
+        out.write_str(self.mnemonic)?;
+        let mut width = self.mnemonic.len();
+        if let Some(size) = self.size {
+            out.write_char('.')?;
+            out.write_char(size.suffix())?;
+            width += 2;
+        }
+        for (i, operand) in self.operands.iter().enumerate() {
+            if i == 0 {
+                for _ in width..7 {
+                    out.write_char(' ')?;
+                }
+                out.write_char(' ')?;
+            } else {
+                out.write_str(", ")?;
+            }
+            operand.write_with(out, symbol)?;
+        }
+        Ok(())
+    }
+
+    // Assembler syntax, or None if the instruction can't be written to assemble to the same bytes.
+    // `relative` names branch and PC-relative destinations, `absolute` absolute addresses.
+    fn format_asm(&self, relative: &dyn Fn(Adr) -> Option<String>, absolute: &dyn Fn(Adr) -> Option<String>) -> Option<String> {
+        let name = match self.size {
+            Some(size) => format!("{}.{}", self.mnemonic, size.suffix()),
+            None if BCC_NAMES.contains(&self.mnemonic) => {
+                format!("{}.{}", self.mnemonic, match self.length { 2 => 's', 4 => 'w', _ => 'l' })
+            },
+            None => self.mnemonic.to_string(),
+        };
+        let operands = self.operands.iter()
+            .map(|o| o.format_asm(relative, absolute))
+            .collect::<Option<Vec<_>>>()?;
+        if operands.is_empty() {
+            Some(format!("\t{}", name))
+        } else {
+            Some(format!("\t{}\t{}", name, operands.join(",")))
+        }
+    }
+}
+
+impl Operand {
+    // Memory address the operand refers to: PC-relative ones are known from the instruction
+    // alone, the others need the register values.
+    pub fn address(&self, size: Option<Size>, regs: Option<&Registers>) -> Option<Adr> {
+        match self {
+            Operand::Indirect(n) | Operand::PostInc(n) => Some(regs?.a[*n as usize]),
+            Operand::PreDec(n) => {
+                let dec = match size {
+                    Some(Size::Long) => 4,
+                    Some(Size::Word) => 2,
+                    _ => if *n == 7 { 2 } else { 1 },  // A7 stays word aligned.
+                };
+                Some(regs?.a[*n as usize].wrapping_sub(dec))
+            },
+            Operand::Displacement(d, n) => Some(regs?.a[*n as usize].wrapping_add(*d as SLong as Adr)),
+            Operand::Indexed(x) => x.address(regs),
+            Operand::PcDisplacement(_, adr) => Some(*adr),
+            Operand::BitField(ea, _, _) => ea.address(size, regs),
+            _ => None,
+        }
+    }
+
+    pub fn format_with(&self, symbol: &dyn Fn(Adr) -> Option<String>) -> String {
+        let mut text = String::new();
+        self.write_with(&mut text, symbol).unwrap();
+        text
+    }
+
+    pub fn write_with(&self, out: &mut dyn Write, symbol: &dyn Fn(Adr) -> Option<String>) -> fmt::Result {
+        match self {
+            Operand::AbsShort(adr) => match symbol(*adr as SWord as SLong as Adr) {
+                Some(name) => write!(out, "{}.w", name),
+                None => self.write_to(out),
+            },
+            Operand::AbsLong(adr) => match symbol(*adr) {
+                Some(name) => write!(out, "{}.l", name),
+                None => self.write_to(out),
+            },
+            Operand::PcDisplacement(_, adr) => match symbol(*adr) {
+                Some(name) => write!(out, "({},PC)", name),
+                None => self.write_to(out),
+            },
+            Operand::Target(adr) => match symbol(*adr) {
+                Some(name) => out.write_str(&name),
+                None => self.write_to(out),
+            },
+            Operand::Pair(a, b) => {
+                a.write_with(out, symbol)?;
+                out.write_char(':')?;
+                b.write_with(out, symbol)
+            },
+            Operand::BitField(ea, offset, width) => {
+                ea.write_with(out, symbol)?;
+                write!(out, "{{{}:{}}}", offset, width)
+            },
+            _ => self.write_to(out),
+        }
+    }
+
+    // Without symbols, as Display.
+    fn write_to(&self, out: &mut dyn Write) -> fmt::Result {
+        match self {
+            Operand::DataReg(n) => out.write_str(DATA_REG_NAMES[*n as usize]),
+            Operand::AddrReg(n) => out.write_str(ADDR_REG_NAMES[*n as usize]),
+            Operand::Indirect(n) => write!(out, "({})", ADDR_REG_NAMES[*n as usize]),
+            Operand::PostInc(n) => write!(out, "({})+", ADDR_REG_NAMES[*n as usize]),
+            Operand::PreDec(n) => write!(out, "-({})", ADDR_REG_NAMES[*n as usize]),
+            Operand::Displacement(d, n) => write!(out, "({},{})", SignedHex(*d as SLong), ADDR_REG_NAMES[*n as usize]),
+            Operand::Indexed(x) => x.write_to(out),
+            Operand::AbsShort(a) => write!(out, "${:x}.w", a),
+            Operand::AbsLong(a) => write!(out, "${:x}.l", a),
+            Operand::PcDisplacement(d, _) => write!(out, "({},PC)", SignedHex(*d as SLong)),
+            Operand::Immediate(v) => write!(out, "#${:x}", v),
+            Operand::SignedImmediate(v) => write!(out, "#{}", SignedHex(*v)),
+            Operand::Quick(v) => write!(out, "#{}", v),
+            Operand::Target(a) => write!(out, "{:x}", a),
+            Operand::RegList(bits) => write_reg_list(out, *bits),
+            Operand::Sr => out.write_str("SR"),
+            Operand::Ccr => out.write_str("CCR"),
+            Operand::Usp => out.write_str("USP"),
+            Operand::ControlReg(cr) => match control_reg_name(*cr) {
+                Some(name) => out.write_str(name),
+                None => write!(out, "${:03x}", cr),
+            },
+            Operand::DataIndirect(n) => write!(out, "({})", DATA_REG_NAMES[*n as usize]),
+            Operand::Pair(a, b) => {
+                a.write_to(out)?;
+                out.write_char(':')?;
+                b.write_to(out)
+            },
+            Operand::BitField(ea, offset, width) => {
+                ea.write_to(out)?;
+                write!(out, "{{{}:{}}}", offset, width)
+            },
+            Operand::Constant(v) => write!(out, "${:04x}", v),
+            Operand::ByteConstant(v) => write!(out, "${:02x}", v),
+            Operand::Invalid(7, n) => write!(out, "IllegalEA(7/{})", n),
+            Operand::Invalid(mode, _) => write!(out, "IllegalEA({})", mode),
+            Operand::Unreadable => out.write_str("????"),
+        }
+    }
+
+    fn format_asm(&self, relative: &dyn Fn(Adr) -> Option<String>, absolute: &dyn Fn(Adr) -> Option<String>) -> Option<String> {
+        match self {
+            Operand::Invalid(..) => None,
+            Operand::Target(adr) => relative(*adr),
+            Operand::AbsShort(adr) => {
+                let name = absolute(*adr as SWord as SLong as Adr);
+                Some(format!("({}).w", name.unwrap_or_else(|| SignedHex(*adr as SWord as SLong).to_string())))
+            },
+            Operand::AbsLong(adr) => Some(format!("({}).l", absolute(*adr).unwrap_or_else(|| format!("${:x}", adr)))),
+            Operand::PcDisplacement(_, adr) => Some(relative(*adr).map_or_else(|| self.to_string(), |s| format!("({},PC)", s))),
+            Operand::Pair(a, b) => Some(format!("{}:{}", a.format_asm(relative, absolute)?, b.format_asm(relative, absolute)?)),
+            Operand::BitField(ea, offset, width) => Some(format!("{}{{{}:{}}}", ea.format_asm(relative, absolute)?, offset, width)),
+            _ => Some(self.to_string()),
+        }
+    }
+}
+
+// Names for addresses, substituted into operands and branch targets.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    names: HashMap<Adr, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, adr: Adr, name: &str) {
+        self.names.insert(adr, name.to_string());
+    }
+
+    // Entries of `other` replace the ones already here.
+    pub fn merge(&mut self, other: SymbolTable) {
+        self.names.extend(other.names);
+    }
+
+    pub fn get(&self, adr: Adr) -> Option<&str> {
+        // Sign-extended short addresses reach the same location on a 24-bit bus.
+        self.names.get(&adr).or_else(|| self.names.get(&(adr & 0x00ffffff))).map(|s| s.as_str())
+    }
+
+    // One "address name" pair per line, the address in hex with an optional $ or 0x prefix.
+    // Blank lines and lines starting with ';' or '#' are skipped.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (adr, name) = match (fields.next(), fields.next()) {
+                (Some(adr), Some(name)) => (adr, name),
+                _ => return Err(format!("line {}: expected an address and a name", i + 1)),
+            };
+            let hex = adr.trim_start_matches('$').trim_start_matches("0x");
+            let adr = Adr::from_str_radix(hex, 16).map_err(|_| format!("line {}: bad address: {}", i + 1, adr))?;
+            table.insert(adr, name);
+        }
+        Ok(table)
+    }
+}
+
+// One instruction of a listing, with the label it carries if something branches to it.
+pub struct ListingLine {
+    pub adr: Adr,
+    pub label: Option<String>,
+    pub instruction: Instruction,
+    pub text: String,
+}
+
+pub fn label_name(adr: Adr) -> String {
+    format!("L{:08X}", adr)
+}
+
+// Two-pass disassembly of [start, end): the first pass collects the destinations of branches
+// and jumps that land on an instruction in the range, the second names them with labels.
+// Addresses in `symbols` go by their symbol names instead.
+pub fn listing(bus: &dyn MemoryView, start: Adr, end: Adr, symbols: &SymbolTable) -> Vec<ListingLine> {
+    let instructions = Disassembler::new(bus).iter(start, end).collect();
+    label_lines(instructions, symbols)
+}
+
+// Like `listing`, but only what `trace_flow` reaches from `entries` is decoded as code; the
+// rest of the range is dumped as dc.w, or dc.b for odd bytes.
+pub fn flow_listing(bus: &dyn MemoryView, start: Adr, end: Adr, entries: &[Adr], symbols: &SymbolTable) -> Vec<ListingLine> {
+    let code = trace_flow(bus, start, end, entries);
+    let mut instructions = Vec::new();
+    let mut adr = start;
+    while adr < end {
+        if let Some(inst) = code.get(&adr) {
+            instructions.push((adr, inst.clone()));
+            adr += inst.length as Adr;
+        } else {
+            // Code overlapped by the previous instruction is dropped along with its bytes.
+            let data_end = code.range(adr..).next().map_or(end, |(&next, _)| next.min(end));
+            instructions.extend(data_lines(bus, adr, data_end));
+            adr = data_end;
+        }
+    }
+    label_lines(instructions, symbols)
+}
+
+// Recursive traversal from `entries`, following branches, and jsr and jmp with known
+// destinations, until returns, unconditional jumps and words that don't decode. Line A and
+// line F words are taken as calls into the system (e.g. Human68k DOS calls) that come back.
+pub fn trace_flow(bus: &dyn MemoryView, start: Adr, end: Adr, entries: &[Adr]) -> BTreeMap<Adr, Instruction> {
+    let mut code = BTreeMap::new();
+    let mut pending = entries.to_vec();
+    while let Some(mut adr) = pending.pop() {
+        while adr >= start && adr < end && (adr & 1) == 0 && !code.contains_key(&adr) {
+            let inst = decode(bus, adr);
+            let valid = match inst.operands.first() {
+                Some(Operand::Constant(op)) => matches!(op >> 12, 0xa | 0xf),
+                _ => !inst.operands.iter().any(|o| matches!(o, Operand::Invalid(..))),
+            };
+            if !valid || inst.unreadable {
+                break;
+            }
+            if let Some(target) = inst.branch_target() {
+                pending.push(target);
+            }
+            let next = adr + inst.length as Adr;
+            let stop = matches!(inst.mnemonic, "bra" | "jmp" | "rts" | "rte" | "rtr" | "rtd" | "illegal");
+            code.insert(adr, inst);
+            if stop {
+                break;
+            }
+            adr = next;
+        }
+    }
+    code
+}
+
+fn data_lines(bus: &dyn MemoryView, mut adr: Adr, end: Adr) -> Vec<(Adr, Instruction)> {
+    const WORDS_PER_LINE: Adr = 4;
+
+    let mut lines = Vec::new();
+    while adr < end {
+        if (adr & 1) != 0 || end - adr < 2 {
+            let byte = bus.peek16(adr & !1).map_or(Operand::Unreadable, |word| {
+                Operand::ByteConstant(if (adr & 1) == 0 { (word >> 8) as Byte } else { word as Byte })
+            });
+            lines.push((adr, Instruction::new("dc", Some(Size::Byte), 1, vec![byte])));
+            adr += 1;
+        } else {
+            let n = ((end - adr) / 2).min(WORDS_PER_LINE);
+            let words = (0..n).map(|i| bus.peek16(adr + i * 2).map_or(Operand::Unreadable, Operand::Constant)).collect();
+            lines.push((adr, Instruction::new("dc", Some(Size::Word), n * 2, words)));
+            adr += n * 2;
+        }
+    }
+    lines
+}
+
+// Second pass of the listings: labels for the branch destinations that start an instruction.
+fn label_lines(instructions: Vec<(Adr, Instruction)>, symbols: &SymbolTable) -> Vec<ListingLine> {
+    let starts: HashSet<Adr> = instructions.iter().map(|(adr, _)| *adr).collect();
+    let labels: HashSet<Adr> = instructions.iter()
+        .filter_map(|(_, inst)| inst.branch_target())
+        .filter(|target| starts.contains(target))
+        .collect();
+    let symbol = |adr: Adr| match symbols.get(adr) {
+        Some(name) => Some(name.to_string()),
+        None if labels.contains(&adr) => Some(label_name(adr)),
+        None => None,
+    };
+    instructions.into_iter().map(|(adr, instruction)| {
+        let text = instruction.format_with(&symbol);
+        ListingLine { adr, label: symbol(adr), instruction, text }
+    }).collect()
+}
+
+// Source for HAS or ass that assembles back to the same bytes, given no optimization (has -c0).
+// Branches get explicit sizes, symbols outside of the range are defined with equ, and anything
+// that can't be written as an instruction, e.g. a branch out of the range, is left as dc.w.
+pub fn assembler_source(bus: &dyn MemoryView, lines: &[ListingLine], symbols: &SymbolTable) -> Vec<String> {
+    let labels: HashMap<Adr, String> = lines.iter()
+        .filter_map(|line| line.label.clone().map(|label| (line.adr, label)))
+        .collect();
+    let equs: RefCell<BTreeMap<String, Adr>> = RefCell::new(BTreeMap::new());
+    let relative = |adr: Adr| labels.get(&adr).cloned();
+    let absolute = |adr: Adr| {
+        if let Some(label) = labels.get(&adr) {
+            return Some(label.clone());
+        }
+        // A name already defined with another value, e.g. by a sign-extended short address.
+        let name = symbols.get(adr)?;
+        let value = *equs.borrow_mut().entry(name.to_string()).or_insert(adr);
+        if value == adr { Some(name.to_string()) } else { None }
+    };
+
+    let mut body = Vec::new();
+    for line in lines.iter() {
+        if let Some(label) = &line.label {
+            body.push(format!("{}:", label));
+        }
+        let text = line.instruction.format_asm(&relative, &absolute).unwrap_or_else(|| {
+            let words = (0..line.instruction.length / 2)
+                .map(|i| bus.peek16(line.adr + i as Adr * 2).map_or_else(|| "????".to_string(), |word| format!("${:04x}", word)))
+                .collect::<Vec<_>>();
+            format!("\tdc.w\t{}", words.join(","))
+        });
+        body.push(text);
+    }
+
+    let mut source = equs.into_inner().into_iter()
+        .map(|(name, adr)| format!("{}\tequ\t${:x}", name, adr))
+        .collect::<Vec<_>>();
+    source.push("\t.text".to_string());
+    source.extend(body);
+    source.push("\t.end".to_string());
+    source
+}
+
+pub fn disasm(bus: &dyn MemoryView, adr: Adr) -> (usize, String) {
+    let inst = decode(bus, adr);
+    (inst.length, inst.to_string())
+}
+
+// Best effort on unreadable memory: an unreadable opcode gives dc.w ????, unreadable extension
+// words are taken as 0 and flagged in `Instruction::unreadable`.
+pub fn decode(mem: &dyn MemoryView, adr: Adr) -> Instruction {
+    let op = match mem.peek16(adr) {
+        Some(op) => op,
+        None => {
+            let mut inst = Instruction::new("dc", Some(Size::Word), 2, vec![Operand::Unreadable]);
+            inst.unreadable = true;
+            return inst;
+        },
+    };
+    let bus = Reader { mem, unreadable: Cell::new(false) };
+    let mut inst = decode_op(&bus, adr, op);
+    inst.unreadable = bus.unreadable.get();
+    inst
+}
+
+// Decodes the instructions in memory one after another.
+pub struct Disassembler<'a> {
+    mem: &'a dyn MemoryView,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(mem: &'a dyn MemoryView) -> Self {
+        Self { mem }
+    }
+
+    pub fn decode(&self, adr: Adr) -> Instruction {
+        decode(self.mem, adr)
+    }
+
+    // The instructions starting in [start, end) with their addresses, each following the
+    // previous one; the last may extend past `end`.
+    pub fn iter(&self, start: Adr, end: Adr) -> Instructions<'a> {
+        Instructions { mem: self.mem, adr: start, end }
+    }
+}
+
+pub struct Instructions<'a> {
+    mem: &'a dyn MemoryView,
+    adr: Adr,
+    end: Adr,
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = (Adr, Instruction);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.adr >= self.end {
+            return None;
+        }
+        let adr = self.adr;
+        let inst = decode(self.mem, adr);
+        self.adr = adr.saturating_add(inst.length as Adr);
+        Some((adr, inst))
+    }
+}
+
+// Reads for decoding one instruction; unreadable words come back as 0 and are remembered.
+struct Reader<'a> {
+    mem: &'a dyn MemoryView,
+    unreadable: Cell<bool>,
+}
+
+impl Reader<'_> {
+    fn read16(&self, adr: Adr) -> Word {
+        self.mem.peek16(adr).unwrap_or_else(|| {
+            self.unreadable.set(true);
+            0
+        })
+    }
+
+    fn read32(&self, adr: Adr) -> Long {
+        ((self.read16(adr) as Long) << 16) | self.read16(adr + 2) as Long
+    }
+}
+
+fn decode_op(bus: &Reader, adr: Adr, op: Word) -> Instruction {
+    if let Some(inst) = decode_68020(bus, adr, op) {
+        return inst;
+    }
+    let inst = &INST[op as usize];
+
+    match inst.op {
+        Opcode::Nop => {
+            Instruction::new("nop", None, 2, vec![])
+        },
+        Opcode::MoveByte | Opcode::MoveWord | Opcode::MoveLong => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let dt = ((op >> 6) & 7) as usize;
+            let di = (op >> 9) & 7;
+            let size = match op >> 12 {
+                1 => Size::Byte,
+                3 => Size::Word,
+                _ => Size::Long,
+            };
+            let (ssz, src) = source(bus, adr + 2, size, st, si);
+            let (dsz, dst) = destination(bus, adr + 2 + ssz, size, dt, di);
+            Instruction::new(MOVE_NAMES[dt], Some(size), 2 + ssz + dsz, vec![src, dst])
+        },
+        Opcode::MoveaWord | Opcode::MoveaLong => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let di = (op >> 9) & 7;
+            let size = if (op >> 12) == 3 { Size::Word } else { Size::Long };
+            let (ssz, src) = source(bus, adr + 2, size, st, si);
+            Instruction::new("movea", Some(size), 2 + ssz, vec![src, Operand::AddrReg(di)])
+        },
+        Opcode::Moveq => {
+            let di = (op >> 9) & 7;
+            Instruction::new("moveq", None, 2, vec![Operand::SignedImmediate(op as SByte as SLong), Operand::DataReg(di)])
+        },
+        Opcode::Movep => {
+            let ai = op & 7;
+            let di = (op >> 9) & 7;
+            let ofs = bus.read16(adr + 2);
+            let size = if (op & 0x0040) != 0 { Size::Long } else { Size::Word };
+            let mem = Operand::Displacement(ofs as SWord, ai);
+            if (op & 0x0080) != 0 {
+                Instruction::new("movep", Some(size), 4, vec![Operand::DataReg(di), mem])
+            } else {
+                Instruction::new("movep", Some(size), 4, vec![mem, Operand::DataReg(di)])
+            }
+        },
+        Opcode::MovemFrom => {
+            let n = op & 7;
+            let mode = ((op >> 3) & 7) as usize;
+            let size = if (op & 0x0040) != 0 { Size::Long } else { Size::Word };
+            let bits = bus.read16(adr + 2);
+            // The mask is reversed (bit 0 is A7) for -(An).
+            let regs = Operand::RegList(if mode == 4 { bits.reverse_bits() } else { bits });
+            let (dsz, dst) = if mode == 4 { (0, Operand::PreDec(n)) } else { control(bus, adr + 4, mode, n) };
+            Instruction::new("movem", Some(size), 4 + dsz, vec![regs, dst])
+        },
+        Opcode::MovemTo => {
+            let n = op & 7;
+            let mode = ((op >> 3) & 7) as usize;
+            let size = if (op & 0x0040) != 0 { Size::Long } else { Size::Word };
+            let bits = bus.read16(adr + 2);
+            let (ssz, src) = if mode == 3 { (0, Operand::PostInc(n)) } else { control(bus, adr + 4, mode, n) };
+            Instruction::new("movem", Some(size), 4 + ssz, vec![src, Operand::RegList(bits)])
+        },
+        Opcode::MoveToSrIm => {
+            let val = bus.read16(adr + 2);
+            Instruction::new("move", None, 4, vec![Operand::Immediate(val as Long), Operand::Sr])
+        },
+        Opcode::MoveToSr => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let (ssz, src) = source(bus, adr + 2, Size::Word, st, si);
+            Instruction::new("move", None, 2 + ssz, vec![src, Operand::Sr])
+        },
+        Opcode::MoveFromSr => {
+            let di = op & 7;
+            let dt = ((op >> 3) & 7) as usize;
+            let (dsz, dst) = destination(bus, adr + 2, Size::Word, dt, di);
+            Instruction::new("move", None, 2 + dsz, vec![Operand::Sr, dst])
+        },
+        Opcode::MoveFromCcr => {
+            let di = op & 7;
+            let dt = ((op >> 3) & 7) as usize;
+            let (dsz, dst) = destination(bus, adr + 2, Size::Word, dt, di);
+            Instruction::new("move", None, 2 + dsz, vec![Operand::Ccr, dst])
+        },
+        Opcode::Movec => {
+            let ext = bus.read16(adr + 2);
+            let reg = general_reg(ext >> 12);
+            let cr = Operand::ControlReg(ext & 0x0fff);
+            if (op & 1) == 0 {
+                Instruction::new("movec", None, 4, vec![cr, reg])
+            } else {
+                Instruction::new("movec", None, 4, vec![reg, cr])
+            }
+        },
+        Opcode::Moves => {
+            let ei = op & 7;
+            let et = ((op >> 3) & 7) as usize;
+            let ext = bus.read16(adr + 2);
+            let reg = general_reg(ext >> 12);
+            let size = Size::from_bits(op >> 6);
+            let (esz, ea) = destination(bus, adr + 4, size, et, ei);
+            if (ext & 0x0800) != 0 {
+                Instruction::new("moves", Some(size), 4 + esz, vec![reg, ea])
+            } else {
+                Instruction::new("moves", Some(size), 4 + esz, vec![ea, reg])
+            }
+        },
+        Opcode::MoveToUsp => {
+            let si = op & 7;
+            Instruction::new("move", None, 2, vec![Operand::AddrReg(si), Operand::Usp])
+        },
+        Opcode::MoveFromUsp => {
+            let di = op & 7;
+            Instruction::new("move", None, 2, vec![Operand::Usp, Operand::AddrReg(di)])
+        },
+        Opcode::LeaDirect | Opcode::LeaOffset | Opcode::LeaOffsetD | Opcode::LeaOffsetPc => {
+            lea(bus, adr, op)
+        },
+        Opcode::Pea => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let (ssz, src) = control(bus, adr + 2, st, si);
+            Instruction::new("pea", None, 2 + ssz, vec![src])
+        },
+        Opcode::ClrByte | Opcode::ClrWord | Opcode::ClrLong => { unary(bus, adr, op, "clr") },
+        Opcode::Swap => {
+            let di = op & 7;
+            Instruction::new("swap", None, 2, vec![Operand::DataReg(di)])
+        },
+        Opcode::ExgD => {
+            let yi = op & 7;
+            let xi = (op >> 9) & 7;
+            Instruction::new("exg", None, 2, vec![Operand::DataReg(xi), Operand::DataReg(yi)])
+        },
+        Opcode::ExgA => {
+            let yi = op & 7;
+            let xi = (op >> 9) & 7;
+            Instruction::new("exg", None, 2, vec![Operand::AddrReg(xi), Operand::AddrReg(yi)])
+        },
+        Opcode::ExgDA => {
+            let yi = op & 7;
+            let xi = (op >> 9) & 7;
+            Instruction::new("exg", None, 2, vec![Operand::DataReg(xi), Operand::AddrReg(yi)])
+        },
+        Opcode::CmpByte | Opcode::CmpWord | Opcode::CmpLong => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let di = (op >> 9) & 7;
+            let size = Size::from_bits(op >> 6);
+            let (ssz, src) = source(bus, adr + 2, size, st, si);
+            Instruction::new("cmp", Some(size), 2 + ssz, vec![src, Operand::DataReg(di)])
+        },
+        Opcode::CmpiByte | Opcode::CmpiWord | Opcode::CmpiLong => { alu_imm(bus, adr, op, "cmpi") },
+        Opcode::CmpaLong => { address_op(bus, adr, op, "cmpa", Size::Long) },
+        Opcode::CmpmByte => { cmpm(op) },
+        Opcode::Cmp2Byte => {
+            let word2 = bus.read16(adr + 2);
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let (ssz, src) = source(bus, adr + 4, Size::Byte, st, si);
+            Instruction::new("cmp2", Some(Size::Byte), 4 + ssz, vec![src, general_reg(word2 >> 12)])
+        },
+        Opcode::Chk => { word_op(bus, adr, op, "chk") },
+        Opcode::TstByte | Opcode::TstWord | Opcode::TstLong => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let size = Size::from_bits(op >> 6);
+            let (ssz, src) = source(bus, adr + 2, size, st, si);
+            Instruction::new("tst", Some(size), 2 + ssz, vec![src])
+        },
+        Opcode::Btst => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let bi = (op >> 9) & 7;
+            let (ssz, src) = source(bus, adr + 2, Size::Byte, st, si);
+            Instruction::new("btst", None, 2 + ssz, vec![Operand::DataReg(bi), src])
+        },
+        Opcode::BtstIm => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let bit = bus.read16(adr + 2);
+            let (ssz, src) = source(bus, adr + 4, Size::Byte, st, si);
+            Instruction::new("btst", None, 4 + ssz, vec![Operand::Immediate(bit as Long), src])
+        },
+        Opcode::Bchg => { bit_op(bus, adr, op, "bchg") },
+        Opcode::BchgIm => { bit_op_im(bus, adr, op, "bchg") },
+        Opcode::Bclr => { bit_op(bus, adr, op, "bclr") },
+        Opcode::BclrIm => { bit_op_im(bus, adr, op, "bclr") },
+        Opcode::Bset => { bit_op(bus, adr, op, "bset") },
+        Opcode::BsetIm => { bit_op_im(bus, adr, op, "bset") },
+        Opcode::Reset => {
+            Instruction::new("reset", None, 2, vec![])
+        },
+        Opcode::Illegal => {
+            Instruction::new("illegal", None, 2, vec![])
+        },
+        Opcode::LineA | Opcode::LineF => {
+            Instruction::new("dc", Some(Size::Word), 2, vec![Operand::Constant(op)])
+        },
+        Opcode::AddByte | Opcode::AddWord | Opcode::AddLong |
+        Opcode::AddEaByte | Opcode::AddEaWord | Opcode::AddEaLong => { alu(bus, adr, op, "add") },
+        Opcode::AddiByte | Opcode::AddiWord | Opcode::AddiLong => { alu_imm(bus, adr, op, "addi") },
+        Opcode::AddaLong => { address_op(bus, adr, op, "adda", Size::Long) },
+        Opcode::AddqByte | Opcode::AddqWord | Opcode::AddqLong => { quick(bus, adr, op, "addq") },
+        Opcode::SubByte | Opcode::SubWord | Opcode::SubLong |
+        Opcode::SubEaByte | Opcode::SubEaWord | Opcode::SubEaLong => { alu(bus, adr, op, "sub") },
+        Opcode::SubiByte | Opcode::SubiWord | Opcode::SubiLong => { alu_imm(bus, adr, op, "subi") },
+        Opcode::SubaLong => { address_op(bus, adr, op, "suba", Size::Long) },
+        Opcode::SubqByte | Opcode::SubqWord | Opcode::SubqLong => { quick(bus, adr, op, "subq") },
+        Opcode::MuluWord => { word_op(bus, adr, op, "mulu") },
+        Opcode::MulLong | Opcode::DivLong => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let ext = bus.read16(adr + 2);
+            let (ssz, src) = source(bus, adr + 4, Size::Long, st, si);
+            let dl = (ext >> 12) & 7;
+            let dh = ext & 7;
+            let name = match (op & 0x0040, ext & 0x0800) {
+                (0, 0) => "mulu",
+                (0, _) => "muls",
+                (_, 0) => if (ext & 0x0400) == 0 && dh != dl {"divul"} else {"divu"},
+                _ => if (ext & 0x0400) == 0 && dh != dl {"divsl"} else {"divs"},
+            };
+            let dst = if (ext & 0x0400) != 0 || ((op & 0x0040) != 0 && dh != dl) {
+                Operand::Pair(Box::new(Operand::DataReg(dh)), Box::new(Operand::DataReg(dl)))
+            } else {
+                Operand::DataReg(dl)
+            };
+            Instruction::new(name, Some(Size::Long), 4 + ssz, vec![src, dst])
+        },
+        Opcode::Abcd => { extend_op(op, "abcd", None) },
+        Opcode::Sbcd => { extend_op(op, "sbcd", None) },
+        Opcode::Nbcd => {
+            let di = op & 7;
+            let dt = ((op >> 3) & 7) as usize;
+            let (dsz, dst) = destination(bus, adr + 2, Size::Byte, dt, di);
+            Instruction::new("nbcd", None, 2 + dsz, vec![dst])
+        },
+        Opcode::AndByte | Opcode::AndWord | Opcode::AndLong |
+        Opcode::AndEaByte | Opcode::AndEaWord | Opcode::AndEaLong => { alu(bus, adr, op, "and") },
+        Opcode::AndiByte | Opcode::AndiWord | Opcode::AndiLong => { alu_imm(bus, adr, op, "andi") },
+        Opcode::AndiCcr => { to_ccr(bus, adr, "andi") },
+        Opcode::AndiSr => { to_sr(bus, adr, "andi") },
+        Opcode::OrByte | Opcode::OrWord | Opcode::OrLong |
+        Opcode::OrEaByte | Opcode::OrEaWord | Opcode::OrEaLong => { alu(bus, adr, op, "or") },
+        Opcode::OriByte | Opcode::OriWord | Opcode::OriLong => { alu_imm(bus, adr, op, "ori") },
+        Opcode::OriCcr => { to_ccr(bus, adr, "ori") },
+        Opcode::OriSr => { to_sr(bus, adr, "ori") },
+        Opcode::EorByte => { eor(bus, adr, op) },
+        Opcode::EoriByte | Opcode::EoriWord | Opcode::EoriLong => { alu_imm(bus, adr, op, "eori") },
+        Opcode::EoriCcr => { to_ccr(bus, adr, "eori") },
+        Opcode::EoriSr => { to_sr(bus, adr, "eori") },
+        Opcode::NotByte | Opcode::NotWord | Opcode::NotLong => { unary(bus, adr, op, "not") },
+        Opcode::AslImByte | Opcode::AslImWord | Opcode::AslImLong | Opcode::AslByte | Opcode::AslWord | Opcode::AslLong |
+        Opcode::AsrImByte | Opcode::AsrImWord | Opcode::AsrImLong | Opcode::AsrByte | Opcode::AsrWord | Opcode::AsrLong |
+        Opcode::LslImByte | Opcode::LslImWord | Opcode::LslImLong | Opcode::LslByte | Opcode::LslWord | Opcode::LslLong |
+        Opcode::LsrImByte | Opcode::LsrImWord | Opcode::LsrImLong | Opcode::LsrByte | Opcode::LsrWord | Opcode::LsrLong |
+        Opcode::RoxlImByte | Opcode::RoxlImWord | Opcode::RoxlImLong | Opcode::RoxlByte | Opcode::RoxlWord | Opcode::RoxlLong |
+        Opcode::RoxrImByte | Opcode::RoxrImWord | Opcode::RoxrImLong | Opcode::RoxrByte | Opcode::RoxrWord | Opcode::RoxrLong |
+        Opcode::RolImByte | Opcode::RolImWord | Opcode::RolImLong | Opcode::RolByte | Opcode::RolWord | Opcode::RolLong |
+        Opcode::RorImByte | Opcode::RorImWord | Opcode::RorImLong | Opcode::RorByte | Opcode::RorWord | Opcode::RorLong => {
+            shift_reg(op)
+        },
+        Opcode::Bftst | Opcode::Bfextu | Opcode::Bfchg | Opcode::Bfexts | Opcode::Bfclr | Opcode::Bfffo | Opcode::Bfset | Opcode::Bfins => {
+            bitfield(bus, adr, op)
+        },
+        Opcode::Cas => {
+            let di = op & 7;
+            let dt = ((op >> 3) & 7) as usize;
+            let ext = bus.read16(adr + 2);
+            let size = Size::from_bits(((op >> 9) & 3) - 1);
+            let (dsz, dst) = destination(bus, adr + 4, size, dt, di);
+            Instruction::new("cas", Some(size), 4 + dsz, vec![Operand::DataReg(ext & 7), Operand::DataReg((ext >> 6) & 7), dst])
+        },
+        Opcode::Cas2 => {
+            let ext1 = bus.read16(adr + 2);
+            let ext2 = bus.read16(adr + 4);
+            let rn = |ext: Word| {
+                let n = (ext >> 12) & 7;
+                Box::new(if (ext & 0x8000) != 0 { Operand::Indirect(n) } else { Operand::DataIndirect(n) })
+            };
+            let dreg = |n: Word| Box::new(Operand::DataReg(n & 7));
+            let size = if (op & 0x0200) != 0 { Size::Long } else { Size::Word };
+            Instruction::new("cas2", Some(size), 6, vec![
+                Operand::Pair(dreg(ext1), dreg(ext2)),
+                Operand::Pair(dreg(ext1 >> 6), dreg(ext2 >> 6)),
+                Operand::Pair(rn(ext1), rn(ext2)),
+            ])
+        },
+        Opcode::AslMem | Opcode::AsrMem | Opcode::LslMem | Opcode::LsrMem | Opcode::RoxlMem | Opcode::RoxrMem | Opcode::RolMem | Opcode::RorMem => {
+            shift_mem(bus, adr, op)
+        },
+        Opcode::ExtWord => {
+            let di = op & 7;
+            Instruction::new("ext", Some(Size::Word), 2, vec![Operand::DataReg(di)])
+        },
+        Opcode::Bra | Opcode::Bhi | Opcode::Bls | Opcode::Bcc | Opcode::Bcs | Opcode::Bne | Opcode::Beq | Opcode::Bvc |
+        Opcode::Bvs | Opcode::Bpl | Opcode::Bmi | Opcode::Bge | Opcode::Blt | Opcode::Bgt | Opcode::Ble | Opcode::Bsr => {
+            bcond(bus, adr + 2, op)
+        },
+        Opcode::Dbcc => {
+            let si = op & 7;
+            let ofs = bus.read16(adr + 2) as SWord;
+            let jmp = ((adr + 2) as SLong).wrapping_add(ofs as SLong) as Long;
+            let cc = ((op >> 8) & 0x0f) as usize;
+            Instruction::new(DBCC_NAMES[cc], None, 4, vec![Operand::DataReg(si), Operand::Target(jmp)])
+        },
+        Opcode::Scc => {
+            let di = op & 7;
+            let dt = ((op >> 3) & 7) as usize;
+            let (dsz, dst) = destination(bus, adr + 2, Size::Byte, dt, di);
+            Instruction::new(SCC_NAMES[((op >> 8) & 0x0f) as usize], None, 2 + dsz, vec![dst])
+        },
+        Opcode::Jsr => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let (ssz, src) = control(bus, adr + 2, st, si);
+            Instruction::new("jsr", None, 2 + ssz, vec![src])
+        },
+        Opcode::Jmp => {
+            let si = op & 7;
+            let st = ((op >> 3) & 7) as usize;
+            let (ssz, src) = control(bus, adr + 2, st, si);
+            Instruction::new("jmp", None, 2 + ssz, vec![src])
+        },
+        Opcode::Rts => {
+            Instruction::new("rts", None, 2, vec![])
+        },
+        Opcode::Rtd => {
+            let disp = bus.read16(adr + 2);
+            Instruction::new("rtd", None, 4, vec![Operand::SignedImmediate(disp as SWord as SLong)])
+        },
+        Opcode::Stop => {
+            let val = bus.read16(adr + 2);
+            Instruction::new("stop", None, 4, vec![Operand::Immediate(val as Long)])
+        },
+        Opcode::Rte => {
+            Instruction::new("rte", None, 2, vec![])
+        },
+        Opcode::Trap => {
+            let no = op & 0x000f;
+            Instruction::new("trap", None, 2, vec![Operand::Immediate(no as Long)])
+        },
+        _ => {
+            decode_unlisted(bus, adr, op).unwrap_or_else(|| Instruction::new("dc", Some(Size::Word), 2, vec![Operand::Constant(op)]))
+        },
+    }
+}
+
+// Whether `op` is an instruction the interpreter's opcode table doesn't list yet, as opposed to
+// an unassigned pattern. Matching depends on the opcode word alone, so memory reads as zeros.
+pub(crate) fn unlisted_instruction(op: Word, m68020: bool) -> bool {
+    struct Zeros;
+    impl MemoryView for Zeros {
+        fn peek16(&self, _adr: Adr) -> Option<Word> { Some(0) }
+    }
+
+    let bus = Reader { mem: &Zeros, unreadable: Cell::new(false) };
+    decode_unlisted(&bus, 0, op).is_some() || (m68020 && decode_68020(&bus, 0, op).is_some())
+}
+
+// 68000 instructions the interpreter's opcode table doesn't list yet, decoded from their bit patterns.
+fn decode_unlisted(bus: &Reader, adr: Adr, op: Word) -> Option<Instruction> {
+    let n = op & 7;
+    let mode = ((op >> 3) & 7) as usize;
+    let size = (op >> 6) & 3;
+    let result = match op {
+        0x4e76 => Instruction::new("trapv", None, 2, vec![]),
+        0x4e77 => Instruction::new("rtr", None, 2, vec![]),
+        _ if (op & 0xfff8) == 0x4e50 => {
+            let disp = bus.read16(adr + 2);
+            Instruction::new("link", None, 4, vec![Operand::AddrReg(n), Operand::SignedImmediate(disp as SWord as SLong)])
+        },
+        _ if (op & 0xfff8) == 0x4e58 => Instruction::new("unlk", None, 2, vec![Operand::AddrReg(n)]),
+        _ if (op & 0xfff8) == 0x48c0 => Instruction::new("ext", Some(Size::Long), 2, vec![Operand::DataReg(n)]),
+        _ if (op & 0xfb00) == 0x4000 && size < 3 && ea_allowed(mode, n, EA_DATA_ALTERABLE) => {
+            unary(bus, adr, op, if (op & 0x0400) == 0 {"negx"} else {"neg"})
+        },
+        _ if (op & 0xffc0) == 0x44c0 && ea_allowed(mode, n, EA_DATA) => {
+            let (ssz, src) = source(bus, adr + 2, Size::Word, mode, n);
+            Instruction::new("move", None, 2 + ssz, vec![src, Operand::Ccr])
+        },
+        _ if (op & 0xffc0) == 0x4ac0 && ea_allowed(mode, n, EA_DATA_ALTERABLE) => {
+            let (dsz, dst) = destination(bus, adr + 2, Size::Byte, mode, n);
+            Instruction::new("tas", None, 2 + dsz, vec![dst])
+        },
+        _ if (op & 0xf1c0) == 0x41c0 && ea_allowed(mode, n, EA_CONTROL) => { lea(bus, adr, op) },
+        _ if (op & 0xf1c0) == 0x80c0 && ea_allowed(mode, n, EA_DATA) => { word_op(bus, adr, op, "divu") },
+        _ if (op & 0xf1c0) == 0x81c0 && ea_allowed(mode, n, EA_DATA) => { word_op(bus, adr, op, "divs") },
+        _ if (op & 0xf1c0) == 0xc1c0 && ea_allowed(mode, n, EA_DATA) => { word_op(bus, adr, op, "muls") },
+        _ if (op & 0xf1c0) == 0xd0c0 && ea_allowed(mode, n, EA_ALL) => { address_op(bus, adr, op, "adda", Size::Word) },
+        _ if (op & 0xf1c0) == 0x90c0 && ea_allowed(mode, n, EA_ALL) => { address_op(bus, adr, op, "suba", Size::Word) },
+        _ if (op & 0xf1c0) == 0xb0c0 && ea_allowed(mode, n, EA_ALL) => { address_op(bus, adr, op, "cmpa", Size::Word) },
+        _ if (op & 0xf130) == 0xd100 && size < 3 => { extend_op(op, "addx", Some(Size::from_bits(size))) },
+        _ if (op & 0xf130) == 0x9100 && size < 3 => { extend_op(op, "subx", Some(Size::from_bits(size))) },
+        _ if (op & 0xf138) == 0xb108 && size < 3 => { cmpm(op) },
+        _ if (op & 0xf100) == 0xb100 && size < 3 && ea_allowed(mode, n, EA_DATA_ALTERABLE) => { eor(bus, adr, op) },
+        _ => return None,
+    };
+    Some(result)
+}
+
+// 68020 instructions whose encodings the 68000 opcode table leaves unknown or gives to
+// another instruction with an addressing mode it doesn't allow.
+fn decode_68020(bus: &Reader, adr: Adr, op: Word) -> Option<Instruction> {
+    let n = op & 7;
+    let mode = ((op >> 3) & 7) as usize;
+    let result = match op {
+        _ if (op & 0xfff8) == 0x49c0 => Instruction::new("extb", Some(Size::Long), 2, vec![Operand::DataReg(n)]),
+        _ if (op & 0xfff8) == 0x4808 => {
+            let disp = bus.read32(adr + 2);
+            Instruction::new("link", Some(Size::Long), 6, vec![Operand::AddrReg(n), Operand::SignedImmediate(disp as SLong)])
+        },
+        _ if (op & 0xfff8) == 0x4848 => Instruction::new("bkpt", None, 2, vec![Operand::Quick(n)]),
+        _ if (op & 0xf0f8) == 0x50f8 && (2..=4).contains(&n) => {
+            let name = TRAPCC_NAMES[((op >> 8) & 0x0f) as usize];
+            match n {
+                2 => Instruction::new(name, Some(Size::Word), 4, vec![Operand::Immediate(bus.read16(adr + 2) as Long)]),
+                3 => Instruction::new(name, Some(Size::Long), 6, vec![Operand::Immediate(bus.read32(adr + 2))]),
+                _ => Instruction::new(name, None, 2, vec![]),
+            }
+        },
+        _ if (op & 0xf9c0) == 0x00c0 && (op & 0x0600) != 0x0600 && ea_allowed(mode, n, EA_CONTROL) => {
+            let ext = bus.read16(adr + 2);
+            let (ssz, src) = control(bus, adr + 4, mode, n);
+            let name = if (ext & 0x0800) != 0 {"chk2"} else {"cmp2"};
+            Instruction::new(name, Some(Size::from_bits(op >> 9)), 4 + ssz, vec![src, general_reg(ext >> 12)])
+        },
+        _ if (op & 0xf1c0) == 0x4100 && ea_allowed(mode, n, EA_DATA) => {
+            let (ssz, src) = source(bus, adr + 2, Size::Long, mode, n);
+            Instruction::new("chk", Some(Size::Long), 2 + ssz, vec![src, Operand::DataReg((op >> 9) & 7)])
+        },
+        _ if (op & 0xf1f0) == 0x8140 || (op & 0xf1f0) == 0x8180 => {
+            let name = if (op & 0x0040) != 0 {"pack"} else {"unpk"};
+            let adjustment = Operand::Immediate(bus.read16(adr + 2) as Long);
+            let mut inst = extend_op(op, name, None);
+            inst.operands.push(adjustment);
+            inst.length = 4;
+            inst
+        },
+        _ => return None,
+    };
+    Some(result)
+}
+
+// Decode the effective address `mode`/`n` whose extension words start at `adr`; returns the
+// number of extension bytes and the operand.
+fn effective_address(bus: &Reader, adr: Adr, size: Size, mode: usize, n: Word, modes: Word) -> (u32, Operand) {
+    if !ea_allowed(mode, n, modes) {
+        return (0, Operand::Invalid(mode, n));
+    }
+    match (mode, n) {
+        (0, _) => (0, Operand::DataReg(n)),
+        (1, _) => (0, Operand::AddrReg(n)),
+        (2, _) => (0, Operand::Indirect(n)),
+        (3, _) => (0, Operand::PostInc(n)),
+        (4, _) => (0, Operand::PreDec(n)),
+        (5, _) => (2, Operand::Displacement(bus.read16(adr) as SWord, n)),
+        (6, _) => indexed(bus, adr, Base::Addr(n)),
+        (7, 0) => (2, Operand::AbsShort(bus.read16(adr))),
+        (7, 1) => (4, Operand::AbsLong(bus.read32(adr))),
+        (7, 2) => {
+            let d = bus.read16(adr) as SWord;
+            (2, Operand::PcDisplacement(d, adr.wrapping_add(d as SLong as Adr)))
+        },
+        (7, 3) => indexed(bus, adr, Base::Pc(adr)),
+        _ => match size {
+            Size::Byte => (2, Operand::Immediate(bus.read16(adr) as Long & 0xff)),
+            Size::Word => (2, Operand::Immediate(bus.read16(adr) as Long)),
+            Size::Long => (4, Operand::Immediate(bus.read32(adr))),
+        },
+    }
+}
+
+fn source(bus: &Reader, adr: Adr, size: Size, mode: usize, n: Word) -> (u32, Operand) {
+    let modes = if size == Size::Byte { EA_DATA } else { EA_ALL };
+    effective_address(bus, adr, size, mode, n, modes)
+}
+
+fn destination(bus: &Reader, adr: Adr, size: Size, mode: usize, n: Word) -> (u32, Operand) {
+    let modes = if size == Size::Byte { EA_DATA_ALTERABLE } else { EA_ALTERABLE };
+    effective_address(bus, adr, size, mode, n, modes)
+}
+
+fn control(bus: &Reader, adr: Adr, mode: usize, n: Word) -> (u32, Operand) {
+    effective_address(bus, adr, Size::Long, mode, n, EA_CONTROL)
+}
+
+// Register number in the top bits of an extension word: D0-D7 or A0-A7.
+fn general_reg(bits: Word) -> Operand {
+    if (bits & 8) != 0 { Operand::AddrReg(bits & 7) } else { Operand::DataReg(bits & 7) }
+}
+
+fn indexed(bus: &Reader, adr: Adr, base: Base) -> (u32, Operand) {
+    let ext = bus.read16(adr);
+    let (disp, da, xr, xl) = brief_extension(ext);
+    let index = IndexReg { addr: da, reg: xr as Word, long: xl, scale: 1 << ((ext >> 9) & 3) };
+    if (ext & 0x0100) != 0 {
+        return full_extension(bus, adr, ext, base, index);
+    }
+    (2, Operand::Indexed(Indexed { base: Some(base), index: Some(index), disp, indirect: None }))
+}
+
+// 68020 full extension word: ([bd,base,Xn],od) / ([bd,base],Xn,od) / (bd,base,Xn).
+fn full_extension(bus: &Reader, adr: Adr, ext: Word, base: Base, index: IndexReg) -> (u32, Operand) {
+    let mut sz = 2;
+    let mut displacement = |size: Word| {
+        match size {
+            2 => { let d = bus.read16(adr + sz) as SWord as SLong; sz += 2; Some(d) },
+            3 => { let d = bus.read32(adr + sz) as SLong; sz += 4; Some(d) },
+            _ => None,
+        }
+    };
+    let disp = displacement((ext >> 4) & 3).unwrap_or(0);
+    let indirect = if (ext & 7) != 0 {
+        Some(MemoryIndirect { postindexed: (ext & 4) != 0, outer: displacement(ext & 3) })
+    } else {
+        None
+    };
+    let base = if (ext & 0x0080) != 0 { None } else { Some(base) };
+    let index = if (ext & 0x0040) != 0 { None } else { Some(index) };
+    (sz, Operand::Indexed(Indexed { base, index, disp, indirect }))
+}
+
+const SHIFT_NAMES: [&str; 8] = ["asr", "lsr", "roxr", "ror", "asl", "lsl", "roxl", "rol"];
+
+fn lea(bus: &Reader, adr: Adr, op: Word) -> Instruction {
+    let si = op & 7;
+    let st = ((op >> 3) & 7) as usize;
+    let di = (op >> 9) & 7;
+    let (ssz, src) = control(bus, adr + 2, st, si);
+    Instruction::new("lea", None, 2 + ssz, vec![src, Operand::AddrReg(di)])
+}
+
+fn alu(bus: &Reader, adr: Adr, op: Word, name: &'static str) -> Instruction {
+    let si = op & 7;
+    let st = ((op >> 3) & 7) as usize;
+    let ri = (op >> 9) & 7;
+    let size = Size::from_bits(op >> 6);
+    if (op & 0x0100) == 0 {
+        let (sz, ea) = source(bus, adr + 2, size, st, si);
+        Instruction::new(name, Some(size), 2 + sz, vec![ea, Operand::DataReg(ri)])
+    } else {
+        let (sz, ea) = destination(bus, adr + 2, size, st, si);
+        Instruction::new(name, Some(size), 2 + sz, vec![Operand::DataReg(ri), ea])
+    }
+}
+
+fn quick(bus: &Reader, adr: Adr, op: Word, name: &'static str) -> Instruction {
+    let di = op & 7;
+    let dt = ((op >> 3) & 7) as usize;
+    let size = Size::from_bits(op >> 6);
+    let (dsz, dst) = destination(bus, adr + 2, size, dt, di);
+    Instruction::new(name, Some(size), 2 + dsz, vec![Operand::Quick(conv07to18(op >> 9)), dst])
+}
+
+fn alu_imm(bus: &Reader, adr: Adr, op: Word, name: &'static str) -> Instruction {
+    let di = op & 7;
+    let dt = ((op >> 3) & 7) as usize;
+    let size = Size::from_bits(op >> 6);
+    let (isz, imm) = effective_address(bus, adr + 2, size, 7, 4, EA_ALL);
+    let (dsz, dst) = destination(bus, adr + 2 + isz, size, dt, di);
+    Instruction::new(name, Some(size), 2 + isz + dsz, vec![imm, dst])
+}
+
+fn to_ccr(bus: &Reader, adr: Adr, name: &'static str) -> Instruction {
+    let v = bus.read16(adr + 2) & 0xff;
+    Instruction::new(name, Some(Size::Byte), 4, vec![Operand::Immediate(v as Long), Operand::Ccr])
+}
+
+fn to_sr(bus: &Reader, adr: Adr, name: &'static str) -> Instruction {
+    let v = bus.read16(adr + 2);
+    Instruction::new(name, Some(Size::Word), 4, vec![Operand::Immediate(v as Long), Operand::Sr])
+}
+
+fn unary(bus: &Reader, adr: Adr, op: Word, name: &'static str) -> Instruction {
+    let di = op & 7;
+    let dt = ((op >> 3) & 7) as usize;
+    let size = Size::from_bits(op >> 6);
+    let (dsz, dst) = destination(bus, adr + 2, size, dt, di);
+    Instruction::new(name, Some(size), 2 + dsz, vec![dst])
+}
+
+fn eor(bus: &Reader, adr: Adr, op: Word) -> Instruction {
+    let di = op & 7;
+    let dt = ((op >> 3) & 7) as usize;
+    let si = (op >> 9) & 7;
+    let size = Size::from_bits(op >> 6);
+    let (dsz, dst) = destination(bus, adr + 2, size, dt, di);
+    Instruction::new("eor", Some(size), 2 + dsz, vec![Operand::DataReg(si), dst])
+}
+
+// Word-sized <ea>,Dn instructions: mulu/muls/divu/divs.w and chk.w.
+fn word_op(bus: &Reader, adr: Adr, op: Word, name: &'static str) -> Instruction {
+    let si = op & 7;
+    let st = ((op >> 3) & 7) as usize;
+    let di = (op >> 9) & 7;
+    let (ssz, src) = source(bus, adr + 2, Size::Word, st, si);
+    Instruction::new(name, Some(Size::Word), 2 + ssz, vec![src, Operand::DataReg(di)])
+}
+
+fn address_op(bus: &Reader, adr: Adr, op: Word, name: &'static str, size: Size) -> Instruction {
+    let si = op & 7;
+    let st = ((op >> 3) & 7) as usize;
+    let di = (op >> 9) & 7;
+    let (ssz, src) = source(bus, adr + 2, size, st, si);
+    Instruction::new(name, Some(size), 2 + ssz, vec![src, Operand::AddrReg(di)])
+}
+
+fn cmpm(op: Word) -> Instruction {
+    let si = op & 7;
+    let di = (op >> 9) & 7;
+    Instruction::new("cmpm", Some(Size::from_bits(op >> 6)), 2, vec![Operand::PostInc(si), Operand::PostInc(di)])
+}
+
+fn shift_reg(op: Word) -> Instruction {
+    let di = op & 7;
+    let name = SHIFT_NAMES[(((op >> 6) & 4) | ((op >> 3) & 3)) as usize];
+    let size = Some(Size::from_bits(op >> 6));
+    if (op & 0x0020) != 0 {
+        Instruction::new(name, size, 2, vec![Operand::DataReg((op >> 9) & 7), Operand::DataReg(di)])
+    } else {
+        Instruction::new(name, size, 2, vec![Operand::Quick(conv07to18(op >> 9)), Operand::DataReg(di)])
+    }
+}
+
+fn shift_mem(bus: &Reader, adr: Adr, op: Word) -> Instruction {
+    let di = op & 7;
+    let dt = ((op >> 3) & 7) as usize;
+    let name = SHIFT_NAMES[(((op >> 6) & 4) | ((op >> 9) & 3)) as usize];
+    let (dsz, dst) = destination(bus, adr + 2, Size::Word, dt, di);
+    Instruction::new(name, Some(Size::Word), 2 + dsz, vec![dst])
+}
+
+const BITFIELD_NAMES: [&str; 8] = ["bftst", "bfextu", "bfchg", "bfexts", "bfclr", "bfffo", "bfset", "bfins"];
+
+fn bitfield(bus: &Reader, adr: Adr, op: Word) -> Instruction {
+    let n = op & 7;
+    let mode = ((op >> 3) & 7) as usize;
+    let ext = bus.read16(adr + 2);
+    let (esz, ea) = if mode == 0 { (0, Operand::DataReg(n)) } else { control(bus, adr + 4, mode, n) };
+    let offset = if (ext & 0x0800) != 0 { BitSpec::Register((ext >> 6) & 7) } else { BitSpec::Immediate((ext >> 6) & 31) };
+    let width = if (ext & 0x0020) != 0 { BitSpec::Register(ext & 7) } else { BitSpec::Immediate(((ext & 31) + 31) % 32 + 1) };
+    let kind = ((op >> 8) & 7) as usize;
+    let field = Operand::BitField(Box::new(ea), offset, width);
+    let operands = match kind {
+        1 | 3 | 5 => vec![field, Operand::DataReg((ext >> 12) & 7)],
+        7 => vec![Operand::DataReg((ext >> 12) & 7), field],
+        _ => vec![field],
+    };
+    Instruction::new(BITFIELD_NAMES[kind], None, 4 + esz, operands)
+}
+
+fn bit_op(bus: &Reader, adr: Adr, op: Word, name: &'static str) -> Instruction {
+    let di = op & 7;
+    let dt = ((op >> 3) & 7) as usize;
+    let si = (op >> 9) & 7;
+    let (dsz, dst) = destination(bus, adr + 2, Size::Byte, dt, di);
+    Instruction::new(name, None, 2 + dsz, vec![Operand::DataReg(si), dst])
+}
+
+fn bit_op_im(bus: &Reader, adr: Adr, op: Word, name: &'static str) -> Instruction {
+    let di = op & 7;
+    let dt = ((op >> 3) & 7) as usize;
+    let bit = bus.read16(adr + 2);
+    let (dsz, dst) = destination(bus, adr + 4, Size::Byte, dt, di);
+    Instruction::new(name, None, 4 + dsz, vec![Operand::Immediate(bit as Long), dst])
+}
+
+// Dy,Dx or -(Ay),-(Ax) forms: abcd, sbcd, addx and subx.
+fn extend_op(op: Word, name: &'static str, size: Option<Size>) -> Instruction {
+    let si = op & 7;
+    let di = (op >> 9) & 7;
+    if (op & 0x0008) == 0 {
+        Instruction::new(name, size, 2, vec![Operand::DataReg(si), Operand::DataReg(di)])
+    } else {
+        Instruction::new(name, size, 2, vec![Operand::PreDec(si), Operand::PreDec(di)])
+    }
+}
+
+fn bcond(bus: &Reader, adr: Adr, op: Word) -> Instruction {
+    let (ofs, sz) = match op & 0x00ff {
+        0 => (bus.read16(adr) as SWord as SLong, 2),
+        0xff => (bus.read32(adr) as SLong, 4),
+        ofs => (ofs as SByte as SLong, 0),
+    };
+    let jmp = (adr as SLong).wrapping_add(ofs) as Long;
+    Instruction::new(BCC_NAMES[((op >> 8) & 0x0f) as usize], None, 2 + sz, vec![Operand::Target(jmp)])
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_with(f, &|_| None)
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
+fn control_reg_name(cr: Word) -> Option<&'static str> {
+    match cr {
+        0x000 => Some("SFC"),
+        0x001 => Some("DFC"),
+        0x800 => Some("USP"),
+        0x801 => Some("VBR"),
+        0x002 => Some("CACR"),
+        0x802 => Some("CAAR"),
+        0x803 => Some("MSP"),
+        0x804 => Some("ISP"),
+        _ => None,
+    }
+}
+
+impl Indexed {
+    // Not known for memory indirection, which would need to read the pointer.
+    fn address(&self, regs: Option<&Registers>) -> Option<Adr> {
+        if self.indirect.is_some() {
+            return None;
+        }
+        let base = match self.base {
+            Some(Base::Addr(n)) => regs?.a[n as usize],
+            Some(Base::Pc(pc)) => pc,
+            None => 0,
+        };
+        let index = match self.index {
+            Some(x) => {
+                let regs = regs?;
+                let value = if x.addr { regs.a[x.reg as usize] } else { regs.d[x.reg as usize] };
+                let value = if x.long { value } else { value as SWord as SLong as Long };
+                value.wrapping_mul(x.scale as Long)
+            },
+            None => 0,
+        };
+        Some(base.wrapping_add(index).wrapping_add(self.disp as Adr))
+    }
+}
+
+impl Indexed {
+    fn write_to(&self, out: &mut dyn Write) -> fmt::Result {
+        // Parts separated by commas, the empty ones left out.
+        let mut first = true;
+        let mut sep = |out: &mut dyn Write| {
+            if first {
+                first = false;
+                Ok(())
+            } else {
+                out.write_char(',')
+            }
+        };
+        let inner_index = match self.indirect {
+            Some(MemoryIndirect { postindexed: true, .. }) => None,
+            _ => self.index,
+        };
+
+        out.write_char('(')?;
+        if self.indirect.is_some() {
+            out.write_char('[')?;
+        } else if self.disp == 0 && self.base.is_none() && self.index.is_none() {
+            out.write_char('0')?;
+        }
+        if self.disp != 0 {
+            sep(out)?;
+            write!(out, "{}", self.disp)?;
+        }
+        match self.base {
+            Some(Base::Addr(n)) => {
+                sep(out)?;
+                out.write_str(ADDR_REG_NAMES[n as usize])?;
+            },
+            Some(Base::Pc(_)) => {
+                sep(out)?;
+                out.write_str("PC")?;
+            },
+            None => {},
+        }
+        if let Some(x) = inner_index {
+            sep(out)?;
+            write_index(out, x)?;
+        }
+        if let Some(MemoryIndirect { postindexed, outer }) = self.indirect {
+            out.write_char(']')?;
+            if let (true, Some(x)) = (postindexed, self.index) {
+                out.write_char(',')?;
+                write_index(out, x)?;
+            }
+            if let Some(od) = outer {
+                write!(out, ",{}", od)?;
+            }
+        }
+        out.write_char(')')
+    }
+}
+
+fn write_index(out: &mut dyn Write, x: IndexReg) -> fmt::Result {
+    let names = if x.addr { &ADDR_REG_NAMES } else { &DATA_REG_NAMES };
+    write!(out, "{}.{}", names[x.reg as usize], if x.long {'l'} else {'w'})?;
+    if x.scale > 1 {
+        write!(out, "*{}", x.scale)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for BitSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BitSpec::Immediate(v) => write!(f, "{}", v),
+            BitSpec::Register(n) => write!(f, "D{}", n),
+        }
+    }
+}
+
+// Hex with the sign in front, e.g. -$10.
+struct SignedHex(SLong);
+
+impl fmt::Display for SignedHex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 >= 0 {
+            write!(f, "${:x}", self.0)
+        } else {
+            write!(f, "-${:x}", self.0.unsigned_abs())
+        }
+    }
+}
+
+// movem register list, e.g. D0-D2/A6.
+fn write_reg_list(out: &mut dyn Write, bits: Word) -> fmt::Result {
+    let bit = |i: usize, j: usize| 1 << (i * 8 + j);
+    let mut first = true;
+    for (i, names) in [&DATA_REG_NAMES, &ADDR_REG_NAMES].iter().enumerate() {
+        let mut j = 0;
+        while j < 8 {
+            if (bits & bit(i, j)) == 0 {
+                j += 1;
+                continue;
+            }
+            let mut k = j + 1;
+            while k < 8 && (bits & bit(i, k)) != 0 {
+                k += 1;
+            }
+            if !first {
+                out.write_char('/')?;
+            }
+            first = false;
+            out.write_str(names[j])?;
+            if k > j + 1 {
+                write!(out, "-{}", names[k - 1])?;
+            }
+            j = k;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use std::fmt;
+
+use super::types::{Word, Adr};
+
+// Conditions the emulator cannot carry on from; the CPU state is left at the
+// start of the offending instruction. Breakpoint and Stopped are not failures:
+// running again resumes execution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuError {
+    UnknownOpcode { pc: Adr, op: Word },  // Decoded, but not implemented yet.
+    UnimplementedMode { pc: Adr, mode: usize, reg: usize },  // Addressing mode not supported by the helper.
+    Halted { pc: Adr },  // Double bus fault; only a reset recovers.
+    Breakpoint { pc: Adr },  // Reached a breakpoint; the instruction is not executed yet.
+    Stopped { pc: Adr },  // An execution hook requested a stop.
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::UnknownOpcode { pc, op } => write!(f, "{:06x}: unknown opcode {:04x}", pc, op),
+            CpuError::UnimplementedMode { pc, mode, reg } => write!(f, "{:06x}: unimplemented addressing mode {}/{}", pc, mode, reg),
+            CpuError::Halted { pc } => write!(f, "{:06x}: CPU halted", pc),
+            CpuError::Breakpoint { pc } => write!(f, "{:06x}: breakpoint", pc),
+            CpuError::Stopped { pc } => write!(f, "{:06x}: stopped", pc),
+        }
+    }
+}
@@ -0,0 +1,324 @@
+// Experimental JIT: straight-line runs of register-only instructions are translated
+// into native code with Cranelift. Blocks never touch memory, so bus timing, faults
+// and observers stay with the interpreter, which also runs everything not covered here.
+
+use std::collections::HashMap;
+
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::types::I32;
+use cranelift_codegen::ir::{AbiParam, InstBuilder, MemFlags, Value};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_codegen::Context;
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::Module;
+
+use super::types::{Word, Long, Adr};
+
+const MAX_BLOCK_INSTS: usize = 32;  // Bounds the interrupt latency added by a block.
+const MAX_COMPILED: usize = 0x10000;  // Code memory is only reclaimed by a flush.
+const PAGE_SHIFT: u32 = 8;  // Granularity of the self-modifying code check; a block spans at most 2 pages.
+
+// Register file shared with the compiled code.
+#[repr(C)]
+pub(crate) struct JitContext {
+    pub d: [Long; 8],
+    pub sr: Long,
+    pub pc: Adr,
+    pub cycles: Long,
+}
+
+const SR_OFFSET: i32 = 32;
+const PC_OFFSET: i32 = 36;
+const CYCLES_OFFSET: i32 = 40;
+
+pub(crate) type BlockFn = unsafe extern "C" fn(*mut JitContext);
+
+struct Block {
+    end: Adr,
+    op: Word,  // First opcode, reported as the step's opcode.
+    count: usize,  // Instructions, all executed on every run.
+    func: Option<BlockFn>,  // None: the first instruction is left to the interpreter.
+}
+
+enum Inst {
+    Nop,
+    Moveq(usize, Long),
+    Move(usize, usize),  // move.l Dy,Dx
+    Add(usize, usize),  // add.l Dy,Dx
+    Sub(usize, usize),  // sub.l Dy,Dx
+    Addq(usize, Long),
+    Subq(usize, Long),
+}
+
+enum Exit {
+    Fallthrough,
+    Bra(Adr),
+    Dbra(usize, Adr),
+}
+
+pub(crate) struct Jit {
+    module: JITModule,
+    builder_ctx: FunctionBuilderContext,
+    blocks: HashMap<Adr, Block>,
+    pages: HashMap<Adr, Vec<Adr>>,  // Page -> start addresses of the blocks overlapping it.
+    compiled: usize,
+}
+
+impl Jit {
+    // None when Cranelift doesn't support the host.
+    pub fn new() -> Option<Self> {
+        let mut flags = settings::builder();
+        flags.set("use_colocated_libcalls", "false").ok()?;
+        flags.set("is_pic", "false").ok()?;
+        let isa = cranelift_native::builder().ok()?.finish(settings::Flags::new(flags)).ok()?;
+        let module = JITModule::new(JITBuilder::with_isa(isa, cranelift_module::default_libcall_names()));
+        Some(Self {
+            module,
+            builder_ctx: FunctionBuilderContext::new(),
+            blocks: HashMap::new(),
+            pages: HashMap::new(),
+            compiled: 0,
+        })
+    }
+
+    // The compiled block at `pc` with its first opcode, end address and instruction count,
+    // translating it on the first visit. `fetch` returns the instruction word and its wait states.
+    pub fn block(&mut self, pc: Adr, fetch: impl FnMut(Adr) -> Option<(Word, usize)>) -> Option<(BlockFn, Word, Adr, usize)> {
+        if !self.blocks.contains_key(&pc) {
+            if self.compiled >= MAX_COMPILED {
+                self.flush();
+            }
+            let block = self.translate(pc, fetch)?;
+            for page in (pc >> PAGE_SHIFT)..=(block.end.wrapping_sub(1) >> PAGE_SHIFT) {
+                let starts = self.pages.entry(page).or_default();
+                if !starts.contains(&pc) {
+                    starts.push(pc);
+                }
+            }
+            self.blocks.insert(pc, block);
+        }
+        let block = &self.blocks[&pc];
+        block.func.map(|func| (func, block.op, block.end, block.count))
+    }
+
+    // Drop the blocks overlapping a write to [adr, adr + size).
+    pub fn invalidate(&mut self, adr: Adr, size: Adr) {
+        let end = adr.saturating_add(size);
+        for page in (adr >> PAGE_SHIFT)..=(end.wrapping_sub(1) >> PAGE_SHIFT) {
+            if let Some(starts) = self.pages.get_mut(&page) {
+                let blocks = &mut self.blocks;
+                starts.retain(|start| match blocks.get(start) {
+                    Some(block) if *start < end && adr < block.end => {
+                        blocks.remove(start);
+                        false
+                    },
+                    Some(_) => true,
+                    None => false,
+                });
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(fresh) = Self::new() {
+            let old = std::mem::replace(self, fresh);
+            // Safe: the only pointers into the old code memory were in its block map.
+            unsafe { old.module.free_memory() };
+        } else {
+            self.blocks.clear();
+            self.pages.clear();
+        }
+    }
+
+    fn translate(&mut self, start: Adr, mut fetch: impl FnMut(Adr) -> Option<(Word, usize)>) -> Option<Block> {
+        let mut insts = Vec::new();
+        let mut exit = Exit::Fallthrough;
+        let mut cycles = 0;  // Straight-line part, plus the exit's own cost below.
+        let mut exit_cycles = (0, 0);  // (taken, not taken) internal cycles of a DBRA.
+        let mut adr = start;
+        let first = fetch(start)?.0;
+        while insts.len() < MAX_BLOCK_INSTS {
+            let (op, wait) = match fetch(adr) {
+                Some(word) => word,
+                None => break,
+            };
+            let fetch_cycles = 4 + wait as Long;
+            if let Some((inst, internal)) = decode(op) {
+                insts.push(inst);
+                cycles += fetch_cycles + internal;
+                adr = adr.wrapping_add(2);
+                continue;
+            }
+            if op == 0x6000 || (op & 0xfff8) == 0x51c8 {
+                // BRA.W or DBRA with a 16-bit displacement.
+                let disp = match fetch(adr.wrapping_add(2)) {
+                    Some((disp, wait)) => {
+                        cycles += fetch_cycles + 4 + wait as Long;
+                        disp
+                    },
+                    None => break,
+                };
+                let target = adr.wrapping_add(2).wrapping_add(disp as i16 as Adr);
+                exit = if op == 0x6000 {
+                    cycles += 2;
+                    Exit::Bra(target)
+                } else {
+                    exit_cycles = (2, 6);
+                    Exit::Dbra((op & 7) as usize, target)
+                };
+                adr = adr.wrapping_add(4);
+            } else if (op & 0xff00) == 0x6000 && op != 0x60ff {  // BRA.S; 0xff is BRA.L on the 68020.
+                cycles += fetch_cycles + 6;
+                exit = Exit::Bra(adr.wrapping_add(2).wrapping_add(op as i8 as Adr));
+                adr = adr.wrapping_add(2);
+            }
+            break;
+        }
+        if adr == start {
+            return Some(Block { end: start.wrapping_add(2), op: first, count: 0, func: None });
+        }
+        let func = self.compile(&insts, &exit, adr, cycles, exit_cycles)?;
+        self.compiled += 1;
+        let count = insts.len() + if let Exit::Fallthrough = exit { 0 } else { 1 };
+        Some(Block { end: adr, op: first, count, func: Some(func) })
+    }
+
+    fn compile(&mut self, insts: &[Inst], exit: &Exit, next: Adr, cycles: Long, exit_cycles: (Long, Long)) -> Option<BlockFn> {
+        let mut ctx: Context = self.module.make_context();
+        let ptr = self.module.target_config().pointer_type();
+        ctx.func.signature.params.push(AbiParam::new(ptr));
+        {
+            let mut b = FunctionBuilder::new(&mut ctx.func, &mut self.builder_ctx);
+            let entry = b.create_block();
+            b.append_block_params_for_function_params(entry);
+            b.switch_to_block(entry);
+            b.seal_block(entry);
+            let base = b.block_params(entry)[0];
+            let mem = MemFlags::trusted();
+            let mut d: Vec<Value> = (0..8).map(|i| b.ins().load(I32, mem, base, i * 4)).collect();
+            let mut sr = b.ins().load(I32, mem, base, SR_OFFSET);
+            for inst in insts {
+                match *inst {
+                    Inst::Nop => {},
+                    Inst::Moveq(x, value) => {
+                        d[x] = b.ins().iconst(I32, value as i64);
+                        sr = logic_flags(&mut b, sr, d[x]);
+                    },
+                    Inst::Move(x, y) => {
+                        d[x] = d[y];
+                        sr = logic_flags(&mut b, sr, d[x]);
+                    },
+                    Inst::Add(x, y) => (d[x], sr) = add(&mut b, sr, d[x], d[y]),
+                    Inst::Sub(x, y) => (d[x], sr) = sub(&mut b, sr, d[x], d[y]),
+                    Inst::Addq(x, q) => {
+                        let q = b.ins().iconst(I32, q as i64);
+                        (d[x], sr) = add(&mut b, sr, d[x], q);
+                    },
+                    Inst::Subq(x, q) => {
+                        let q = b.ins().iconst(I32, q as i64);
+                        (d[x], sr) = sub(&mut b, sr, d[x], q);
+                    },
+                }
+            }
+            let (pc, cycles) = match *exit {
+                Exit::Fallthrough => (b.ins().iconst(I32, next as i64), b.ins().iconst(I32, cycles as i64)),
+                Exit::Bra(target) => (b.ins().iconst(I32, target as i64), b.ins().iconst(I32, cycles as i64)),
+                Exit::Dbra(x, target) => {
+                    // Decrement the low word; loop back unless it wrapped to -1.
+                    let low = b.ins().band_imm(d[x], 0xffff);
+                    let count = b.ins().iadd_imm(low, 0xffff);
+                    let count = b.ins().band_imm(count, 0xffff);
+                    let high = b.ins().band_imm(d[x], 0xffff0000u32 as i64);
+                    d[x] = b.ins().bor(high, count);
+                    let taken = b.ins().icmp_imm(IntCC::NotEqual, count, 0xffff);
+                    let target = b.ins().iconst(I32, target as i64);
+                    let next = b.ins().iconst(I32, next as i64);
+                    let taken_cycles = b.ins().iconst(I32, (cycles + exit_cycles.0) as i64);
+                    let expired_cycles = b.ins().iconst(I32, (cycles + exit_cycles.1) as i64);
+                    (b.ins().select(taken, target, next), b.ins().select(taken, taken_cycles, expired_cycles))
+                },
+            };
+            for (i, &value) in d.iter().enumerate() {
+                b.ins().store(mem, value, base, i as i32 * 4);
+            }
+            b.ins().store(mem, sr, base, SR_OFFSET);
+            b.ins().store(mem, pc, base, PC_OFFSET);
+            b.ins().store(mem, cycles, base, CYCLES_OFFSET);
+            b.ins().return_(&[]);
+            b.finalize();
+        }
+        let id = self.module.declare_anonymous_function(&ctx.func.signature).ok()?;
+        self.module.define_function(id, &mut ctx).ok()?;
+        self.module.clear_context(&mut ctx);
+        self.module.finalize_definitions().ok()?;
+        let code = self.module.get_finalized_function(id);
+        // Safe: the function was built with exactly this signature.
+        Some(unsafe { std::mem::transmute::<*const u8, BlockFn>(code) })
+    }
+}
+
+// Supported instruction and its internal cycles on top of the opcode fetch.
+fn decode(op: Word) -> Option<(Inst, Long)> {
+    let x = ((op >> 9) & 7) as usize;
+    let y = (op & 7) as usize;
+    let q = if x == 0 { 8 } else { x as Long };
+    match op {
+        0x4e71 => Some((Inst::Nop, 0)),
+        _ if (op & 0xf100) == 0x7000 => Some((Inst::Moveq(x, op as i8 as Long), 0)),
+        _ if (op & 0xf1f8) == 0x2000 => Some((Inst::Move(x, y), 0)),
+        _ if (op & 0xf1f8) == 0xd080 => Some((Inst::Add(x, y), 4)),
+        _ if (op & 0xf1f8) == 0x9080 => Some((Inst::Sub(x, y), 4)),
+        _ if (op & 0xf1f8) == 0x5080 => Some((Inst::Addq(y, q), 4)),
+        _ if (op & 0xf1f8) == 0x5180 => Some((Inst::Subq(y, q), 4)),
+        _ => None,
+    }
+}
+
+fn bit(b: &mut FunctionBuilder, cond: Value, shift: i64) -> Value {
+    let flag = b.ins().uextend(I32, cond);
+    b.ins().ishl_imm(flag, shift)
+}
+
+// N and Z from the result, V and C cleared, X unchanged.
+fn logic_flags(b: &mut FunctionBuilder, sr: Value, res: Value) -> Value {
+    let n = b.ins().ushr_imm(res, 28);
+    let n = b.ins().band_imm(n, 0x08);
+    let z = b.ins().icmp_imm(IntCC::Equal, res, 0);
+    let z = bit(b, z, 2);
+    let sr = b.ins().band_imm(sr, !0x0f);
+    let sr = b.ins().bor(sr, n);
+    b.ins().bor(sr, z)
+}
+
+// XNZVC from the result and the carry/overflow conditions.
+fn arith_flags(b: &mut FunctionBuilder, sr: Value, res: Value, carry: Value, overflow: Value) -> Value {
+    let n = b.ins().ushr_imm(res, 28);
+    let n = b.ins().band_imm(n, 0x08);
+    let z = b.ins().icmp_imm(IntCC::Equal, res, 0);
+    let z = bit(b, z, 2);
+    let v = b.ins().ushr_imm(overflow, 31);
+    let v = b.ins().ishl_imm(v, 1);
+    let c = bit(b, carry, 0);
+    let x = bit(b, carry, 4);
+    let sr = b.ins().band_imm(sr, !0x1f);
+    [n, z, v, c, x].iter().fold(sr, |acc, &flag| b.ins().bor(acc, flag))
+}
+
+fn add(b: &mut FunctionBuilder, sr: Value, dst: Value, src: Value) -> (Value, Value) {
+    let res = b.ins().iadd(dst, src);
+    let carry = b.ins().icmp(IntCC::UnsignedLessThan, res, dst);
+    let t1 = b.ins().bxor(dst, res);
+    let t2 = b.ins().bxor(src, res);
+    let overflow = b.ins().band(t1, t2);
+    (res, arith_flags(b, sr, res, carry, overflow))
+}
+
+fn sub(b: &mut FunctionBuilder, sr: Value, dst: Value, src: Value) -> (Value, Value) {
+    let res = b.ins().isub(dst, src);
+    let carry = b.ins().icmp(IntCC::UnsignedLessThan, dst, src);
+    let t1 = b.ins().bxor(dst, src);
+    let t2 = b.ins().bxor(dst, res);
+    let overflow = b.ins().band(t1, t2);
+    (res, arith_flags(b, sr, res, carry, overflow))
+}
@@ -0,0 +1,17 @@
+mod bus_trait;
+mod cpu;
+mod error;
+mod registers;
+pub mod disasm;
+#[cfg(feature = "jit")]
+mod jit;
+mod opcode;
+mod trace;
+pub mod types;
+mod util;
+
+pub use self::bus_trait::{BusTrait, BusView, InterruptAck, MemoryView};
+pub use self::cpu::{Cpu, CpuModel, ExecHook, LineFHook, MemoryAccess, MemoryObserver, StepInfo};
+pub use self::error::CpuError;
+pub use self::registers::Registers;
+pub use self::trace::{Reg, RegChange, StdoutTrace, TraceEvent, TraceSink};
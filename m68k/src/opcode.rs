@@ -0,0 +1,564 @@
+use super::types::Word;
+
+#[derive(Clone, Copy)]
+pub enum Opcode {
+    Unknown,
+    Nop,                 // nop
+    MoveByte,            // move.b XX, YY
+    MoveLong,            // move.l XX, YY
+    MoveWord,            // move.w XX, YY
+    MoveaLong,           // movea.l XX, Ad
+    MoveaWord,           // movea.w XX, Ad
+    Moveq,               // moveq #%d, D%d
+    Movep,               // movep.w/l (d16, As), Dd / movep.w/l Ds, (d16, Ad)
+    MovemFrom,           // movem.w/l Dx/Dy-Dz/Ai.., YY
+    MovemTo,             // movem.w/l XX, Dx/Dy-Dz/Ai..
+    MoveToSrIm,          // move #$xxxx, SR
+    MoveToSr,            // move XX, SR
+    MoveFromSr,          // move SR, XX
+    MoveFromCcr,         // move CCR, XX (68010~)
+    Movec,               // movec Rc, Rn / movec Rn, Rc (68010~)
+    Moves,               // moves.b/w/l XX, Rn / moves.b/w/l Rn, XX (68010~)
+    MoveToUsp,           // move As, USP
+    MoveFromUsp,         // move USP, Ad
+    LeaDirect,           // lea $xxxxxxxx, Ax
+    LeaOffset,           // lea (xx, As), Ad
+    LeaOffsetD,          // lea (xx, As, Dt), Ad
+    LeaOffsetPc,         // lea (xx, PC), Ad
+    Pea,                 // pea XX
+    ClrByte,             // clr.b xx
+    ClrWord,             // clr.w xx
+    ClrLong,             // clr.l xx
+    Swap,                // swap Dd
+    ExgD,                // exg Dx, Dy
+    ExgA,                // exg Ax, Ay
+    ExgDA,               // exg Dx, Ay
+    CmpByte,             // cmp.b XX, YY
+    CmpWord,             // cmp.w XX, YY
+    CmpLong,             // cmp.l XX, YY
+    CmpiByte,            // cmpi.b #xx, YY
+    CmpiWord,            // cmpi.w #xx, YY
+    CmpiLong,            // cmpi.l #xx, YY
+    CmpaLong,            // cmpa.l XX, Ad
+    CmpmByte,            // cmpm.b (Am)+, (An)+
+    Cmp2Byte,            // cmp2.b XX, Dd
+    Chk,                 // chk.w XX, Dd
+    TstByte,             // tst.b xx
+    TstWord,             // tst.w xx
+    TstLong,             // tst.l xx
+    Btst,                // btst Ds, YY
+    BtstIm,              // btst #xx, YY
+    Bclr,                // bclr Ds, YY
+    BclrIm,              // bclr #xx, YY
+    Bchg,                // bchg Ds, YY
+    BchgIm,              // bchg #xx, YY
+    Bset,                // bset Ds, YY
+    BsetIm,              // bset #xx, YY
+    AddByte,             // add.b XX, Dd
+    AddWord,             // add.w XX, Dd
+    AddLong,             // add.l XX, Dd
+    AddEaByte,           // add.b Ds, YY
+    AddEaWord,           // add.w Ds, YY
+    AddEaLong,           // add.l Ds, YY
+    AddiByte,            // addi.b XX, Dd
+    AddiWord,            // addi.w XX, Dd
+    AddiLong,            // addi.l XX, Dd
+    AddaLong,            // adda.l XX, Ad
+    AddqByte,            // addq.b #%d, D%d
+    AddqWord,            // addq.w #%d, D%d
+    AddqLong,            // addq.l #%d, D%d
+    SubByte,             // sub.b XX, Dd
+    SubWord,             // sub.w XX, Dd
+    SubLong,             // sub.l XX, Dd
+    SubEaByte,           // sub.b Ds, YY
+    SubEaWord,           // sub.w Ds, YY
+    SubEaLong,           // sub.l Ds, YY
+    SubiByte,            // subi.b XX, Dd
+    SubiWord,            // subi.w XX, Dd
+    SubiLong,            // subi.l XX, Dd
+    SubaLong,            // suba.l As, Ad
+    SubqByte,            // subq.b #%d, D%d
+    SubqWord,            // subq.w #%d, D%d
+    SubqLong,            // subq.l #%d, D%d
+    MuluWord,            // mulu.w XX, Dd
+    MulLong,             // mulu.l/muls.l XX, [Dh:]Dl (68020~)
+    DivLong,             // divu.l/divs.l XX, [Dr:]Dq (68020~)
+    Abcd,                // abcd Ds, Dd / abcd -(As), -(Ad)
+    Sbcd,                // sbcd Ds, Dd / sbcd -(As), -(Ad)
+    Nbcd,                // nbcd XX
+    AndByte,             // and.b XX, Dd
+    AndWord,             // and.w XX, Dd
+    AndLong,             // and.l XX, Dd
+    AndEaByte,           // and.b Ds, YY
+    AndEaWord,           // and.w Ds, YY
+    AndEaLong,           // and.l Ds, YY
+    AndiByte,            // andi.b #xx, YY
+    AndiWord,            // andi.w #xx, YY
+    AndiLong,            // andi.l #xx, YY
+    AndiCcr,             // andi.b #xx, CCR
+    AndiSr,              // andi.w #xxxx, SR
+    OrByte,              // or.b XX, Dd
+    OrWord,              // or.w XX, Dd
+    OrLong,              // or.l XX, Dd
+    OrEaByte,            // or.b Ds, YY
+    OrEaWord,            // or.w Ds, YY
+    OrEaLong,            // or.l Ds, YY
+    OriByte,             // ori.b #xx, YY
+    OriWord,             // ori.w #xx, YY
+    OriLong,             // ori.l #xx, YY
+    OriCcr,              // ori.b #xx, CCR
+    OriSr,               // ori.w #xxxx, SR
+    EorByte,             // eor.b XX, Dd
+    EoriByte,            // eori.b #xx, YY
+    EoriWord,            // eori.w #xx, YY
+    EoriLong,            // eori.l #xx, YY
+    EoriCcr,             // eori.b #xx, CCR
+    EoriSr,              // eori.w #xxxx, SR
+    NotByte,             // not.b XX
+    NotWord,             // not.w XX
+    NotLong,             // not.l XX
+    AslImByte,           // asl.b #n, Dd
+    AslImWord,           // asl.w #n, Dd
+    AslImLong,           // asl.l #n, Dd
+    AslByte,             // asl.b Ds, Dd
+    AslWord,             // asl.w Ds, Dd
+    AslLong,             // asl.l Ds, Dd
+    AslMem,              // asl.w XX
+    AsrImByte,           // asr.b #n, Dd
+    AsrImWord,           // asr.w #n, Dd
+    AsrImLong,           // asr.l #n, Dd
+    AsrByte,             // asr.b Ds, Dd
+    AsrWord,             // asr.w Ds, Dd
+    AsrLong,             // asr.l Ds, Dd
+    AsrMem,              // asr.w XX
+    LslImByte,           // lsl.b #n, Dd
+    LslImWord,           // lsl.w #n, Dd
+    LslImLong,           // lsl.l #n, Dd
+    LslByte,             // lsl.b Ds, Dd
+    LslWord,             // lsl.w Ds, Dd
+    LslLong,             // lsl.l Ds, Dd
+    LslMem,              // lsl.w XX
+    LsrImByte,           // lsr.b #n, Dd
+    LsrImWord,           // lsr.w #n, Dd
+    LsrImLong,           // lsr.l #n, Dd
+    LsrByte,             // lsr.b Ds, Dd
+    LsrWord,             // lsr.w Ds, Dd
+    LsrLong,             // lsr.l Ds, Dd
+    LsrMem,              // lsr.w XX
+    RoxlImByte,          // roxl.b #n, Dd
+    RoxlImWord,          // roxl.w #n, Dd
+    RoxlImLong,          // roxl.l #n, Dd
+    RoxlByte,            // roxl.b Ds, Dd
+    RoxlWord,            // roxl.w Ds, Dd
+    RoxlLong,            // roxl.l Ds, Dd
+    RoxlMem,             // roxl.w XX
+    RoxrImByte,          // roxr.b #n, Dd
+    RoxrImWord,          // roxr.w #n, Dd
+    RoxrImLong,          // roxr.l #n, Dd
+    RoxrByte,            // roxr.b Ds, Dd
+    RoxrWord,            // roxr.w Ds, Dd
+    RoxrLong,            // roxr.l Ds, Dd
+    RoxrMem,             // roxr.w XX
+    RolImByte,           // rol.b #n, Dd
+    RolImWord,           // rol.w #n, Dd
+    RolImLong,           // rol.l #n, Dd
+    RolByte,             // rol.b Ds, Dd
+    RolWord,             // rol.w Ds, Dd
+    RolLong,             // rol.l Ds, Dd
+    RolMem,              // rol.w XX
+    RorImByte,           // ror.b #n, Dd
+    RorImWord,           // ror.w #n, Dd
+    RorImLong,           // ror.l #n, Dd
+    RorByte,             // ror.b Ds, Dd
+    RorWord,             // ror.w Ds, Dd
+    RorLong,             // ror.l Ds, Dd
+    RorMem,              // ror.w XX
+    Bftst,               // bftst XX{o:w} (68020~)
+    Bfextu,              // bfextu XX{o:w}, Dn (68020~)
+    Bfchg,               // bfchg XX{o:w} (68020~)
+    Bfexts,              // bfexts XX{o:w}, Dn (68020~)
+    Bfclr,               // bfclr XX{o:w} (68020~)
+    Bfffo,               // bfffo XX{o:w}, Dn (68020~)
+    Bfset,               // bfset XX{o:w} (68020~)
+    Bfins,               // bfins Dn, XX{o:w} (68020~)
+    Cas,                 // cas.b/w/l Dc, Du, XX (68020~)
+    Cas2,                // cas2.w/l Dc1:Dc2, Du1:Du2, (Rn1):(Rn2) (68020~)
+    ExtWord,             // ext.w Dd
+    Bra,                 // bra $xxxx
+    Bhi,                 // bhi $xxxx
+    Bls,                 // bls $xxxx
+    Bcc,                 // bcc $xxxx
+    Bcs,                 // bcs $xxxx
+    Bne,                 // bne $xxxx
+    Beq,                 // beq $xxxx
+    Bvc,                 // bvc $xxxx
+    Bvs,                 // bvs $xxxx
+    Bpl,                 // bpl $xxxx
+    Bmi,                 // bmi $xxxx
+    Bge,                 // bge $xxxx
+    Blt,                 // blt $xxxx
+    Bgt,                 // bgt $xxxx
+    Ble,                 // ble $xxxx
+    Dbcc,                // dbcc Dn, $xxxx
+    Scc,                 // scc XX
+    Bsr,                 // bsr $xxxx
+    Jsr,                 // jsr XX
+    Jmp,                 // jmp XX
+    Rts,                 // rts
+    Rtd,                 // rtd #xx (68010~)
+    Stop,                // stop #xxxx
+    Rte,                 // rte
+    Trap,                // trap #x
+    Reset,               // reset
+    Illegal,             // illegal
+    LineA,               // $Axxx: line 1010 emulator
+    LineF,               // $Fxxx: line 1111 emulator (coprocessor)
+}
+
+pub const OPCODE_COUNT: usize = Opcode::LineF as usize + 1;  // LineF must stay the last variant.
+
+#[derive(Clone, Copy)]
+pub struct Inst {
+    pub op: Opcode,
+}
+
+// Addressing mode sets, one bit each for Dn, An, (An), (An)+, -(An), (d16,An), (d8,An,Xn),
+// abs.w, abs.l, (d16,PC), (d8,PC,Xn) and #imm.
+pub(crate) const EA_ALL: Word = 0x0fff;
+pub(crate) const EA_DATA: Word = 0x0ffd;
+pub(crate) const EA_ALTERABLE: Word = 0x01ff;
+pub(crate) const EA_DATA_ALTERABLE: Word = 0x01fd;
+pub(crate) const EA_MEMORY_ALTERABLE: Word = 0x01fc;
+pub(crate) const EA_CONTROL: Word = 0x07e4;
+pub(crate) const EA_CONTROL_ALTERABLE: Word = 0x01e4;
+pub(crate) const EA_PC_RELATIVE: Word = 0x0600;
+
+pub(crate) const fn ea_allowed(mode: usize, n: Word, modes: Word) -> bool {
+    let index = if mode < 7 { mode as Word } else { 7 + n };
+    index < 12 && (modes & (1 << index)) != 0
+}
+
+// Addressing modes the effective address in the low 6 bits of `op` may use, or None if
+// those bits aren't one. The 68020 additions (PC relative CMPI and TST, TST An) are
+// listed too, and the handlers reject them on earlier models.
+const fn ea_modes(op: Opcode) -> Option<Word> {
+    use Opcode::*;
+    let modes = match op {
+        MoveByte | OrByte | OrWord | OrLong | AndByte | AndWord | AndLong | SubByte | AddByte
+            | CmpByte | Chk | MoveToSr | MuluWord | MulLong | DivLong | Btst | TstByte => EA_DATA,
+        MoveLong | MoveWord | MoveaLong | MoveaWord | SubWord | SubLong | AddWord | AddLong
+            | CmpWord | CmpLong | SubaLong | AddaLong | CmpaLong | TstWord | TstLong => EA_ALL,
+        OriByte | OriWord | OriLong | AndiByte | AndiWord | AndiLong | SubiByte | SubiWord | SubiLong
+            | AddiByte | AddiWord | AddiLong | EoriByte | EoriWord | EoriLong | Bchg | Bclr | Bset
+            | BchgIm | BclrIm | BsetIm | MoveFromSr | MoveFromCcr | ClrByte | ClrWord | ClrLong
+            | NotByte | NotWord | NotLong | Nbcd | Scc | EorByte | AddqByte | SubqByte => EA_DATA_ALTERABLE,
+        CmpiByte | CmpiWord | CmpiLong | BtstIm => EA_DATA_ALTERABLE | EA_PC_RELATIVE,
+        AddqWord | AddqLong | SubqWord | SubqLong => EA_ALTERABLE,
+        OrEaByte | OrEaWord | OrEaLong | AndEaByte | AndEaWord | AndEaLong | SubEaByte | SubEaWord
+            | SubEaLong | AddEaByte | AddEaWord | AddEaLong | Moves | Cas | AslMem | AsrMem | LslMem
+            | LsrMem | RoxlMem | RoxrMem | RolMem | RorMem => EA_MEMORY_ALTERABLE,
+        Pea | Jsr | Jmp => EA_CONTROL,
+        Bftst | Bfextu | Bfexts | Bfffo => EA_CONTROL | 1,
+        Bfchg | Bfclr | Bfset | Bfins => EA_CONTROL_ALTERABLE | 1,
+        _ => return None,
+    };
+    Some(modes)
+}
+
+const fn valid_ea(op: Opcode, word: Word) -> bool {
+    let source_ok = match ea_modes(op) {
+        Some(modes) => ea_allowed(((word >> 3) & 7) as usize, word & 7, modes),
+        None => true,
+    };
+    // MOVE also has its destination in bits 6-11, with mode and register swapped.
+    let destination_ok = match op {
+        Opcode::MoveByte | Opcode::MoveWord | Opcode::MoveLong => {
+            ea_allowed(((word >> 6) & 7) as usize, (word >> 9) & 7, EA_DATA_ALTERABLE)
+        },
+        _ => true,
+    };
+    source_ok && destination_ok
+}
+
+// Register `inst` for every opcode matching `value` on the bits set in `mask`.
+const fn mask_inst(m: &mut [Inst; 0x10000], mask: Word, value: Word, inst: Inst) {
+    let free = !mask;
+    let mut bits: Word = 0;
+    loop {  // Enumerate the subsets of the free bits.
+        m[(value | bits) as usize] = inst;
+        if bits == free {
+            break;
+        }
+        bits = bits.wrapping_sub(free) & free;
+    }
+}
+
+const fn range_inst(m: &mut [Inst; 0x10000], start: Word, end: Word, inst: Inst) {
+    let mut op = start;
+    while op < end {
+        m[op as usize] = inst;
+        op += 1;
+    }
+}
+
+// Decode table indexed by the first instruction word, built at compile time.
+pub(crate) static INST: [Inst; 0x10000] = build_table();
+
+const fn build_table() -> [Inst; 0x10000] {
+    let mut m = [Inst {op: Opcode::Unknown}; 0x10000];
+    mask_inst(&mut m, 0xffc0, 0x0000, Inst {op: Opcode::OriByte});  // 0000-003f
+    mask_inst(&mut m, 0xffc0, 0x0040, Inst {op: Opcode::OriWord});  // 0040-007f
+    mask_inst(&mut m, 0xffc0, 0x0080, Inst {op: Opcode::OriLong});  // 0080-00bf
+    m[0x003c] = Inst {op: Opcode::OriCcr};
+    m[0x007c] = Inst {op: Opcode::OriSr};
+    mask_inst(&mut m, 0xf1c0, 0x0100, Inst {op: Opcode::Btst});  // 0100-013f, 0300-033f, ..., -0f3f
+    mask_inst(&mut m, 0xf1c0, 0x0140, Inst {op: Opcode::Bchg});  // 0140-017f, 0340-037f, ..., -0f7f
+    mask_inst(&mut m, 0xf1c0, 0x0180, Inst {op: Opcode::Bclr});  // 0180-01bf, 0380-03bf, ..., -0fbf
+    mask_inst(&mut m, 0xf1c0, 0x01c0, Inst {op: Opcode::Bset});  // 01c0-01ff, 03c0-03ff, ..., -0fff
+    mask_inst(&mut m, 0xf138, 0x0108, Inst {op: Opcode::Movep});  // 0108-010f, 0148-014f, 0188-018f, 01c8-01cf, ..., -0fcf
+    mask_inst(&mut m, 0xffc0, 0x0200, Inst {op: Opcode::AndiByte});  // 0200-023f
+    mask_inst(&mut m, 0xffc0, 0x0240, Inst {op: Opcode::AndiWord});  // 0240-027f
+    mask_inst(&mut m, 0xffc0, 0x0280, Inst {op: Opcode::AndiLong});  // 0280-02bf
+    m[0x023c] = Inst {op: Opcode::AndiCcr};
+    m[0x027c] = Inst {op: Opcode::AndiSr};
+    mask_inst(&mut m, 0xffc0, 0x0400, Inst {op: Opcode::SubiByte});  // 0400-043f
+    mask_inst(&mut m, 0xffc0, 0x0440, Inst {op: Opcode::SubiWord});  // 0440-047f
+    mask_inst(&mut m, 0xffc0, 0x0480, Inst {op: Opcode::SubiLong});  // 0480-04bf
+    mask_inst(&mut m, 0xffc0, 0x0600, Inst {op: Opcode::AddiByte});  // 0600-063f
+    mask_inst(&mut m, 0xffc0, 0x0640, Inst {op: Opcode::AddiWord});  // 0640-067f
+    mask_inst(&mut m, 0xffc0, 0x0680, Inst {op: Opcode::AddiLong});  // 0680-06bf
+    mask_inst(&mut m, 0xffc0, 0x0800, Inst {op: Opcode::BtstIm});  // 0800-083f
+    mask_inst(&mut m, 0xffc0, 0x0840, Inst {op: Opcode::BchgIm});  // 0840-087f
+    mask_inst(&mut m, 0xffc0, 0x0880, Inst {op: Opcode::BclrIm});  // 0880-08bf
+    mask_inst(&mut m, 0xffc0, 0x08c0, Inst {op: Opcode::BsetIm});  // 08c0-08ff
+    mask_inst(&mut m, 0xffc0, 0x0a00, Inst {op: Opcode::EoriByte});  // 0a00-0a3f
+    mask_inst(&mut m, 0xffc0, 0x0a40, Inst {op: Opcode::EoriWord});  // 0a40-0a7f
+    mask_inst(&mut m, 0xffc0, 0x0a80, Inst {op: Opcode::EoriLong});  // 0a80-0abf
+    m[0x0a3c] = Inst {op: Opcode::EoriCcr};
+    m[0x0a7c] = Inst {op: Opcode::EoriSr};
+    mask_inst(&mut m, 0xffc0, 0x0c00, Inst {op: Opcode::CmpiByte});  // 0c00-0c3f
+    mask_inst(&mut m, 0xffc0, 0x0c40, Inst {op: Opcode::CmpiWord});  // 0c40-0c7f
+    mask_inst(&mut m, 0xffc0, 0x0c80, Inst {op: Opcode::CmpiLong});  // 0c80-0cbf
+    mask_inst(&mut m, 0xffc0, 0x0e00, Inst {op: Opcode::Moves});  // 0e00-0e3f
+    mask_inst(&mut m, 0xffc0, 0x0e40, Inst {op: Opcode::Moves});  // 0e40-0e7f
+    mask_inst(&mut m, 0xffc0, 0x0e80, Inst {op: Opcode::Moves});  // 0e80-0ebf
+    mask_inst(&mut m, 0xffc0, 0x0ac0, Inst {op: Opcode::Cas});  // 0ac0-0aff
+    mask_inst(&mut m, 0xffc0, 0x0cc0, Inst {op: Opcode::Cas});  // 0cc0-0cff
+    mask_inst(&mut m, 0xffc0, 0x0ec0, Inst {op: Opcode::Cas});  // 0ec0-0eff
+    m[0x0cfc] = Inst {op: Opcode::Cas2};
+    m[0x0efc] = Inst {op: Opcode::Cas2};
+    mask_inst(&mut m, 0xf000, 0x1000, Inst {op: Opcode::MoveByte});  // 1000-1fff
+    mask_inst(&mut m, 0xf000, 0x2000, Inst {op: Opcode::MoveLong});  // 2000-2fff
+    mask_inst(&mut m, 0xf000, 0x3000, Inst {op: Opcode::MoveWord});  // 3000-3fff
+    mask_inst(&mut m, 0xf1c0, 0x2040, Inst {op: Opcode::MoveaLong});  // 2040-207f, 2240-227f, ..., -2e7f
+    mask_inst(&mut m, 0xf1c0, 0x3040, Inst {op: Opcode::MoveaWord});  // 3040-307f, 3240-327f, ..., -3e7f
+    mask_inst(&mut m, 0xffc0, 0x40c0, Inst {op: Opcode::MoveFromSr});  // 40c0-40ff
+    mask_inst(&mut m, 0xf1c0, 0x4180, Inst {op: Opcode::Chk});  // 4180-41bf, 4380-43bf, ..., -4fbf
+    mask_inst(&mut m, 0xf1f8, 0x41e8, Inst {op: Opcode::LeaOffset});  // 41e8-41ef, 43e8-43ef, ..., -4fef
+    mask_inst(&mut m, 0xf1f8, 0x41f0, Inst {op: Opcode::LeaOffsetD});  // 41f0-41f7, 43f0-43f7, ..., -4ff7
+    mask_inst(&mut m, 0xf1ff, 0x41f9, Inst {op: Opcode::LeaDirect});  // 41f9, 43f9, ..., 4ff9
+    mask_inst(&mut m, 0xf1ff, 0x41fa, Inst {op: Opcode::LeaOffsetPc});  // 41fa, 43fa, ..., 4ffa
+    m[0x46fc] = Inst {op: Opcode::MoveToSrIm};
+    mask_inst(&mut m, 0xfff8, 0x4e60, Inst {op: Opcode::MoveToUsp});  // 4e60-4e67
+    mask_inst(&mut m, 0xfff8, 0x4e68, Inst {op: Opcode::MoveFromUsp});  // 4e68-4e6f
+    m[0x4e70] = Inst {op: Opcode::Reset};
+    m[0x4e71] = Inst {op: Opcode::Nop};
+    m[0x4e72] = Inst {op: Opcode::Stop};
+    m[0x4e73] = Inst {op: Opcode::Rte};
+    m[0x4e75] = Inst {op: Opcode::Rts};
+    m[0x4e74] = Inst {op: Opcode::Rtd};
+    m[0x4e7a] = Inst {op: Opcode::Movec};
+    m[0x4e7b] = Inst {op: Opcode::Movec};
+    mask_inst(&mut m, 0xffc0, 0x4200, Inst {op: Opcode::ClrByte});  // 4200-423f
+    mask_inst(&mut m, 0xffc0, 0x4240, Inst {op: Opcode::ClrWord});  // 4240-427f
+    mask_inst(&mut m, 0xffc0, 0x4280, Inst {op: Opcode::ClrLong});  // 4280-42bf
+    mask_inst(&mut m, 0xffc0, 0x42c0, Inst {op: Opcode::MoveFromCcr});  // 42c0-42ff
+    mask_inst(&mut m, 0xffc0, 0x4600, Inst {op: Opcode::NotByte});  // 4600-463f
+    mask_inst(&mut m, 0xffc0, 0x4640, Inst {op: Opcode::NotWord});  // 4640-467f
+    mask_inst(&mut m, 0xffc0, 0x4680, Inst {op: Opcode::NotLong});  // 4680-46bf
+    mask_inst(&mut m, 0xffc0, 0x46c0, Inst {op: Opcode::MoveToSr});  // 46c0-46ff
+    mask_inst(&mut m, 0xffc0, 0x4800, Inst {op: Opcode::Nbcd});  // 4800-483f
+    mask_inst(&mut m, 0xffc0, 0x4840, Inst {op: Opcode::Pea});  // 4840-487f
+    mask_inst(&mut m, 0xfff8, 0x4840, Inst {op: Opcode::Swap});  // 4840-4847
+    mask_inst(&mut m, 0xfff8, 0x4880, Inst {op: Opcode::ExtWord});  // 4880-4887
+    mask_inst(&mut m, 0xffb8, 0x4890, Inst {op: Opcode::MovemFrom});  // 4890-4897, 48d0-48d7
+    mask_inst(&mut m, 0xffb8, 0x48a0, Inst {op: Opcode::MovemFrom});  // 48a0-48a7, 48e0-48e7
+    mask_inst(&mut m, 0xffb8, 0x48a8, Inst {op: Opcode::MovemFrom});  // 48a8-48af, 48e8-48ef
+    mask_inst(&mut m, 0xffb8, 0x48b0, Inst {op: Opcode::MovemFrom});  // 48b0-48b7, 48f0-48f7
+    mask_inst(&mut m, 0xffbf, 0x48b8, Inst {op: Opcode::MovemFrom});  // 48b8, 48f8
+    mask_inst(&mut m, 0xffbf, 0x48b9, Inst {op: Opcode::MovemFrom});  // 48b9, 48f9
+    mask_inst(&mut m, 0xffc0, 0x4a00, Inst {op: Opcode::TstByte});  // 4a00-4a3f
+    mask_inst(&mut m, 0xffc0, 0x4a40, Inst {op: Opcode::TstWord});  // 4a40-4a7f
+    mask_inst(&mut m, 0xffc0, 0x4a80, Inst {op: Opcode::TstLong});  // 4a80-4abf
+    m[0x4afc] = Inst {op: Opcode::Illegal};
+    mask_inst(&mut m, 0xffc0, 0x4c00, Inst {op: Opcode::MulLong});  // 4c00-4c3f
+    mask_inst(&mut m, 0xffc0, 0x4c40, Inst {op: Opcode::DivLong});  // 4c40-4c7f
+    mask_inst(&mut m, 0xffb8, 0x4c90, Inst {op: Opcode::MovemTo});  // 4c90-4c97, 4cd0-4cd7
+    mask_inst(&mut m, 0xffb8, 0x4c98, Inst {op: Opcode::MovemTo});  // 4c98-4c9f, 4cd8-4cdf
+    mask_inst(&mut m, 0xffb8, 0x4ca8, Inst {op: Opcode::MovemTo});  // 4ca8-4caf, 4ce8-4cef
+    mask_inst(&mut m, 0xffb8, 0x4cb0, Inst {op: Opcode::MovemTo});  // 4cb0-4cb7, 4cf0-4cf7
+    mask_inst(&mut m, 0xffbf, 0x4cb8, Inst {op: Opcode::MovemTo});  // 4cb8, 4cf8
+    mask_inst(&mut m, 0xffbf, 0x4cb9, Inst {op: Opcode::MovemTo});  // 4cb9, 4cf9
+    mask_inst(&mut m, 0xffbf, 0x4cba, Inst {op: Opcode::MovemTo});  // 4cba, 4cfa
+    mask_inst(&mut m, 0xffbf, 0x4cbb, Inst {op: Opcode::MovemTo});  // 4cbb, 4cfb
+    mask_inst(&mut m, 0xfff0, 0x4e40, Inst {op: Opcode::Trap});  // 4e40-4e4f
+    mask_inst(&mut m, 0xffc0, 0x4e80, Inst {op: Opcode::Jsr});  // 4e80-4ebf
+    mask_inst(&mut m, 0xffc0, 0x4ec0, Inst {op: Opcode::Jmp});  // 4ec0-4eff
+    let mut i = 0;
+    while i < 8 {
+        let o = i * 0x0200;
+        range_inst(&mut m, 0x5000 + o, 0x503a + o, Inst {op: Opcode::AddqByte});  // 5000...5039, 5200...5239, ..., 5e39
+        range_inst(&mut m, 0x5040 + o, 0x507a + o, Inst {op: Opcode::AddqWord});  // 5040...5079, 5240...5279, ..., 5e79
+        range_inst(&mut m, 0x5080 + o, 0x50ba + o, Inst {op: Opcode::AddqLong});  // 5080...50b9, 5280...52b9, ..., 5eb9
+        range_inst(&mut m, 0x5100 + o, 0x513a + o, Inst {op: Opcode::SubqByte});  // 5100...5139, 5300...5339, ..., 5f39
+        range_inst(&mut m, 0x5140 + o, 0x517a + o, Inst {op: Opcode::SubqWord});  // 5140...5179, 5340...5379, ..., 5f79
+        range_inst(&mut m, 0x5180 + o, 0x51ba + o, Inst {op: Opcode::SubqLong});  // 5180...51b9, 5380...53b9, ..., 5fb9
+        i += 1;
+    }
+    mask_inst(&mut m, 0xf0c0, 0x50c0, Inst {op: Opcode::Scc});  // 50c0-50ff, 51c0-51ff, ..., -5fff
+    mask_inst(&mut m, 0xf0f8, 0x50c8, Inst {op: Opcode::Dbcc});  // 50c8-50cf, 51c8-51cf, ..., -5fcf
+    mask_inst(&mut m, 0xff00, 0x6000, Inst {op: Opcode::Bra});  // 6000-60ff
+    mask_inst(&mut m, 0xff00, 0x6100, Inst {op: Opcode::Bsr});  // 6100-61ff
+    mask_inst(&mut m, 0xff00, 0x6200, Inst {op: Opcode::Bhi});  // 6200-62ff
+    mask_inst(&mut m, 0xff00, 0x6300, Inst {op: Opcode::Bls});  // 6300-63ff
+    mask_inst(&mut m, 0xff00, 0x6400, Inst {op: Opcode::Bcc});  // 6400-64ff
+    mask_inst(&mut m, 0xff00, 0x6500, Inst {op: Opcode::Bcs});  // 6500-65ff
+    mask_inst(&mut m, 0xff00, 0x6600, Inst {op: Opcode::Bne});  // 6600-66ff
+    mask_inst(&mut m, 0xff00, 0x6700, Inst {op: Opcode::Beq});  // 6700-67ff
+    mask_inst(&mut m, 0xff00, 0x6800, Inst {op: Opcode::Bvc});  // 6800-68ff
+    mask_inst(&mut m, 0xff00, 0x6900, Inst {op: Opcode::Bvs});  // 6900-69ff
+    mask_inst(&mut m, 0xff00, 0x6a00, Inst {op: Opcode::Bpl});  // 6a00-6aff
+    mask_inst(&mut m, 0xff00, 0x6b00, Inst {op: Opcode::Bmi});  // 6b00-6bff
+    mask_inst(&mut m, 0xff00, 0x6c00, Inst {op: Opcode::Bge});  // 6c00-6cff
+    mask_inst(&mut m, 0xff00, 0x6d00, Inst {op: Opcode::Blt});  // 6d00-6dff
+    mask_inst(&mut m, 0xff00, 0x6e00, Inst {op: Opcode::Bgt});  // 6e00-6eff
+    mask_inst(&mut m, 0xff00, 0x6f00, Inst {op: Opcode::Ble});  // 6f00-6fff
+    mask_inst(&mut m, 0xf100, 0x7000, Inst {op: Opcode::Moveq});  // 7000...70ff, 7200...72ff, ..., 7eff
+    mask_inst(&mut m, 0xf1c0, 0x8000, Inst {op: Opcode::OrByte});  // 8000-803f, 8200-823f, ..., -8e3f
+    mask_inst(&mut m, 0xf1c0, 0x8040, Inst {op: Opcode::OrWord});  // 8040-807f, 8240-827f, ..., -8e7f
+    mask_inst(&mut m, 0xf1c0, 0x8080, Inst {op: Opcode::OrLong});  // 8080-80bf, 8280-82bf, ..., -8ebf
+    mask_inst(&mut m, 0xf1f0, 0x8110, Inst {op: Opcode::OrEaByte});  // 8110-811f, 8310-831f, ..., -8f1f
+    mask_inst(&mut m, 0xf1e0, 0x8120, Inst {op: Opcode::OrEaByte});  // 8120-813f, 8320-833f, ..., -8f3f
+    mask_inst(&mut m, 0xf1f0, 0x8150, Inst {op: Opcode::OrEaWord});  // 8150-815f, 8350-835f, ..., -8f5f
+    mask_inst(&mut m, 0xf1e0, 0x8160, Inst {op: Opcode::OrEaWord});  // 8160-817f, 8360-837f, ..., -8f7f
+    mask_inst(&mut m, 0xf1f0, 0x8190, Inst {op: Opcode::OrEaLong});  // 8190-819f, 8390-839f, ..., -8f9f
+    mask_inst(&mut m, 0xf1e0, 0x81a0, Inst {op: Opcode::OrEaLong});  // 81a0-81bf, 83a0-83bf, ..., -8fbf
+    mask_inst(&mut m, 0xf1f0, 0x8100, Inst {op: Opcode::Sbcd});  // 8100-810f, 8300-830f, ..., -8f0f
+    mask_inst(&mut m, 0xf1c0, 0x9000, Inst {op: Opcode::SubByte});  // 9000-903f, 9200-923f, ..., -9e3f
+    mask_inst(&mut m, 0xf1c0, 0x9040, Inst {op: Opcode::SubWord});  // 9040-907f, 9240-927f, ..., -9e7f
+    mask_inst(&mut m, 0xf1c0, 0x9080, Inst {op: Opcode::SubLong});  // 9080-90bf, 9280-92bf, ..., -9ebf
+    mask_inst(&mut m, 0xf1f0, 0x9110, Inst {op: Opcode::SubEaByte});  // 9110-911f, 9310-931f, ..., -9f1f
+    mask_inst(&mut m, 0xf1e0, 0x9120, Inst {op: Opcode::SubEaByte});  // 9120-913f, 9320-933f, ..., -9f3f
+    mask_inst(&mut m, 0xf1f0, 0x9150, Inst {op: Opcode::SubEaWord});  // 9150-915f, 9350-935f, ..., -9f5f
+    mask_inst(&mut m, 0xf1e0, 0x9160, Inst {op: Opcode::SubEaWord});  // 9160-917f, 9360-937f, ..., -9f7f
+    mask_inst(&mut m, 0xf1f0, 0x9190, Inst {op: Opcode::SubEaLong});  // 9190-919f, 9390-939f, ..., -9f9f
+    mask_inst(&mut m, 0xf1e0, 0x91a0, Inst {op: Opcode::SubEaLong});  // 91a0-91bf, 93a0-93bf, ..., -9fbf
+    mask_inst(&mut m, 0xf1c0, 0x91c0, Inst {op: Opcode::SubaLong});  // 91c0-91ff, 93c0-93ff, ..., -9fff
+    mask_inst(&mut m, 0xfff8, 0x00e8, Inst {op: Opcode::Cmp2Byte});  // 00e8-00ef
+    mask_inst(&mut m, 0xf000, 0xa000, Inst {op: Opcode::LineA});  // a000-afff
+    mask_inst(&mut m, 0xf1c0, 0xb000, Inst {op: Opcode::CmpByte});  // b000-b03f, b200-b23f, ..., be3f
+    mask_inst(&mut m, 0xf1c0, 0xb040, Inst {op: Opcode::CmpWord});  // b040-b07f, b240-b27f, ..., be7f
+    mask_inst(&mut m, 0xf1c0, 0xb080, Inst {op: Opcode::CmpLong});  // b080-b0bf, b280-b2bf, ..., bebf
+    mask_inst(&mut m, 0xf1c0, 0xb100, Inst {op: Opcode::EorByte});  // b100-8000-803f, 8300-833f, ..., -8f3f
+    mask_inst(&mut m, 0xf1f8, 0xb108, Inst {op: Opcode::CmpmByte});  // b108-b10f, b308-b30f, ..., -bf0f
+    mask_inst(&mut m, 0xf1c0, 0xb1c0, Inst {op: Opcode::CmpaLong});  // b1c0-b1ff, b3c0-b3ff, ..., -bfff
+    mask_inst(&mut m, 0xf1c0, 0xc000, Inst {op: Opcode::AndByte});  // c000-c03f, c200-c23f, ..., -ce3f
+    mask_inst(&mut m, 0xf1c0, 0xc040, Inst {op: Opcode::AndWord});  // c040-c07f, c240-c27f, ..., -ce7f
+    mask_inst(&mut m, 0xf1c0, 0xc080, Inst {op: Opcode::AndLong});  // c080-c8bf, c280-c2bf, ..., -cebf
+    mask_inst(&mut m, 0xf1f0, 0xc110, Inst {op: Opcode::AndEaByte});  // c110-c11f, c310-c31f, ..., -cf1f
+    mask_inst(&mut m, 0xf1e0, 0xc120, Inst {op: Opcode::AndEaByte});  // c120-c13f, c320-c33f, ..., -cf3f
+    mask_inst(&mut m, 0xf1f0, 0xc150, Inst {op: Opcode::AndEaWord});  // c150-c15f, c350-c35f, ..., -cf5f
+    mask_inst(&mut m, 0xf1e0, 0xc160, Inst {op: Opcode::AndEaWord});  // c160-c17f, c360-c37f, ..., -cf7f
+    mask_inst(&mut m, 0xf1f0, 0xc190, Inst {op: Opcode::AndEaLong});  // c190-c19f, c390-c39f, ..., -cf9f
+    mask_inst(&mut m, 0xf1e0, 0xc1a0, Inst {op: Opcode::AndEaLong});  // c1a0-c1bf, c3a0-c3bf, ..., -cfbf
+    mask_inst(&mut m, 0xf1c0, 0xc0c0, Inst {op: Opcode::MuluWord});  // c0c0-c0fff, c2c0-c2ff, ..., -ceff
+    mask_inst(&mut m, 0xf1f0, 0xc100, Inst {op: Opcode::Abcd});  // c100-c10f, c300-c30f, ..., -cf0f
+    mask_inst(&mut m, 0xf1f8, 0xc140, Inst {op: Opcode::ExgD});  // c140-c147, c340-c347, ..., -cf47
+    mask_inst(&mut m, 0xf1f8, 0xc148, Inst {op: Opcode::ExgA});  // c148-c14f, c348-c34f, ..., -cf4f
+    mask_inst(&mut m, 0xf1f8, 0xc188, Inst {op: Opcode::ExgDA});  // c188-c18f, c388-c38f, ..., -cf8f
+    mask_inst(&mut m, 0xf1c0, 0xd000, Inst {op: Opcode::AddByte});  // d000-d03f, d200-d23f, ..., -de3f
+    mask_inst(&mut m, 0xf1c0, 0xd040, Inst {op: Opcode::AddWord});  // d040-d07f, d240-d27f, ..., -de7f
+    mask_inst(&mut m, 0xf1c0, 0xd080, Inst {op: Opcode::AddLong});  // d080-d0bf, d280-d2bf, ..., -debf
+    mask_inst(&mut m, 0xf1f0, 0xd110, Inst {op: Opcode::AddEaByte});  // d110-d11f, d310-d31f, ..., -df1f
+    mask_inst(&mut m, 0xf1e0, 0xd120, Inst {op: Opcode::AddEaByte});  // d120-d13f, d320-d33f, ..., -df3f
+    mask_inst(&mut m, 0xf1f0, 0xd150, Inst {op: Opcode::AddEaWord});  // d150-d15f, d350-d35f, ..., -df5f
+    mask_inst(&mut m, 0xf1e0, 0xd160, Inst {op: Opcode::AddEaWord});  // d160-d17f, d360-d37f, ..., -df7f
+    mask_inst(&mut m, 0xf1f0, 0xd190, Inst {op: Opcode::AddEaLong});  // d190-d19f, d390-d39f, ..., -df9f
+    mask_inst(&mut m, 0xf1e0, 0xd1a0, Inst {op: Opcode::AddEaLong});  // d1a0-d1bf, d3a0-d3bf, ..., -dfbf
+    mask_inst(&mut m, 0xf1c0, 0xd1c0, Inst {op: Opcode::AddaLong});  // d1c8, d1c9, d3c8, ..., dfff
+    mask_inst(&mut m, 0xf1f8, 0xe100, Inst {op: Opcode::AslImByte});  // e100-e107, e300-e307, ..., -ef07
+    mask_inst(&mut m, 0xf1f8, 0xe140, Inst {op: Opcode::AslImWord});  // e140-e147, e340-e347, ..., -ef47
+    mask_inst(&mut m, 0xf1f8, 0xe180, Inst {op: Opcode::AslImLong});  // e180-e187, e380-e387, ..., -ef87
+    mask_inst(&mut m, 0xf1f8, 0xe120, Inst {op: Opcode::AslByte});  // e120-e127, e320-e327, ..., -ef27
+    mask_inst(&mut m, 0xf1f8, 0xe160, Inst {op: Opcode::AslWord});  // e160-e167, e360-e367, ..., -ef67
+    mask_inst(&mut m, 0xf1f8, 0xe1a0, Inst {op: Opcode::AslLong});  // e1a0-e1a7, e3a0-e3a7, ..., -efa7
+    mask_inst(&mut m, 0xffc0, 0xe1c0, Inst {op: Opcode::AslMem});  // e1c0-e1ff
+    mask_inst(&mut m, 0xf1f8, 0xe000, Inst {op: Opcode::AsrImByte});  // e000-e007, e200-e207, ..., -ee07
+    mask_inst(&mut m, 0xf1f8, 0xe040, Inst {op: Opcode::AsrImWord});  // e040-e047, e240-e247, ..., -ee47
+    mask_inst(&mut m, 0xf1f8, 0xe080, Inst {op: Opcode::AsrImLong});  // e080-e087, e280-e287, ..., -ee87
+    mask_inst(&mut m, 0xf1f8, 0xe020, Inst {op: Opcode::AsrByte});  // e020-e027, e220-e227, ..., -ee27
+    mask_inst(&mut m, 0xf1f8, 0xe060, Inst {op: Opcode::AsrWord});  // e060-e067, e260-e267, ..., -ee67
+    mask_inst(&mut m, 0xf1f8, 0xe0a0, Inst {op: Opcode::AsrLong});  // e0a0-e0a7, e2a0-e2a7, ..., -eea7
+    mask_inst(&mut m, 0xffc0, 0xe0c0, Inst {op: Opcode::AsrMem});  // e0c0-e0ff
+    mask_inst(&mut m, 0xf1f8, 0xe108, Inst {op: Opcode::LslImByte});  // e108-e10f, e308-e30f, ..., -ef0f
+    mask_inst(&mut m, 0xf1f8, 0xe148, Inst {op: Opcode::LslImWord});  // e148-e14f, e348-e34f, ..., -ef4f
+    mask_inst(&mut m, 0xf1f8, 0xe188, Inst {op: Opcode::LslImLong});  // e188-e18f, e388-e38f, ..., -ef8f
+    mask_inst(&mut m, 0xf1f8, 0xe128, Inst {op: Opcode::LslByte});  // e128-e12f, e328-e32f, ..., -ef2f
+    mask_inst(&mut m, 0xf1f8, 0xe168, Inst {op: Opcode::LslWord});  // e168-e16f, e368-e36f, ..., -ef6f
+    mask_inst(&mut m, 0xf1f8, 0xe1a8, Inst {op: Opcode::LslLong});  // e1a8-e1af, e3a8-e3af, ..., -efaf
+    mask_inst(&mut m, 0xffc0, 0xe3c0, Inst {op: Opcode::LslMem});  // e3c0-e3ff
+    mask_inst(&mut m, 0xf1f8, 0xe008, Inst {op: Opcode::LsrImByte});  // e008-e00f, e208-e20f, ..., -ee0f
+    mask_inst(&mut m, 0xf1f8, 0xe048, Inst {op: Opcode::LsrImWord});  // e048-e04f, e248-e24f, ..., -ee4f
+    mask_inst(&mut m, 0xf1f8, 0xe088, Inst {op: Opcode::LsrImLong});  // e088-e08f, e288-e28f, ..., -ee8f
+    mask_inst(&mut m, 0xf1f8, 0xe028, Inst {op: Opcode::LsrByte});  // e028-e02f, e228-e22f, ..., -ee2f
+    mask_inst(&mut m, 0xf1f8, 0xe068, Inst {op: Opcode::LsrWord});  // e068-e06f, e268-e26f, ..., -ee6f
+    mask_inst(&mut m, 0xf1f8, 0xe0a8, Inst {op: Opcode::LsrLong});  // e0a8-e0af, e2a8-e2af, ..., -eeaf
+    mask_inst(&mut m, 0xffc0, 0xe2c0, Inst {op: Opcode::LsrMem});  // e2c0-e2ff
+    mask_inst(&mut m, 0xf1f8, 0xe110, Inst {op: Opcode::RoxlImByte});  // e110-e117, e310-e317, ..., -ef17
+    mask_inst(&mut m, 0xf1f8, 0xe150, Inst {op: Opcode::RoxlImWord});  // e150-e157, e350-e357, ..., -ef57
+    mask_inst(&mut m, 0xf1f8, 0xe190, Inst {op: Opcode::RoxlImLong});  // e190-e197, e390-e397, ..., -ef97
+    mask_inst(&mut m, 0xf1f8, 0xe130, Inst {op: Opcode::RoxlByte});  // e130-e137, e330-e337, ..., -ef37
+    mask_inst(&mut m, 0xf1f8, 0xe170, Inst {op: Opcode::RoxlWord});  // e170-e177, e370-e377, ..., -ef77
+    mask_inst(&mut m, 0xf1f8, 0xe1b0, Inst {op: Opcode::RoxlLong});  // e1b0-e1b7, e3b0-e3b7, ..., -efb7
+    mask_inst(&mut m, 0xffc0, 0xe5c0, Inst {op: Opcode::RoxlMem});  // e5c0-e5ff
+    mask_inst(&mut m, 0xf1f8, 0xe010, Inst {op: Opcode::RoxrImByte});  // e010-e017, e210-e217, ..., -ee17
+    mask_inst(&mut m, 0xf1f8, 0xe050, Inst {op: Opcode::RoxrImWord});  // e050-e057, e250-e257, ..., -ee57
+    mask_inst(&mut m, 0xf1f8, 0xe090, Inst {op: Opcode::RoxrImLong});  // e090-e097, e290-e297, ..., -ee97
+    mask_inst(&mut m, 0xf1f8, 0xe030, Inst {op: Opcode::RoxrByte});  // e030-e037, e230-e237, ..., -ee37
+    mask_inst(&mut m, 0xf1f8, 0xe070, Inst {op: Opcode::RoxrWord});  // e070-e077, e270-e277, ..., -ee77
+    mask_inst(&mut m, 0xf1f8, 0xe0b0, Inst {op: Opcode::RoxrLong});  // e0b0-e0b7, e2b0-e2b7, ..., -eeb7
+    mask_inst(&mut m, 0xffc0, 0xe4c0, Inst {op: Opcode::RoxrMem});  // e4c0-e4ff
+    mask_inst(&mut m, 0xf1f8, 0xe118, Inst {op: Opcode::RolImByte});  // e118-e11f, e318-e31f, ..., -ef1f
+    mask_inst(&mut m, 0xf1f8, 0xe158, Inst {op: Opcode::RolImWord});  // e158-e15f, e358-e35f, ..., -ef5f
+    mask_inst(&mut m, 0xf1f8, 0xe198, Inst {op: Opcode::RolImLong});  // e198-e19f, e398-e39f, ..., -ef9f
+    mask_inst(&mut m, 0xf1f8, 0xe138, Inst {op: Opcode::RolByte});  // e138-e13f, e338-e33f, ..., -ef3f
+    mask_inst(&mut m, 0xf1f8, 0xe178, Inst {op: Opcode::RolWord});  // e178-e17f, e378-e37f, ..., -ef7f
+    mask_inst(&mut m, 0xf1f8, 0xe1b8, Inst {op: Opcode::RolLong});  // e1b8-e1bf, e3b8-e3bf, ..., -efbf
+    mask_inst(&mut m, 0xffc0, 0xe7c0, Inst {op: Opcode::RolMem});  // e7c0-e7ff
+    mask_inst(&mut m, 0xf1f8, 0xe018, Inst {op: Opcode::RorImByte});  // e018-e01f, e218-e21f, ..., -ee1f
+    mask_inst(&mut m, 0xf1f8, 0xe058, Inst {op: Opcode::RorImWord});  // e058-e05f, e258-e25f, ..., -ee5f
+    mask_inst(&mut m, 0xf1f8, 0xe098, Inst {op: Opcode::RorImLong});  // e098-e09f, e298-e29f, ..., -ee9f
+    mask_inst(&mut m, 0xf1f8, 0xe038, Inst {op: Opcode::RorByte});  // e038-e03f, e238-e23f, ..., -ee3f
+    mask_inst(&mut m, 0xf1f8, 0xe078, Inst {op: Opcode::RorWord});  // e078-e07f, e278-e27f, ..., -ee7f
+    mask_inst(&mut m, 0xf1f8, 0xe0b8, Inst {op: Opcode::RorLong});  // e0b8-e0bf, e2b8-e2bf, ..., -eebf
+    mask_inst(&mut m, 0xffc0, 0xe6c0, Inst {op: Opcode::RorMem});  // e6c0-e6ff
+    mask_inst(&mut m, 0xffc0, 0xe8c0, Inst {op: Opcode::Bftst});  // e8c0-e8ff
+    mask_inst(&mut m, 0xffc0, 0xe9c0, Inst {op: Opcode::Bfextu});  // e9c0-e9ff
+    mask_inst(&mut m, 0xffc0, 0xeac0, Inst {op: Opcode::Bfchg});  // eac0-eaff
+    mask_inst(&mut m, 0xffc0, 0xebc0, Inst {op: Opcode::Bfexts});  // ebc0-ebff
+    mask_inst(&mut m, 0xffc0, 0xecc0, Inst {op: Opcode::Bfclr});  // ecc0-ecff
+    mask_inst(&mut m, 0xffc0, 0xedc0, Inst {op: Opcode::Bfffo});  // edc0-edff
+    mask_inst(&mut m, 0xffc0, 0xeec0, Inst {op: Opcode::Bfset});  // eec0-eeff
+    mask_inst(&mut m, 0xffc0, 0xefc0, Inst {op: Opcode::Bfins});  // efc0-efff
+    mask_inst(&mut m, 0xf000, 0xf000, Inst {op: Opcode::LineF});  // f000-ffff
+
+    // Addressing modes an instruction doesn't allow leave the pattern unknown (illegal).
+    let mut op = 0;
+    while op < 0x10000 {
+        if !valid_ea(m[op].op, op as Word) {
+            m[op] = Inst {op: Opcode::Unknown};
+        }
+        op += 1;
+    }
+    m
+}
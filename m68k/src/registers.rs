@@ -0,0 +1,25 @@
+use serde::{Serialize, Deserialize};
+
+use super::types::{Byte, Word, Long, Adr};
+
+// Also serves as a snapshot of the CPU state, see Cpu::registers.
+#[derive (Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Registers {
+    pub a: [Adr; 8],  // Address registers
+    pub d: [Long; 8],  // Data registers
+    pub pc: Adr,
+    pub sr: Word,
+    pub usp: Adr,  // User stack pointer, while in supervisor mode
+    pub ssp: Adr,  // Supervisor stack pointer, while in user mode
+    pub vbr: Adr,  // Vector base register (68010~)
+    pub sfc: Byte,  // Source function code (68010~)
+    pub dfc: Byte,  // Destination function code (68010~)
+    pub cacr: Long,  // Cache control register (68020~)
+    pub caar: Adr,  // Cache address register (68020~)
+}
+
+impl Registers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Write;
+
+use super::registers::Registers;
+use super::types::{Word, Long, Adr};
+
+// Receives an event for each executed instruction while tracing is enabled.
+// Send so that the CPU can run on its own thread.
+pub trait TraceSink: Send {
+    fn trace(&mut self, event: &TraceEvent);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub pc: Adr,
+    pub words: Vec<Word>,  // Opcode and extension words.
+    pub mnemonic: String,  // Disassembled before execution.
+    pub comment: Option<String>,  // Addresses the operands refer to, see Instruction::annotation.
+    pub regs: Registers,  // Before executing the instruction.
+    pub changes: Vec<RegChange>,  // Registers modified by the instruction, PC excluded.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reg {
+    D(usize),
+    A(usize),
+    Sr,
+    Usp,  // Inactive stack pointers.
+    Ssp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegChange {
+    pub reg: Reg,
+    pub old: Long,
+    pub new: Long,
+}
+
+// Prints one disassembled line per instruction, as the emulator has always done.
+pub struct StdoutTrace;
+
+impl TraceSink for StdoutTrace {
+    fn trace(&mut self, event: &TraceEvent) {
+        let mut line = String::with_capacity(80);
+        write!(line, "{:06x}:", event.pc).unwrap();
+        for i in 0..5 {
+            match event.words.get(i) {
+                Some(word) => write!(line, " {:04x}", word).unwrap(),
+                None => line.push_str("     "),
+            }
+        }
+        match &event.comment {
+            Some(comment) => write!(line, "  {:<32}{}", event.mnemonic, comment).unwrap(),
+            None => write!(line, "  {}", event.mnemonic).unwrap(),
+        }
+        println!("{}", line);
+    }
+}
+
+pub fn diff_registers(old: &Registers, new: &Registers) -> Vec<RegChange> {
+    let mut changes = Vec::new();
+    let mut check = |reg, old, new| {
+        if old != new {
+            changes.push(RegChange { reg, old, new });
+        }
+    };
+    for i in 0..8 {
+        check(Reg::D(i), old.d[i], new.d[i]);
+    }
+    for i in 0..8 {
+        check(Reg::A(i), old.a[i], new.a[i]);
+    }
+    check(Reg::Sr, old.sr as Long, new.sr as Long);
+    check(Reg::Usp, old.usp, new.usp);
+    check(Reg::Ssp, old.ssp, new.ssp);
+    changes
+}
@@ -0,0 +1,182 @@
+use super::bus_trait::BusTrait;
+use super::types::{Byte, Word, Long, SByte, SWord, SLong, Adr};
+
+pub fn get_branch_offset<BusT: BusTrait>(op: Word, bus: &mut BusT, adr: Adr) -> (SLong, u32) {
+    let ofs = op & 0x00ff;
+    match ofs {
+        0 => {
+            (bus.read16(adr) as SWord as SLong, 2)
+        },
+        0xff => {
+            (bus.read32(adr) as SLong, 4)
+        },
+        _ => {
+            (ofs as SByte as SWord as SLong , 0)
+        },
+    }
+}
+
+// Return 0~7 => 8,1~7
+pub fn conv07to18(x: Word) -> Word {
+    ((x & 7).wrapping_sub(1) & 7) + 1
+}
+
+#[test]
+fn test_conv07to18() {
+    assert_eq!(8, conv07to18(0));
+    assert_eq!(1, conv07to18(1));
+    assert_eq!(7, conv07to18(7));
+}
+
+// Decode a brief extension word: returns (displacement, index is An?, index register, long index?).
+// Bits 8~10 (scale and full format on later CPUs) are ignored by the 68000.
+pub fn brief_extension(extension: Word) -> (SLong, bool, usize, bool) {
+    let disp = extension as SByte as SLong;
+    let da = (extension & 0x8000) != 0;
+    let reg = ((extension >> 12) & 7) as usize;
+    let long = (extension & 0x0800) != 0;
+    (disp, da, reg, long)
+}
+
+#[test]
+fn test_brief_extension() {
+    assert_eq!((2, false, 1, false), brief_extension(0x1002));
+    assert_eq!((-4, true, 7, true), brief_extension(0xf8fc));
+    assert_eq!((0, false, 0, false), brief_extension(0x0100));
+}
+
+// Add packed BCD bytes with extend: returns (result, carry, overflow).
+pub fn bcd_add(dst: Byte, src: Byte, x: bool) -> (Byte, bool, bool) {
+    let (dst, src) = (dst as u32, src as u32);
+    let mut res = (src & 0x0f) + (dst & 0x0f) + (x as u32);
+    let v = !res;
+    if res > 9 {
+        res += 6;
+    }
+    res += (src & 0xf0) + (dst & 0xf0);
+    let carry = res > 0x99;
+    if carry {
+        res -= 0xa0;
+    }
+    (res as Byte, carry, (v & res & 0x80) != 0)
+}
+
+// Subtract packed BCD bytes with extend (dst - src - x): returns (result, borrow, overflow).
+pub fn bcd_sub(dst: Byte, src: Byte, x: bool) -> (Byte, bool, bool) {
+    let (dst, src) = (dst as u32, src as u32);
+    let mut res = (dst & 0x0f).wrapping_sub(src & 0x0f).wrapping_sub(x as u32);
+    let v = !res;
+    if res > 9 {
+        res = res.wrapping_sub(6);
+    }
+    res = res.wrapping_add(dst & 0xf0).wrapping_sub(src & 0xf0);
+    let borrow = res > 0x99;
+    if borrow {
+        res = res.wrapping_add(0xa0);
+    }
+    (res as Byte, borrow, (v & res & 0x80) != 0)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShiftKind {
+    As,   // Arithmetic shift
+    Ls,   // Logical shift
+    Rox,  // Rotate with extend
+    Ro,   // Rotate
+}
+
+impl ShiftKind {
+    pub fn from_bits(bits: Word) -> Self {
+        match bits & 3 {
+            0 => ShiftKind::As,
+            1 => ShiftKind::Ls,
+            2 => ShiftKind::Rox,
+            _ => ShiftKind::Ro,
+        }
+    }
+}
+
+// Shift or rotate the low `bits` bits of `val` by `count`:
+// returns (result, carry, overflow, extend).
+pub fn shift(kind: ShiftKind, left: bool, bits: u32, val: Long, count: u32, x: bool) -> (Long, bool, bool, bool) {
+    let mask = (1u64 << bits) - 1;
+    let v = (val as u64) & mask;
+    if count == 0 {
+        return (v as Long, kind == ShiftKind::Rox && x, false, x);
+    }
+    match kind {
+        ShiftKind::As | ShiftKind::Ls => {
+            let (res, carry) = if left {
+                let wide = if count > bits { 0 } else { v << count };
+                (wide & mask, ((wide >> bits) & 1) != 0)
+            } else if kind == ShiftKind::As {
+                let signed = ((v << (64 - bits)) as i64) >> (64 - bits);
+                ((signed >> count.min(63)) as u64 & mask, ((signed >> (count - 1).min(63)) & 1) != 0)
+            } else {
+                let res = if count >= bits { 0 } else { v >> count };
+                (res, count <= bits && ((v >> (count - 1)) & 1) != 0)
+            };
+            // ASL sets V if the most significant bit changes at any time during the shift.
+            let overflow = kind == ShiftKind::As && left && if count >= bits {
+                v != 0
+            } else {
+                let top = (mask >> (bits - 1 - count)) << (bits - 1 - count);
+                (v & top) != 0 && (v & top) != top
+            };
+            (res as Long, carry, overflow, carry)
+        },
+        ShiftKind::Ro => {
+            let n = count % bits;
+            let res = if n == 0 {
+                v
+            } else if left {
+                ((v << n) | (v >> (bits - n))) & mask
+            } else {
+                ((v >> n) | (v << (bits - n))) & mask
+            };
+            let carry = if left { (res & 1) != 0 } else { (res >> (bits - 1)) != 0 };
+            (res as Long, carry, false, x)
+        },
+        ShiftKind::Rox => {
+            let total = bits + 1;
+            let n = count % total;
+            let w = ((x as u64) << bits) | v;
+            let wmask = (1u64 << total) - 1;
+            let r = if n == 0 {
+                w
+            } else if left {
+                ((w << n) | (w >> (total - n))) & wmask
+            } else {
+                ((w >> n) | (w << (total - n))) & wmask
+            };
+            let ext = (r >> bits) != 0;
+            ((r & mask) as Long, ext, false, ext)
+        },
+    }
+}
+
+#[test]
+fn test_shift() {
+    assert_eq!((0x50, true, true, true), shift(ShiftKind::As, true, 8, 0xa8, 1, false));
+    assert_eq!((0xf5, false, false, false), shift(ShiftKind::As, false, 8, 0xea, 1, true));
+    assert_eq!((0xffff, true, false, true), shift(ShiftKind::As, false, 16, 0x8000, 40, false));
+    assert_eq!((0, true, false, true), shift(ShiftKind::Ls, false, 8, 0x80, 8, false));
+    assert_eq!((0, false, false, false), shift(ShiftKind::Ls, true, 8, 0xff, 9, false));
+    assert_eq!((0x0001, true, false, false), shift(ShiftKind::Ro, true, 16, 0x8000, 1, false));
+    assert_eq!((0x80, false, false, false), shift(ShiftKind::Rox, false, 8, 0x00, 1, true));
+    assert_eq!((0x34, true, false, true), shift(ShiftKind::Rox, true, 8, 0x34, 0, true));
+}
+
+#[test]
+fn test_bcd_add() {
+    assert_eq!((0x42, false, false), bcd_add(0x19, 0x23, false));
+    assert_eq!((0x00, true, false), bcd_add(0x99, 0x00, true));
+    assert_eq!((0x10, true, false), bcd_add(0x55, 0x55, false));
+}
+
+#[test]
+fn test_bcd_sub() {
+    assert_eq!((0x19, false, false), bcd_sub(0x42, 0x23, false));
+    assert_eq!((0x99, true, false), bcd_sub(0x00, 0x00, true));
+    assert_eq!((0x90, true, true), bcd_sub(0x10, 0x20, false));
+}
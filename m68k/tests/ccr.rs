@@ -0,0 +1,152 @@
+// Condition codes of the ALU and shift instructions against straightforward
+// reference formulas, over random operands and incoming flags.
+
+use proptest::prelude::*;
+
+use m68k::types::{Adr, Byte, Long, Word};
+use m68k::{BusTrait, Cpu};
+
+const FLAG_C: Word = 1 << 0;
+const FLAG_V: Word = 1 << 1;
+const FLAG_Z: Word = 1 << 2;
+const FLAG_N: Word = 1 << 3;
+const FLAG_X: Word = 1 << 4;
+
+const CODE: Adr = 0x1000;
+
+struct Ram(Vec<Byte>);
+
+impl BusTrait for Ram {
+    fn read16(&self, adr: Adr) -> Word {
+        let i = adr as usize & 0xffff;
+        Word::from_be_bytes([self.0[i], self.0[i + 1]])
+    }
+
+    fn write16(&mut self, adr: Adr, value: Word) {
+        let i = adr as usize & 0xffff;
+        self.0[i..i + 2].copy_from_slice(&value.to_be_bytes());
+    }
+}
+
+// Execute `op` with D0 = src, D1 = dst and the given XNZVC, and return D1 and XNZVC.
+fn exec(op: Word, src: Long, dst: Long, ccr: Word) -> (Long, Word) {
+    let mut ram = Ram(vec![0; 0x10000]);
+    ram.write16(CODE, op);
+    let mut cpu = Cpu::new(ram);
+    cpu.set_sr(0x2700 | ccr);
+    cpu.set_d(0, src);
+    cpu.set_d(1, dst);
+    cpu.set_pc(CODE);
+    cpu.step().unwrap();
+    (cpu.d(1), cpu.sr() & 0x1f)
+}
+
+// Operation size: mask and sign bit of .b/.w/.l.
+struct Size {
+    mask: Long,
+    msb: Long,
+}
+
+fn size(s: Word) -> Size {
+    let bits = 8 << s;
+    Size { mask: (((1u64 << bits) - 1) as Long), msb: 1 << (bits - 1) }
+}
+
+fn nz(sz: &Size, r: Long) -> Word {
+    (if (r & sz.msb) != 0 { FLAG_N } else { 0 }) | (if r == 0 { FLAG_Z } else { 0 })
+}
+
+fn flag(cond: bool, f: Word) -> Word {
+    if cond { f } else { 0 }
+}
+
+// Result written back to D1: the bits above the operation size are kept.
+fn merge(sz: &Size, dst: Long, r: Long) -> Long {
+    (dst & !sz.mask) | r
+}
+
+fn ref_add(sz: &Size, src: Long, dst: Long) -> (Long, Word) {
+    let (s, d) = (src & sz.mask, dst & sz.mask);
+    let r = s.wrapping_add(d) & sz.mask;
+    let c = (s as u64 + d as u64) > sz.mask as u64;
+    let v = ((s ^ r) & (d ^ r) & sz.msb) != 0;
+    (r, nz(sz, r) | flag(v, FLAG_V) | flag(c, FLAG_C | FLAG_X))
+}
+
+fn ref_sub(sz: &Size, src: Long, dst: Long) -> (Long, Word) {
+    let (s, d) = (src & sz.mask, dst & sz.mask);
+    let r = d.wrapping_sub(s) & sz.mask;
+    let c = s > d;
+    let v = ((s ^ d) & (r ^ d) & sz.msb) != 0;
+    (r, nz(sz, r) | flag(v, FLAG_V) | flag(c, FLAG_C | FLAG_X))
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Shift { As, Ls, Rox, Ro }
+
+// One bit at a time, as the manual describes it.
+fn ref_shift(sz: &Size, kind: Shift, left: bool, count: u32, dst: Long, ccr: Word) -> (Long, Word) {
+    let mut v = dst & sz.mask;
+    let mut x = (ccr & FLAG_X) != 0;
+    let mut c = false;
+    let mut overflow = false;
+    for _ in 0..count {
+        let out = if left { (v & sz.msb) != 0 } else { (v & 1) != 0 };
+        let fill = match kind {
+            Shift::As => !left && (v & sz.msb) != 0,
+            Shift::Ls => false,
+            Shift::Rox => x,
+            Shift::Ro => out,
+        };
+        let before = v;
+        v = if left {
+            ((v << 1) & sz.mask) | fill as Long
+        } else {
+            (v >> 1) | if fill { sz.msb } else { 0 }
+        };
+        overflow |= ((before ^ v) & sz.msb) != 0;
+        c = out;
+        if let Shift::As | Shift::Ls | Shift::Rox = kind {
+            x = out;
+        }
+    }
+    if count == 0 {
+        // Rotates through X still copy it to C; nothing else has a carry.
+        c = matches!(kind, Shift::Rox) && x;
+    }
+    let v_flag = matches!(kind, Shift::As) && left && overflow;
+    (v, nz(sz, v) | flag(v_flag, FLAG_V) | flag(c, FLAG_C) | flag(x, FLAG_X))
+}
+
+proptest! {
+    #[test]
+    fn add(s in 0..3u16, src: Long, dst: Long, ccr in 0..32u16) {
+        let sz = size(s);
+        let (r, flags) = ref_add(&sz, src, dst);
+        prop_assert_eq!(exec(0xd200 | s << 6, src, dst, ccr), (merge(&sz, dst, r), flags));
+    }
+
+    #[test]
+    fn sub(s in 0..3u16, src: Long, dst: Long, ccr in 0..32u16) {
+        let sz = size(s);
+        let (r, flags) = ref_sub(&sz, src, dst);
+        prop_assert_eq!(exec(0x9200 | s << 6, src, dst, ccr), (merge(&sz, dst, r), flags));
+    }
+
+    #[test]
+    fn cmp(s in 0..3u16, src: Long, dst: Long, ccr in 0..32u16) {
+        let sz = size(s);
+        let (_, flags) = ref_sub(&sz, src, dst);
+        prop_assert_eq!(exec(0xb200 | s << 6, src, dst, ccr), (dst, (flags & !FLAG_X) | (ccr & FLAG_X)));
+    }
+
+    #[test]
+    fn shift(s in 0..3u16, kind in 0..4u16, left: bool, count in 0..64u32, dst: Long, ccr in 0..32u16) {
+        let sz = size(s);
+        let kinds = [Shift::As, Shift::Ls, Shift::Rox, Shift::Ro];
+        let (r, flags) = ref_shift(&sz, kinds[kind as usize], left, count, dst, ccr);
+        // asd/lsd/roxd/rod.s d0,d1
+        let op = 0xe021 | (left as Word) << 8 | s << 6 | kind << 3;
+        prop_assert_eq!(exec(op, count, dst, ccr), (merge(&sz, dst, r), flags));
+    }
+}
@@ -0,0 +1,259 @@
+// Instruction execution from reset, for regressions that the flag tests don't reach.
+
+use m68k::types::{Adr, Byte, Long, Word};
+use m68k::{BusTrait, Cpu, CpuError, CpuModel};
+
+const STACK: Long = 0x8000;
+const CODE: Adr = 0x1000;
+const DATA: Adr = 0x2000;
+
+struct Ram(Vec<Byte>);
+
+impl BusTrait for Ram {
+    fn read16(&self, adr: Adr) -> Word {
+        let i = adr as usize & 0xffff;
+        Word::from_be_bytes([self.0[i], self.0[i + 1]])
+    }
+
+    fn write16(&mut self, adr: Adr, value: Word) {
+        let i = adr as usize & 0xffff;
+        self.0[i..i + 2].copy_from_slice(&value.to_be_bytes());
+    }
+}
+
+// A CPU just out of reset, about to run `code`.
+fn boot(code: &[Word]) -> Cpu<Ram> {
+    let mut ram = Ram(vec![0; 0x10000]);
+    ram.write32(0, STACK);
+    ram.write32(4, CODE);
+    for (i, &w) in code.iter().enumerate() {
+        ram.write16(CODE + 2 * i as Adr, w);
+    }
+    let mut cpu = Cpu::new(ram);
+    cpu.reset();
+    cpu
+}
+
+#[test]
+fn test_read_modify_write_after_reset() {
+    // not.b $2000.l / not.w $2000.l / addq.l #1,$2000.l
+    for &(code, result, cycles) in &[
+        ([0x4639, 0x0000, 0x2000], 0xed, 20),
+        ([0x4679, 0x0000, 0x2000], 0xedcb, 20),
+        ([0x52b9, 0x0000, 0x2000], 0x12345679, 28),
+    ] {
+        let mut cpu = boot(&code);
+        cpu.bus_mut().write32(DATA, 0x12345678);
+        let info = cpu.step().unwrap();
+        assert_eq!(cpu.pc(), CODE + 6);
+        assert_eq!(info.cycles, cycles);
+        let value = match code[0] & 0x00c0 {
+            0x0000 => cpu.bus().read8(DATA) as Long,
+            0x0040 => cpu.bus().read16(DATA) as Long,
+            _ => cpu.bus().read32(DATA),
+        };
+        assert_eq!(value, result);
+    }
+}
+
+#[test]
+fn test_unimplemented_is_not_illegal() {
+    // link a6,#-4 / neg.l d0 / muls.w d1,d0 / rtr: assigned, so they stop the CPU instead.
+    for code in &[[0x4e56, 0xfffc], [0x4480, 0x4e71], [0xc1c1, 0x4e71], [0x4e77, 0x4e71]] {
+        let mut cpu = boot(code);
+        assert_eq!(cpu.step().unwrap_err(), CpuError::UnknownOpcode { pc: CODE, op: code[0] });
+    }
+
+    // An unassigned pattern (addi with size 3) takes the illegal instruction exception.
+    let mut cpu = boot(&[0x06c0]);
+    cpu.bus_mut().write32(4 * 4, 0x3000);
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), 0x3000);
+}
+
+#[test]
+fn test_invalid_addressing_mode_is_illegal() {
+    // jmp d0 / pea a0 / jsr (a0)+ / jmp -(a0) / chk a0,d0 / move sr,a0 / addq.b #1,a0 /
+    // move.b a0,d0 / move.w d0,#imm / tst.w a0 (68020 only)
+    for &op in &[0x4ec0, 0x4848, 0x4e98, 0x4ee0, 0x4188, 0x40c8, 0x5208, 0x1008, 0x39c0, 0x4a48] {
+        let mut cpu = boot(&[op, 0x4e71]);
+        cpu.bus_mut().write32(4 * 4, 0x3000);
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc(), 0x3000, "{:04x}", op);
+    }
+
+    let mut cpu = boot(&[0x4a48]);
+    cpu.set_model(CpuModel::M68020);
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), CODE + 2);
+}
+
+#[test]
+fn test_predecrement_wraps_around() {
+    // abcd -(a0),-(a1) with A0 at the bottom of the address space.
+    let mut cpu = boot(&[0xc308]);
+    cpu.bus_mut().write8(0xffff, 0x12);
+    cpu.bus_mut().write8(DATA, 0x34);
+    cpu.set_a(0, 0);
+    cpu.set_a(1, DATA + 1);
+    cpu.step().unwrap();
+    assert_eq!(cpu.a(0), 0xffffffff);
+    assert_eq!(cpu.a(1), DATA);
+    assert_eq!(cpu.bus().read8(DATA), 0x46);
+}
+
+#[test]
+fn test_byte_stack_access_keeps_sp_even() {
+    // move.b d0,-(sp) / move.w d0,-(sp) / move.b (sp)+,d1
+    let mut cpu = boot(&[0x1f00, 0x3f00, 0x121f]);
+    cpu.set_d(0, 0x1234);
+    cpu.step().unwrap();
+    assert_eq!(cpu.a(7), STACK - 2);
+    assert_eq!(cpu.bus().read8(STACK - 2), 0x34);
+    cpu.step().unwrap();
+    assert_eq!(cpu.a(7), STACK - 4);
+    cpu.step().unwrap();
+    assert_eq!(cpu.a(7), STACK - 2);
+    assert_eq!(cpu.d(1) & 0xff, 0x12);
+}
+
+#[test]
+fn test_stack_wraps_around() {
+    // move.w d0,-(sp) / pea (a0) / move.l (sp)+,d1 / move.w (sp)+,d2 with SP at 0.
+    let mut cpu = boot(&[0x3f00, 0x4850, 0x221f, 0x341f]);
+    cpu.set_d(0, 0x1234);
+    cpu.set_a(0, 0x00abcdef);
+    cpu.set_a(7, 0);
+    cpu.step().unwrap();
+    assert_eq!(cpu.a(7), 0xfffffffe);
+    cpu.step().unwrap();
+    assert_eq!(cpu.a(7), 0xfffffffa);
+    cpu.step().unwrap();
+    assert_eq!(cpu.a(7), 0xfffffffe);
+    assert_eq!(cpu.d(1), 0x00abcdef);
+    cpu.step().unwrap();
+    assert_eq!(cpu.a(7), 0);
+    assert_eq!(cpu.d(2) & 0xffff, 0x1234);
+}
+
+#[test]
+fn test_exception_frame_wraps_around() {
+    // trap #0 with SSP just above 0, returning with rte.
+    let mut cpu = boot(&[0x4e40]);
+    cpu.bus_mut().write32(32 * 4, 0x3000);
+    cpu.bus_mut().write16(0x3000, 0x4e73);
+    cpu.set_a(7, 2);
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), 0x3000);
+    assert_eq!(cpu.a(7), 0xfffffffc);
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), CODE + 2);
+    assert_eq!(cpu.a(7), 2);
+}
+
+#[test]
+fn test_breakpoint_on_interrupt_handler() {
+    // Stopped at a breakpoint when an interrupt comes in: its handler still stops.
+    let mut cpu = boot(&[0x4e71]);
+    cpu.bus_mut().write32((24 + 4) * 4, 0x3000);
+    cpu.bus_mut().write16(0x3000, 0x4e71);
+    cpu.set_sr(0x2000);
+    cpu.add_breakpoint(CODE);
+    cpu.add_breakpoint(0x3000);
+    assert_eq!(cpu.step().unwrap_err(), CpuError::Breakpoint { pc: CODE });
+    cpu.assert_interrupt(4);
+    assert!(cpu.step().unwrap().exception);
+    assert_eq!(cpu.step().unwrap_err(), CpuError::Breakpoint { pc: 0x3000 });
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), 0x3002);
+}
+
+#[test]
+fn test_ccr_immediate_keeps_unused_bits_clear() {
+    // ori #$ff,ccr / eori #$ff,ccr / ori #$ff,ccr
+    let mut cpu = boot(&[0x003c, 0x00ff, 0x0a3c, 0x00ff, 0x003c, 0x00ff]);
+    cpu.set_sr(0x2700);
+    cpu.step().unwrap();
+    assert_eq!(cpu.sr(), 0x271f);
+    cpu.step().unwrap();
+    assert_eq!(cpu.sr(), 0x2700);
+    cpu.step().unwrap();
+    assert_eq!(cpu.sr(), 0x271f);
+}
+
+// Steps until PC reaches `end`, returning the cycles taken.
+fn run_to(cpu: &mut Cpu<Ram>, end: Adr) -> usize {
+    let mut cycles = 0;
+    while cpu.pc() != end {
+        cycles += cpu.step().unwrap().cycles;
+    }
+    cycles
+}
+
+#[test]
+fn test_decode_cache() {
+    // loop: move.w #$1111,d0 / move.w #$2222,(loop+2).w / addq.w #1,d1 / dbra d2,loop
+    let code = [0x303c, 0x1111, 0x31fc, 0x2222, 0x1002, 0x5241, 0x51ca, 0xfff2];
+    let end = CODE + 2 * code.len() as Adr;
+    let mut results = Vec::new();
+    for &cache in &[false, true] {
+        let mut cpu = boot(&code);
+        cpu.set_decode_cache(cache);
+        cpu.set_d(2, 2);
+        let cycles = run_to(&mut cpu, end);
+        // The write to its immediate operand dropped the first instruction from the cache.
+        assert_eq!(0x2222, cpu.d(0));
+        assert_eq!(3, cpu.d(1));
+        results.push(cycles);
+    }
+    assert_eq!(results[0], results[1]);
+
+    // Changes behind the CPU's back show only once the cache is invalidated.
+    let mut cpu = boot(&[0x303c, 0x1111]);
+    cpu.set_decode_cache(true);
+    cpu.step().unwrap();
+    cpu.bus_mut().write16(CODE + 2, 0x3333);
+    cpu.set_pc(CODE);
+    cpu.step().unwrap();
+    assert_eq!(0x1111, cpu.d(0));
+    cpu.invalidate_decode_cache();
+    cpu.set_pc(CODE);
+    cpu.step().unwrap();
+    assert_eq!(0x3333, cpu.d(0));
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn test_jit_matches_interpreter() {
+    // loop: addq.w #1,(patch).w / patch: moveq #1,d0 / add.l d0,d1 / sub.l d0,d2 / move.l d1,d3 /
+    //       addq.l #2,d4 / subq.l #1,d5 / <last> / dbra d6,loop / stop #$2700
+    // Each pass bumps the immediate of the moveq inside the compiled block, and <last>
+    // leaves its flags at the loop head.
+    for &last in &[0x2601, 0xd280, 0x9480, 0x5385] {
+        let code = [0x5278, 0x1004, 0x7001, 0xd280, 0x9480, 0x2601, 0x5484, 0x5385, last, 0x51ce, 0xffec, 0x4e72, 0x2700];
+        let end = CODE + 2 * code.len() as Adr - 4;
+        let mut runs = Vec::new();
+        for &jit in &[false, true] {
+            let mut cpu = boot(&code);
+            assert_eq!(cpu.set_jit(jit), jit);
+            cpu.set_d(1, 0x7ffffff8);
+            cpu.set_d(2, 4);
+            cpu.set_d(5, 3);
+            cpu.set_d(6, 0x12350003);
+            let mut snapshots = Vec::new();
+            let mut cycles = 0;
+            while cpu.pc() != end {
+                cycles += cpu.run_cycles(1).unwrap();
+                if cpu.pc() == CODE || cpu.pc() == end {
+                    let d: Vec<Long> = (0..8).map(|i| cpu.d(i)).collect();
+                    snapshots.push((d, cpu.sr(), cpu.pc(), cycles));
+                }
+            }
+            runs.push(snapshots);
+        }
+        assert_eq!(runs[0], runs[1], "{:04x}", last);
+        assert_eq!(runs[0].len(), 4);
+        assert_eq!(runs[0][3].0[0], 5);
+        assert_eq!(runs[0][3].0[6], 0x1235ffff);
+    }
+}
@@ -0,0 +1,335 @@
+// Disassembler for X68000 binaries, e.g. the IPL ROM:
+//
+//   x68kdisasm --load=fe0000 --start=ff0010 --count=400 --labels X68BIOSE/IPLROM.DAT
+//
+// Human68k executables (*.X, *.Z) are loaded by their header instead: .X files are
+// relocated to the load address, and the text section is disassembled by default.
+//
+// Options:
+//   --load=ADDR      Address the file is loaded at (hex, default 0, or the base address of a .X file).
+//   --start=ADDR     First address to disassemble (hex, default the load address).
+//   --end=ADDR       Address to stop at (hex, default the end of the file or the text section).
+//   --count=BYTES    Number of bytes to disassemble, instead of --end.
+//   --labels         Label branch targets and refer to them by name.
+//   --flow           Decode only the code reachable from the entry points, following
+//                    branches and calls, and dump the rest as data. Implies --labels.
+//   --entry=ADDR     Entry point for --flow (hex, repeatable, default the start address or
+//                    the entry point of an executable).
+//   --asm            Write source for HAS instead of a listing: labels, no address or hex
+//                    columns, and dc.w for what doesn't assemble back to the same bytes.
+//   --ascii          Show the instruction bytes as text too: ASCII, half-width katakana and
+//                    Shift-JIS double-byte characters, to spot strings and tables.
+//   --iocs=ADDR:NO   The IOCS call number in D0 at the trap #15 at ADDR (both hex), where it
+//                    isn't set by a moveq or move just before.
+//   --symbols=FILE   Names for addresses ("address name" per line).
+//
+// IOCS calls (trap #15) and Human68k DOS calls ($ffxx) are named in a comment.
+//   --output=FILE    Write the listing to FILE instead of stdout.
+
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use x68kemu::{
+    cpu,
+    cpu::MemoryView,
+    cpu::disasm::{Disassembler, Instruction, Operand, SymbolTable},
+    types::{Adr, Byte, Long, Word},
+    x68k::{call_name, hardware_symbols, Executable},
+};
+
+const MAX_DUMP_WORDS: usize = 5;
+const TEXT_WIDTH: usize = MAX_DUMP_WORDS * 2 + 1;  // One more for a character continued on the next line.
+
+// The file mapped at its load address; nothing outside of it can be read.
+struct FileMemory {
+    data: Vec<Byte>,
+    load_address: Adr,
+}
+
+impl FileMemory {
+    fn peek8(&self, adr: Adr) -> Option<Byte> {
+        let i = adr.checked_sub(self.load_address)? as usize;
+        self.data.get(i).copied()
+    }
+}
+
+impl MemoryView for FileMemory {
+    fn peek16(&self, adr: Adr) -> Option<Word> {
+        let i = adr.checked_sub(self.load_address)? as usize;
+        self.data.get(i..i + 2).map(|bytes| Word::from_be_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+struct Options {
+    input: String,
+    output: Option<String>,
+    load: Option<Adr>,
+    start: Option<Adr>,
+    end: Option<Adr>,
+    count: Option<Adr>,
+    labels: bool,
+    flow: bool,
+    entries: Vec<Adr>,
+    asm: bool,
+    ascii: bool,
+    iocs: Vec<(Adr, Long)>,
+    symbols: Option<String>,
+}
+
+const USAGE: &str = "Usage: x68kdisasm [--load=ADDR] [--start=ADDR] [--end=ADDR | --count=BYTES] [--labels] [--flow [--entry=ADDR]...] [--asm] [--ascii] [--iocs=ADDR:NO]... [--symbols=FILE] [--output=FILE] <file>";
+
+fn parse_hex(s: &str) -> Result<Adr, String> {
+    let hex = s.trim_start_matches('$').trim_start_matches("0x");
+    Adr::from_str_radix(hex, 16).map_err(|_| format!("Bad address: {}", s))
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        input: String::new(), output: None, load: None, start: None, end: None, count: None, labels: false, flow: false, entries: Vec::new(), asm: false, ascii: false, iocs: Vec::new(), symbols: None,
+    };
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("--load=") {
+            options.load = Some(parse_hex(value)?);
+        } else if let Some(value) = arg.strip_prefix("--start=") {
+            options.start = Some(parse_hex(value)?);
+        } else if let Some(value) = arg.strip_prefix("--end=") {
+            options.end = Some(parse_hex(value)?);
+        } else if let Some(value) = arg.strip_prefix("--count=") {
+            options.count = Some(value.parse().map_err(|_| format!("Bad count: {}", value))?);
+        } else if let Some(value) = arg.strip_prefix("--entry=") {
+            options.entries.push(parse_hex(value)?);
+        } else if let Some(value) = arg.strip_prefix("--iocs=") {
+            let (adr, no) = value.split_once(':').ok_or_else(|| format!("Bad IOCS hint: {}", value))?;
+            options.iocs.push((parse_hex(adr)?, parse_hex(no)?));
+        } else if let Some(value) = arg.strip_prefix("--symbols=") {
+            options.symbols = Some(value.to_string());
+        } else if let Some(value) = arg.strip_prefix("--output=") {
+            options.output = Some(value.to_string());
+        } else if arg == "--labels" {
+            options.labels = true;
+        } else if arg == "--flow" {
+            options.flow = true;
+        } else if arg == "--asm" {
+            options.asm = true;
+        } else if arg == "--ascii" {
+            options.ascii = true;
+        } else if arg.starts_with("--") || !options.input.is_empty() {
+            return Err(USAGE.to_string());
+        } else {
+            options.input = arg.clone();
+        }
+    }
+    if options.input.is_empty() || (options.end.is_some() && options.count.is_some()) {
+        return Err(USAGE.to_string());
+    }
+    Ok(options)
+}
+
+// Executables are recognized by their extension: a raw ROM may well start with the same
+// bra.s as a .Z header.
+fn extension(path: &str) -> Option<String> {
+    Path::new(path).extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase())
+}
+
+fn dump_mem(mem: &FileMemory, adr: Adr, sz: usize) -> String {
+    let arr = (0..MAX_DUMP_WORDS).map(|i| {
+        if i * 2 < sz {
+            mem.peek16(adr + (i as u32) * 2).map_or_else(|| String::from("????"), |word| format!("{:04x}", word))
+        } else {
+            String::from("    ")
+        }
+    });
+    arr.collect::<Vec<String>>().join(" ")
+}
+
+fn is_sjis_lead(b: Byte) -> bool {
+    matches!(b, 0x81..=0x9f | 0xe0..=0xfc)
+}
+
+fn is_sjis_trail(b: Byte) -> bool {
+    matches!(b, 0x40..=0x7e | 0x80..=0xfc)
+}
+
+// Text column for the bytes in the dump. A double-byte character whose trail byte is on the
+// next line is shown on this one, and `carry` tells the next line to skip that byte.
+fn text_column(mem: &FileMemory, adr: Adr, sz: usize, carry: &mut bool) -> String {
+    let sz = sz.min(MAX_DUMP_WORDS * 2);
+    let mut text = String::new();
+    let mut width = 0;
+    let mut i = 0;
+    if *carry {
+        text.push(' ');
+        width += 1;
+        i += 1;
+        *carry = false;
+    }
+    while i < sz {
+        let adr = adr + i as Adr;
+        let (c, len) = match mem.peek8(adr) {
+            Some(b @ 0x20..=0x7e) => (Some((b as char).to_string()), 1),
+            Some(b @ 0xa1..=0xdf) => (char::from_u32(0xff61 + (b - 0xa1) as u32).map(String::from), 1),
+            Some(lead) if is_sjis_lead(lead) => match mem.peek8(adr + 1) {
+                Some(trail) if is_sjis_trail(trail) => {
+                    let bytes = [lead, trail];
+                    let decoded = encoding_rs::SHIFT_JIS.decode_without_bom_handling_and_without_replacement(&bytes);
+                    (decoded.map(|s| s.into_owned()), 2)
+                },
+                _ => (None, 1),
+            },
+            _ => (None, 1),
+        };
+        match c {
+            Some(c) => {
+                text += &c;
+                width += len;
+                i += len;
+            },
+            None => {
+                text.push('.');
+                width += 1;
+                i += 1;
+            },
+        }
+    }
+    *carry = i > sz;
+    text + &" ".repeat(TEXT_WIDTH.saturating_sub(width))
+}
+
+// The value of D0 after `inst`, as far as it can be told from the instruction alone.
+fn track_d0(d0: Option<Long>, inst: &Instruction) -> Option<Long> {
+    fn writes_d0(operand: &Operand) -> bool {
+        match operand {
+            Operand::DataReg(0) => true,
+            Operand::Pair(high, low) => writes_d0(high) || writes_d0(low),
+            Operand::RegList(mask) => (mask & 1) != 0,
+            _ => false,
+        }
+    }
+
+    match (inst.mnemonic, inst.operands.as_slice()) {
+        ("moveq", [Operand::SignedImmediate(value), Operand::DataReg(0)]) => Some(*value as Long),
+        ("move", [Operand::Immediate(value), Operand::DataReg(0)]) => Some(*value),
+        // Calls and traps may return anything in D0.
+        ("bsr" | "jsr" | "trap", _) => None,
+        ("exg", operands) if operands.iter().any(writes_d0) => None,
+        (_, [.., last]) if writes_d0(last) => None,
+        _ => d0,
+    }
+}
+
+// The addresses of PC-relative operands, or the name of the call made, in a comment after
+// the instruction.
+fn annotate(text: &str, inst: &Instruction, call: Option<&str>) -> String {
+    match inst.annotation(None).or_else(|| call.map(|name| format!("; {}", name))) {
+        Some(comment) => format!("{:<32}{}", text, comment),
+        None => text.to_string(),
+    }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    let file = fs::read(&options.input)?;
+
+    // Hardware register names, then the executable's own symbols and --symbols on top.
+    let mut symbols = hardware_symbols();
+    let exe = match extension(&options.input).as_deref() {
+        Some("x") => Some(Executable::parse_x(&file, options.load)?),
+        Some("z") => Some(Executable::parse_z(&file)?),
+        _ => None,
+    };
+    let (data, load, default_end, exe_entry) = match exe {
+        Some(exe) => {
+            symbols.merge(exe.symbols);
+            (exe.image, exe.load_address, exe.load_address + exe.text_size as Adr, Some(exe.entry))
+        },
+        None => {
+            let load = options.load.unwrap_or(0);
+            let end = load + file.len() as Adr;
+            (file, load, end, None)
+        },
+    };
+    let start = options.start.unwrap_or(load);
+    let end = match (options.end, options.count) {
+        (Some(end), _) => end,
+        (_, Some(count)) => start + count,
+        _ => default_end,
+    };
+
+    if let Some(path) = &options.symbols {
+        symbols.merge(SymbolTable::parse(&fs::read_to_string(path)?)?);
+    }
+
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    let mem = FileMemory { data, load_address: load };
+    let lines = if options.flow {
+        let entries = match exe_entry {
+            _ if !options.entries.is_empty() => options.entries.clone(),
+            Some(entry) if options.start.is_none() => vec![entry],
+            _ => vec![start],
+        };
+        Some(cpu::disasm::flow_listing(&mem, start, end, &entries, &symbols))
+    } else if options.labels || options.asm {
+        Some(cpu::disasm::listing(&mem, start, end, &symbols))
+    } else {
+        None
+    };
+    // D0 as set up for the next trap #15, from the instructions before it or a --iocs hint.
+    let mut d0 = None;
+    let mut call = |adr: Adr, inst: &Instruction, label: bool| {
+        if label {
+            d0 = None;  // Can be reached from elsewhere.
+        }
+        if let Some(&(_, no)) = options.iocs.iter().find(|&&(hint, _)| hint == adr) {
+            d0 = Some(no);
+        }
+        let name = mem.peek16(adr).filter(|_| inst.length == 2).and_then(|op| call_name(op, d0));
+        d0 = track_d0(d0, inst);
+        name
+    };
+    // Address, hex dump and, with --ascii, the text column before each instruction.
+    let mut carry = false;
+    let mut columns = |adr: Adr, sz: usize| {
+        if options.ascii {
+            format!("{:06x}: {}  {}", adr, dump_mem(&mem, adr, sz), text_column(&mem, adr, sz, &mut carry))
+        } else {
+            format!("{:06x}: {}", adr, dump_mem(&mem, adr, sz))
+        }
+    };
+    if let Some(lines) = lines {
+        if options.asm {
+            for line in cpu::disasm::assembler_source(&mem, &lines, &symbols) {
+                writeln!(out, "{}", line)?;
+            }
+        } else {
+            for line in lines {
+                if let Some(label) = &line.label {
+                    writeln!(out, "{}:", label)?;
+                }
+                let name = call(line.adr, &line.instruction, line.label.is_some());
+                writeln!(out, "{}  {}", columns(line.adr, line.instruction.length), annotate(&line.text, &line.instruction, name))?;
+            }
+        }
+    } else {
+        for (pc, inst) in Disassembler::new(&mem).iter(start, end) {
+            let text = inst.format_with(&|adr| symbols.get(adr).map(String::from));
+            let name = call(pc, &inst, symbols.get(pc).is_some());
+            writeln!(out, "{}  {}", columns(pc, inst.length), annotate(&text, &inst, name))?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let result: Result<(), Box<dyn Error>> = parse_args(&args).map_err(Into::into).and_then(|options| run(&options));
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -1,9 +0,0 @@
-mod bus_trait;
-mod cpu;
-mod registers;
-pub mod disasm;
-mod opcode;
-mod util;
-
-pub use self::bus_trait::BusTrait;
-pub use self::cpu::Cpu;
@@ -1,3 +1,3 @@
-pub mod cpu;
-pub mod types;
+pub use m68k as cpu;
+pub use m68k::types;
 pub mod x68k;
@@ -1,20 +1,48 @@
+use std::env;
 use std::fs;
 use std::io::ErrorKind;
 
-mod cpu;
-mod types;
-mod x68k;
-
-use self::x68k::X68k;
+use x68kemu::x68k::{open_line, Command, Event, Runner, X68k, STOCK_CLOCK};
 
 const IPLROM_PATH: &str = "X68BIOSE/IPLROM.DAT";
 
 fn main() {
     match fs::read(IPLROM_PATH) {
         Result::Ok(ipl) => {
-            let mut x68k = X68k::new(ipl);
+            // --clock=MHz, e.g. 16 or 24 for a clocked-up machine.
+            let clock = env::args().skip(1)
+                .find_map(|arg| arg.strip_prefix("--clock=").and_then(|mhz| mhz.parse::<u32>().ok()))
+                .map_or(STOCK_CLOCK, |mhz| mhz * 1_000_000);
+            let mut x68k = X68k::new(ipl, clock);
+            x68k.set_trace(env::args().any(|arg| arg == "--trace"));
+            // --wait=VRAM,IO wait states, e.g. to slow a game down to the speed of the real machine.
+            if let Some((vram, io)) = env::args().find_map(|arg| {
+                let (vram, io) = arg.strip_prefix("--wait=")?.split_once(',')?;
+                Some((vram.parse().ok()?, io.parse().ok()?))
+            }) {
+                x68k.set_wait_states(vram, io);
+            }
+            // --rs232c=listen:ADDR, connect:ADDR or pty.
+            if let Some(spec) = env::args().find_map(|arg| arg.strip_prefix("--rs232c=").map(String::from)) {
+                match open_line(&spec) {
+                    Ok((line, description)) => {
+                        eprintln!("RS-232C: {}", description);
+                        x68k.set_serial_line(Some(line));
+                    },
+                    Err(err) => eprintln!("Cannot open RS-232C line {}: {}", spec, err),
+                }
+            }
+            #[cfg(feature = "jit")]
+            if env::args().any(|arg| arg == "--jit") && !x68k.set_jit(true) {
+                eprintln!("JIT not supported on this host, using the interpreter");
+            }
+            let runner = Runner::spawn(x68k);
+            runner.send(Command::Run);
             loop {
-                x68k.update(10000);
+                if let Event::Stopped(err) = runner.wait_event() {
+                    eprintln!("{}", err);
+                    break;
+                }
             }
         },
         Result::Err(err) => {
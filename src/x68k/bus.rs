@@ -1,134 +1,250 @@
 use std::cell::Cell;
 
+use serde::{Serialize, Deserialize};
+
+use super::devices::{DeviceId, Devices, Key, DEVICE_MAP};
+use super::serial::SerialLine;
 use super::vram::Vram;
-use super::super::cpu::BusTrait;
-use super::super::types::{Byte, Adr};
+use super::super::cpu::{BusTrait, InterruptAck, MemoryView};
+use super::super::types::{Byte, Word, Adr};
 
 const RAM_SIZE: usize = 0x200000;
 const SRAM_SIZE: usize = 0x4000;
 
+const PAGE_SHIFT: u32 = 13;  // 8KB pages over the 24-bit address space.
+const PAGE_COUNT: usize = 1 << (24 - PAGE_SHIFT);
+
+// What an 8KB page of the address space is mapped to.
+#[derive(Clone, Copy)]
+enum Page {
+    Ram,
+    GraphicVram,
+    TextVram,
+    Device(DeviceId),
+    Sram,
+    Rom,
+    Stub,  // Device not emulated yet: writes are ignored, reads fault.
+    Unmapped,
+}
+
+fn page_table() -> Vec<Page> {
+    let map = [
+        (0x000000, RAM_SIZE as Adr - 1, Page::Ram),
+        (0xc00000, 0xdfffff, Page::GraphicVram),
+        (0xe00000, 0xe7ffff, Page::TextVram),
+        (0xe80000, 0xecffff, Page::Stub),  // I/O area, devices in DEVICE_MAP on top.
+        (0xed0000, 0xed0000 + SRAM_SIZE as Adr - 1, Page::Sram),
+        (0xed4000, 0xefffff, Page::Stub),
+        (0xfe0000, 0xffffff, Page::Rom),
+    ];
+    let mut pages = vec![Page::Unmapped; PAGE_COUNT];
+    let devices = DEVICE_MAP.iter().map(|&(start, end, id)| (start, end, Page::Device(id)));
+    for (start, end, page) in map.iter().copied().chain(devices) {
+        for entry in &mut pages[(start >> PAGE_SHIFT) as usize..=(end >> PAGE_SHIFT) as usize] {
+            *entry = page;
+        }
+    }
+    pages
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Bus {
     mem: Vec<Byte>,
     sram: Vec<Byte>,
-    ipl: Vec<Byte>,
+    #[serde(skip)]
+    ipl: Vec<Byte>,  // Loaded from the ROM file, not saved.
     booting: Cell<bool>,
+    #[serde(skip)]
+    bus_error: Cell<bool>,
     vram: Vram,
+    devices: Devices,
+    #[serde(skip, default = "page_table")]
+    pages: Vec<Page>,
+    vram_wait: usize,  // Wait states of graphic/text VRAM accesses.
+    io_wait: usize,  // Wait states of I/O area accesses.
 }
 
 impl BusTrait for Bus {
     fn reset(&mut self) {
         self.booting = true.into();
+        self.reset_devices();
+    }
+
+    fn reset_devices(&mut self) {
+        self.devices.reset();
+        self.bus_error.set(false);
     }
 
     fn read8(&self, adr: Adr) -> Byte {
-        if /*0x000000 <= adr &&*/ adr < RAM_SIZE as Adr {
-            if self.booting.get() {
-                self.ipl[(adr + 0x10000) as usize]
-            } else {
-                self.mem[adr as usize]
-            }
-        } else if (0xc00000..=0xdfffff).contains(&adr) {  // Graphic RAM
-            return self.vram.read_graphic(adr - 0xc00000);
-        } else if (0xe00000..=0xe7ffff).contains(&adr) {  // TEXT RAM
-            return self.vram.read_text(adr - 0xe00000);
-        } else if (0xe80000..=0xe80030).contains(&adr) {  // CRTC
-            // TODO: Implement.
-            return 0;
-        } else if (0xe88000..=0xe89fff).contains(&adr) {  // MFP
-            // TODO: Implement.
-            match adr {
-                0xe8802d => 0x80,  // Transmittance Status Register.
-                _ => 0,
-            }
-        } else if (0xe8e000..=0xe8ffff).contains(&adr) {  // I/O port
-            // TODO: Implement.
-            0
-        } else if (0xe94000..=0xe94fff).contains(&adr) {  // Floppy Disk Controller
-            // TODO: Implement.
-            match adr {
-                0xe94001 => {
-                    0xd0  // RQM: Request for Master
+        match self.page(adr) {
+            Page::Ram => {
+                if self.booting.get() {
+                    self.ipl[(adr + 0x10000) as usize]
+                } else {
+                    self.mem[adr as usize]
+                }
+            },
+            Page::GraphicVram => self.vram.read_graphic(adr - 0xc00000),
+            Page::TextVram => self.vram.read_text(adr - 0xe00000),
+            Page::Device(id) => match self.devices.read(id, adr) {
+                Some(value) => value,
+                None => {
+                    self.bus_error.set(true);
+                    0xff
                 },
-                _ => {
-                    0
+            },
+            Page::Sram => self.sram[(adr - 0xed0000) as usize],
+            Page::Rom => {
+                if adr >= 0xff0000 {
+                    self.booting.set(false);
+                }
+                self.ipl[(adr - 0xfe0000) as usize]
+            },
+            _ => {
+                self.bus_error.set(true);
+                0xff
+            },
+        }
+    }
+
+    // RAM and ROM words come straight from the backing store; devices are byte wide.
+    fn read16(&self, adr: Adr) -> Word {
+        if (adr & 1) == 0 {
+            match self.page(adr) {
+                Page::Ram if !self.booting.get() => {
+                    let i = adr as usize;
+                    return Word::from_be_bytes([self.mem[i], self.mem[i + 1]]);
                 },
+                Page::Rom => {
+                    if adr >= 0xff0000 {
+                        self.booting.set(false);
+                    }
+                    let i = (adr - 0xfe0000) as usize;
+                    return Word::from_be_bytes([self.ipl[i], self.ipl[i + 1]]);
+                },
+                _ => {},
             }
-        } else if (0xe96000..=0xe96fff).contains(&adr) {  // SASI
-            0
-        } else if (0xe9c000..=0xe9cfff).contains(&adr) {  // I/O Controller
-            // TODO: Implement.
-            0
-        } else if (0xed0000..0xed0000 + (SRAM_SIZE as Adr)).contains(&adr) {
-            self.sram[(adr - 0xed0000) as usize]
-        } else if (0xfe0000..=0xffffff).contains(&adr) {
-            if adr >= 0xff0000 {
-                self.booting.set(false);
-            }
-            self.ipl[(adr - 0xfe0000) as usize]
+        }
+        ((self.read8(adr) as Word) << 8) | self.read8(adr + 1) as Word
+    }
+
+    fn write16(&mut self, adr: Adr, value: Word) {
+        if (adr & 1) == 0 && matches!(self.page(adr), Page::Ram) {
+            let i = adr as usize;
+            self.mem[i..i + 2].copy_from_slice(&value.to_be_bytes());
         } else {
-            panic!("Illegal address: {:08x}", adr);
+            self.write8(adr, (value >> 8) as Byte);
+            self.write8(adr + 1, value as Byte);
+        }
+    }
+
+    fn take_bus_error(&self) -> bool {
+        self.bus_error.replace(false)
+    }
+
+    fn interrupt_level(&self) -> Word {
+        self.devices.irq()
+    }
+
+    fn acknowledge_interrupt(&mut self, level: Word) -> InterruptAck {
+        match level {
+            7 => InterruptAck::Autovector,  // NMI switch
+            _ => self.devices.acknowledge(level),
         }
     }
 
+    fn wait_states(&self, adr: Adr) -> usize {
+        match self.page(adr) {
+            Page::GraphicVram | Page::TextVram => self.vram_wait,
+            Page::Device(_) | Page::Sram | Page::Stub => self.io_wait,
+            Page::Ram | Page::Rom | Page::Unmapped => 0,
+        }
+    }
+
+    fn advance(&mut self, cycles: usize) {
+        self.devices.tick(cycles);
+    }
+
+    fn next_event(&self) -> Option<usize> {
+        self.devices.next_event()
+    }
+
     fn write8(&mut self, adr: Adr, value: Byte) {
-        if /*0x000000 <= adr &&*/ adr < RAM_SIZE as Adr {
-            self.mem[adr as usize] = value;
-        } else if (0xc00000..=0xdfffff).contains(&adr) {  // Graphic VRAM
-            self.vram.write_graphic(adr - 0xc00000, value);
-        } else if (0xe00000..=0xe7ffff).contains(&adr) {  // TEXT VRAM
-            self.vram.write_text(adr - 0xe00000, value);
-        } else if (0xe80000..=0xe81fff).contains(&adr) {  // CRTC
-            // TODO: Implement.
-        } else if (0xe82000..=0xe83fff).contains(&adr) {  // video
-            // TODO: Implement.
-        } else if (0xe84000..=0xe85fff).contains(&adr) {  // DMAC
-            // TODO: Implement.
-        } else if (0xe86000..=0xe87fff).contains(&adr) {  // AREA set
-            // TODO: Implement.
-        } else if (0xe88000..=0xe89fff).contains(&adr) {  // MFP
-            // TODO: Implement.
-        } else if (0xe8a000..=0xe8bfff).contains(&adr) {  // Printer
-            // TODO: Implement.
-        } else if (0xe8c000..=0xe8dfff).contains(&adr) {  // Sys port
-            // TODO: Implement.
-        } else if (0xe8e000..=0xe8ffff).contains(&adr) {  // I/O port
-            // TODO: Implement.
-        } else if (0xe90000..=0xe91fff).contains(&adr) {  // FM Audio
-            // TODO: Implement.
-        } else if (0xe92000..=0xe93fff).contains(&adr) {  // ADPCM
-            // TODO: Implement.
-        } else if (0xe94000..=0xe95fff).contains(&adr) {  // FDC
-            // TODO: Implement.
-        } else if (0xe96000..=0xe97fff).contains(&adr) {  // HDD
-            // TODO: Implement.
-        } else if (0xe98000..=0xe99fff).contains(&adr) {  // SCC
-            // TODO: Implement.
-        } else if (0xe9a000..=0xe9dfff).contains(&adr) {  // i8255
-            // TODO: Implement.
-        } else if (0xe9e000..=0xe9ffff).contains(&adr) {  // FPU
-            // TODO: Implement.
-        } else if (0xe9a000..=0xeaffff).contains(&adr) {  // SCSI
-            // TODO: Implement.
-        } else if (0xeb0000..=0xecffff).contains(&adr) {  // Sprite
-            // TODO: Implement.
-        } else if (0xed0000..=0xed3fff).contains(&adr) {
-            self.sram[(adr - 0xed0000) as usize] = value;
-        } else if (0xed4000..=0xefffff).contains(&adr) {
-            // TODO: Implement.
-        } else {
-            panic!("Illegal address: {:08x}", adr);
+        match self.page(adr) {
+            Page::Ram => self.mem[adr as usize] = value,
+            Page::GraphicVram => self.vram.write_graphic(adr - 0xc00000, value),
+            Page::TextVram => self.vram.write_text(adr - 0xe00000, value),
+            Page::Sram => self.sram[(adr - 0xed0000) as usize] = value,
+            Page::Device(id) => self.devices.write(id, adr, value),
+            Page::Stub => {},
+            Page::Rom | Page::Unmapped => self.bus_error.set(true),
         }
     }
 }
 
+// Memory only: device registers may react to reads, so they are left unreadable.
+impl MemoryView for Bus {
+    fn peek16(&self, adr: Adr) -> Option<Word> {
+        let peek8 = |adr: Adr| match self.page(adr) {
+            Page::Ram if self.booting.get() => self.ipl.get((adr + 0x10000) as usize).copied(),
+            Page::Ram => self.mem.get(adr as usize).copied(),
+            Page::GraphicVram => Some(self.vram.read_graphic(adr - 0xc00000)),
+            Page::TextVram => Some(self.vram.read_text(adr - 0xe00000)),
+            Page::Sram => self.sram.get((adr - 0xed0000) as usize).copied(),
+            Page::Rom => self.ipl.get((adr - 0xfe0000) as usize).copied(),
+            _ => None,
+        };
+        Some(Word::from_be_bytes([peek8(adr)?, peek8(adr.wrapping_add(1))?]))
+    }
+}
+
 impl Bus {
-    pub fn new(ipl: Vec<Byte>, vram: Vram) -> Self {
+    pub fn new(ipl: Vec<Byte>, vram: Vram, cpu_clock: u32) -> Self {
         Self {
             mem: vec![0; RAM_SIZE],
             sram: vec![0; SRAM_SIZE],
             ipl,
             booting: true.into(),
+            bus_error: false.into(),
             vram,
+            devices: Devices::new(cpu_clock),
+            pages: page_table(),
+            vram_wait: 0,
+            io_wait: 0,
         }
     }
+
+    fn page(&self, adr: Adr) -> Page {
+        self.pages.get((adr >> PAGE_SHIFT) as usize).copied().unwrap_or(Page::Unmapped)
+    }
+
+    // After loading a save state, take the IPL ROM over from the running machine.
+    pub fn take_ipl(&mut self, other: &mut Bus) {
+        self.ipl = std::mem::take(&mut other.ipl);
+    }
+
+    pub fn take_serial_line(&mut self, other: &mut Bus) {
+        self.devices.take_serial_line(&mut other.devices);
+    }
+
+    pub fn set_wait_states(&mut self, vram: usize, io: usize) {
+        self.vram_wait = vram;
+        self.io_wait = io;
+    }
+
+    pub fn key_event(&mut self, key: Key, pressed: bool) {
+        self.devices.key_event(key, pressed);
+    }
+
+    pub fn keyboard_leds(&self) -> Byte {
+        self.devices.keyboard_leds()
+    }
+
+    pub fn mouse_event(&mut self, dx: i32, dy: i32, left: bool, right: bool) {
+        self.devices.mouse_event(dx, dy, left, right);
+    }
+
+    pub fn set_serial_line(&mut self, line: Option<Box<dyn SerialLine>>) {
+        self.devices.set_serial_line(line);
+    }
 }
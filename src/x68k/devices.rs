@@ -0,0 +1,206 @@
+mod crtc;
+mod fdc;
+mod ioc;
+mod keyboard;
+mod mfp;
+mod mouse;
+mod rs232c;
+mod sasi;
+mod scc;
+mod sysport;
+
+use serde::{Serialize, Deserialize};
+
+use self::crtc::Crtc;
+use self::fdc::Fdc;
+use self::ioc::Ioc;
+use self::keyboard::Keyboard;
+use self::mfp::{Mfp, GPIP_VDISP};
+use self::mouse::Mouse;
+use self::rs232c::Rs232c;
+use self::sasi::Sasi;
+use self::scc::Scc;
+use self::sysport::SystemPort;
+use super::serial::SerialLine;
+use super::super::cpu::InterruptAck;
+use super::super::types::{Adr, Byte, Word};
+
+pub use self::keyboard::Key;
+
+// A memory-mapped peripheral. Registers are byte wide and addressed by their offset from
+// the start of the device's mapping.
+pub trait Device {
+    // None faults the access with a bus error. Reads that change the device state, e.g.
+    // clearing a status bit, need interior mutability.
+    fn read(&self, offset: Adr) -> Option<Byte>;
+    fn write(&mut self, offset: Adr, value: Byte);
+
+    // Let the device run for `cycles` CPU clocks.
+    fn tick(&mut self, _cycles: usize) {}
+
+    // Power-on and RESET instruction.
+    fn reset(&mut self) {}
+
+    // Interrupt level requested (1~7), 0 for none.
+    fn irq(&self) -> Word { 0 }
+
+    // Interrupt acknowledge cycle for the level requested by `irq`.
+    fn acknowledge(&mut self) -> InterruptAck { InterruptAck::Autovector }
+
+    // CPU clocks until the device may next change its interrupt request.
+    fn next_event(&self) -> Option<usize> { None }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceId {
+    Crtc,
+    Mfp,
+    SystemPort,
+    Fdc,
+    Sasi,
+    Scc,
+    Ioc,
+}
+
+// First and last address of each device, in 8KB pages of the bus. Among devices requesting
+// the same interrupt level, the earlier one wins.
+pub const DEVICE_MAP: [(Adr, Adr, DeviceId); 7] = [
+    (0xe80000, 0xe81fff, DeviceId::Crtc),
+    (0xe88000, 0xe89fff, DeviceId::Mfp),
+    (0xe8e000, 0xe8ffff, DeviceId::SystemPort),
+    (0xe94000, 0xe95fff, DeviceId::Fdc),
+    (0xe96000, 0xe97fff, DeviceId::Sasi),
+    (0xe98000, 0xe99fff, DeviceId::Scc),
+    (0xe9c000, 0xe9dfff, DeviceId::Ioc),
+];
+
+// The peripherals attached to the bus, and the ones attached to them.
+#[derive(Serialize, Deserialize)]
+pub struct Devices {
+    crtc: Crtc,
+    mfp: Mfp,
+    sysport: SystemPort,
+    fdc: Fdc,
+    sasi: Sasi,
+    scc: Scc,
+    ioc: Ioc,
+    keyboard: Keyboard,  // On the MFP USART.
+    mouse: Mouse,  // On SCC channel B, controlled through the keyboard.
+    rs232c: Rs232c,  // On SCC channel A.
+}
+
+impl Devices {
+    // Devices with timers run on their own clocks, converted from `cpu_clock` (Hz).
+    pub fn new(cpu_clock: u32) -> Self {
+        Self {
+            crtc: Crtc::new(cpu_clock),
+            mfp: Mfp::new(cpu_clock),
+            sysport: SystemPort::default(),
+            fdc: Fdc,
+            sasi: Sasi,
+            scc: Scc::new(cpu_clock),
+            ioc: Ioc,
+            keyboard: Keyboard::new(cpu_clock),
+            mouse: Mouse::new(cpu_clock),
+            rs232c: Rs232c::default(),
+        }
+    }
+
+    pub fn get(&self, id: DeviceId) -> &dyn Device {
+        match id {
+            DeviceId::Crtc => &self.crtc,
+            DeviceId::Mfp => &self.mfp,
+            DeviceId::SystemPort => &self.sysport,
+            DeviceId::Fdc => &self.fdc,
+            DeviceId::Sasi => &self.sasi,
+            DeviceId::Scc => &self.scc,
+            DeviceId::Ioc => &self.ioc,
+        }
+    }
+
+    pub fn get_mut(&mut self, id: DeviceId) -> &mut dyn Device {
+        match id {
+            DeviceId::Crtc => &mut self.crtc,
+            DeviceId::Mfp => &mut self.mfp,
+            DeviceId::SystemPort => &mut self.sysport,
+            DeviceId::Fdc => &mut self.fdc,
+            DeviceId::Sasi => &mut self.sasi,
+            DeviceId::Scc => &mut self.scc,
+            DeviceId::Ioc => &mut self.ioc,
+        }
+    }
+
+    fn base(id: DeviceId) -> Adr {
+        DEVICE_MAP.iter().find(|&&(_, _, d)| d == id).map_or(0, |&(start, _, _)| start)
+    }
+
+    pub fn read(&self, id: DeviceId, adr: Adr) -> Option<Byte> {
+        self.get(id).read(adr - Self::base(id))
+    }
+
+    pub fn write(&mut self, id: DeviceId, adr: Adr, value: Byte) {
+        self.get_mut(id).write(adr - Self::base(id), value);
+    }
+
+    pub fn reset(&mut self) {
+        for &(_, _, id) in DEVICE_MAP.iter() {
+            self.get_mut(id).reset();
+        }
+        self.keyboard.reset();
+        self.mouse.reset();
+        self.rs232c.reset();
+    }
+
+    pub fn tick(&mut self, cycles: usize) {
+        for &(_, _, id) in DEVICE_MAP.iter() {
+            self.get_mut(id).tick(cycles);
+        }
+        self.mfp.set_gpip(GPIP_VDISP, self.crtc.vdisp());
+        self.keyboard.tick(cycles, &mut self.mfp, self.sysport.key_enabled());
+        self.mouse.set_msctrl(self.keyboard.msctrl());
+        self.mouse.tick(cycles, &mut self.scc);
+        self.rs232c.tick(cycles, &mut self.scc);
+    }
+
+    pub fn irq(&self) -> Word {
+        DEVICE_MAP.iter().map(|&(_, _, id)| self.get(id).irq()).max().unwrap_or(0)
+    }
+
+    // Acknowledged by the first device requesting `level`; nobody responding is a spurious interrupt.
+    pub fn acknowledge(&mut self, level: Word) -> InterruptAck {
+        match DEVICE_MAP.iter().find(|&&(_, _, id)| self.get(id).irq() == level) {
+            Some(&(_, _, id)) => self.get_mut(id).acknowledge(),
+            None => InterruptAck::Spurious,
+        }
+    }
+
+    pub fn next_event(&self) -> Option<usize> {
+        DEVICE_MAP.iter().filter_map(|&(_, _, id)| self.get(id).next_event())
+            .chain(self.keyboard.next_event(&self.mfp, self.sysport.key_enabled()))
+            .chain(self.mouse.next_event(&self.scc))
+            .chain(self.rs232c.next_event(&self.scc))
+            .min()
+    }
+
+    pub fn key_event(&mut self, key: Key, pressed: bool) {
+        self.keyboard.key_event(key, pressed);
+    }
+
+    pub fn keyboard_leds(&self) -> Byte {
+        self.keyboard.leds()
+    }
+
+    pub fn mouse_event(&mut self, dx: i32, dy: i32, left: bool, right: bool) {
+        self.mouse.mouse_event(dx, dy, left, right);
+    }
+
+    pub fn set_serial_line(&mut self, line: Option<Box<dyn SerialLine>>) {
+        self.rs232c.set_line(line);
+    }
+
+    // Move the host's serial line over from `other`, e.g. a machine being replaced by a
+    // loaded state.
+    pub fn take_serial_line(&mut self, other: &mut Devices) {
+        self.rs232c.take_line(&mut other.rs232c);
+    }
+}
@@ -0,0 +1,54 @@
+use serde::{Serialize, Deserialize};
+
+use super::Device;
+use super::super::super::types::{Adr, Byte};
+
+// Frame timing of the 768x512 31kHz mode set up by the IPL. The CRTC registers don't change it yet.
+const HSYNC_FREQ: u64 = 31_500;  // Hz
+const TOTAL_LINES: u64 = 568;
+const DISPLAY_LINES: u64 = 512;
+
+// CRTC at $e80000: R00~R23 and the operation port.
+#[derive(Serialize, Deserialize)]
+pub struct Crtc {
+    frame_cycles: u64,  // CPU clocks per frame.
+    display_cycles: u64,  // CPU clocks of the vertical display period, at the start of a frame.
+    position: u64,  // CPU clocks since the start of the frame.
+}
+
+impl Crtc {
+    pub fn new(cpu_clock: u32) -> Self {
+        let cpu_clock = cpu_clock as u64;
+        Self {
+            frame_cycles: cpu_clock * TOTAL_LINES / HSYNC_FREQ,
+            display_cycles: cpu_clock * DISPLAY_LINES / HSYNC_FREQ,
+            position: 0,
+        }
+    }
+
+    // V-DISP: high in the vertical display period, low in the blanking.
+    pub fn vdisp(&self) -> bool {
+        self.position < self.display_cycles
+    }
+}
+
+impl Device for Crtc {
+    fn read(&self, offset: Adr) -> Option<Byte> {
+        // TODO: Implement.
+        if offset <= 0x30 { Some(0) } else { None }
+    }
+
+    fn write(&mut self, _offset: Adr, _value: Byte) {
+        // TODO: Implement.
+    }
+
+    fn tick(&mut self, cycles: usize) {
+        self.position = (self.position + cycles as u64) % self.frame_cycles;
+    }
+
+    // V-DISP changes, as seen by the MFP.
+    fn next_event(&self) -> Option<usize> {
+        let edge = if self.vdisp() { self.display_cycles } else { self.frame_cycles };
+        Some((edge - self.position) as usize)
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Serialize, Deserialize};
+
+use super::Device;
+use super::super::super::types::{Adr, Byte};
+
+const STATUS: Adr = 0x01;
+
+// uPD72065 floppy disk controller and drive control at $e94000.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Fdc;
+
+impl Device for Fdc {
+    fn read(&self, offset: Adr) -> Option<Byte> {
+        // TODO: Implement.
+        match offset {
+            STATUS => Some(0xd0),  // RQM: Request for Master
+            _ if offset <= 0xfff => Some(0),
+            _ => None,
+        }
+    }
+
+    fn write(&mut self, _offset: Adr, _value: Byte) {
+        // TODO: Implement.
+    }
+}
@@ -0,0 +1,19 @@
+use serde::{Serialize, Deserialize};
+
+use super::Device;
+use super::super::super::types::{Adr, Byte};
+
+// I/O controller at $e9c000: interrupt status and vector of the FDC, FDD, HDD and printer.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Ioc;
+
+impl Device for Ioc {
+    fn read(&self, offset: Adr) -> Option<Byte> {
+        // TODO: Implement.
+        if offset <= 0xfff { Some(0) } else { None }
+    }
+
+    fn write(&mut self, _offset: Adr, _value: Byte) {
+        // TODO: Implement.
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+
+use serde::{Serialize, Deserialize};
+
+use super::mfp::Mfp;
+use super::super::super::types::Byte;
+
+const BAUD_RATE: u32 = 2400;
+const BITS_PER_BYTE: u32 = 10;  // Start, 8 data and stop bits.
+const QUEUE_SIZE: usize = 32;  // Scancodes held while the machine isn't reading them.
+const BREAK: Byte = 0x80;  // Set in the scancode of a released key.
+
+// Keys of the X68000 keyboard, by their scancode. Frontends map host keys to these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Esc = 0x01,
+    Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9, Num0,
+    Minus, Caret, Yen, BackSpace,
+    Tab = 0x10,
+    Q, W, E, R, T, Y, U, I, O, P,
+    At, LeftBracket, Return,
+    A = 0x1e,
+    S, D, F, G, H, J, K, L,
+    Semicolon, Colon, RightBracket,
+    Z = 0x2a,
+    X, C, V, B, N, M,
+    Comma, Period, Slash, Underscore,
+    Space = 0x35,
+    Home, Del, RollUp, RollDown, Undo,
+    Left = 0x3b,
+    Up, Right, Down,
+    Clr = 0x3f,
+    PadDivide, PadMultiply, PadMinus,
+    Pad7, Pad8, Pad9, PadPlus,
+    Pad4, Pad5, Pad6, PadEqual,
+    Pad1, Pad2, Pad3, Enter,
+    Pad0, PadComma, PadPeriod,
+    Kigou = 0x52,  // 記号入力
+    Touroku,  // 登録
+    Help,
+    Xf1, Xf2, Xf3, Xf4, Xf5,
+    Kana = 0x5a,
+    Romaji, Code, Caps, Ins, Hiragana, Zenkaku,
+    Break = 0x61,
+    Copy,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10,
+    Shift = 0x70,
+    Ctrl, Opt1, Opt2,
+}
+
+// Default repeat settings, as the keyboard powers on.
+const REPEAT_DELAY: usize = 500;  // ms
+const REPEAT_INTERVAL: usize = 110;  // ms
+
+// The keyboard sends make and break codes to the MFP USART, one byte per serial frame.
+// Codes are held while the receiver isn't ready, so that nothing is lost to an overrun.
+// The last key pressed repeats, timed by the keyboard itself: repeated presses from the
+// host are ignored.
+#[derive(Serialize, Deserialize)]
+pub struct Keyboard {
+    byte_cycles: usize,  // CPU clocks to send one byte.
+    ms_cycles: usize,  // CPU clocks in a millisecond.
+    queue: VecDeque<Byte>,
+    wait: usize,  // Clocks until the byte being sent arrives.
+    repeating: Option<Byte>,  // Scancode of the key held down.
+    repeat_wait: usize,  // Clocks until it repeats.
+    repeat_delay: usize,  // ms
+    repeat_interval: usize,  // ms
+    leds: Byte,  // Lit LEDs, see X68k::keyboard_leds.
+    msctrl: bool,  // Output to the mouse, requesting a packet on the falling edge.
+}
+
+impl Keyboard {
+    pub fn new(cpu_clock: u32) -> Self {
+        Self {
+            byte_cycles: (cpu_clock / (BAUD_RATE / BITS_PER_BYTE)) as usize,
+            ms_cycles: (cpu_clock / 1000) as usize,
+            queue: VecDeque::new(),
+            wait: 0,
+            repeating: None,
+            repeat_wait: 0,
+            repeat_delay: REPEAT_DELAY,
+            repeat_interval: REPEAT_INTERVAL,
+            leds: 0,
+            msctrl: true,
+        }
+    }
+
+    // Dropped if the queue is full.
+    fn send(&mut self, code: Byte) {
+        if self.queue.len() >= QUEUE_SIZE {
+            return;
+        }
+        if self.queue.is_empty() {
+            self.wait = self.byte_cycles;
+        }
+        self.queue.push_back(code);
+    }
+
+    // Key pressed or released.
+    pub fn key_event(&mut self, key: Key, pressed: bool) {
+        let code = key as Byte;
+        if pressed {
+            if self.repeating == Some(code) {
+                return;
+            }
+            self.send(code);
+            if repeats(key) {
+                self.repeating = Some(code);
+                self.repeat_wait = self.repeat_delay * self.ms_cycles;
+            }
+        } else {
+            if self.repeating == Some(code) {
+                self.repeating = None;
+            }
+            self.send(code | BREAK);
+        }
+    }
+
+    // Bytes sent to the keyboard by the machine.
+    fn command(&mut self, value: Byte) {
+        match value {
+            0x00..=0x1f => {},  // TV control, sent on to the display by infrared.
+            0x40..=0x41 => self.msctrl = (value & 1) != 0,
+            0x60..=0x6f => self.repeat_delay = 200 + 100 * (value & 0x0f) as usize,
+            0x70..=0x7f => {
+                let n = (value & 0x0f) as usize;
+                self.repeat_interval = 30 + 5 * n * n;
+            },
+            0x80..=0xff => self.leds = !value & 0x7f,  // A cleared bit lights the LED.
+            _ => {},
+        }
+    }
+
+    // Key data is sent only while `enabled` by the system port.
+    pub fn tick(&mut self, cycles: usize, mfp: &mut Mfp, enabled: bool) {
+        if let Some(value) = mfp.take_transmitted() {
+            self.command(value);
+        }
+        if !self.queue.is_empty() && enabled {
+            self.wait = self.wait.saturating_sub(cycles);
+            if self.wait == 0 && mfp.ready_to_receive() {
+                if let Some(code) = self.queue.pop_front() {
+                    mfp.receive(code);
+                }
+                self.wait = self.byte_cycles;
+            }
+        }
+        if let Some(code) = self.repeating {
+            self.repeat_wait = self.repeat_wait.saturating_sub(cycles);
+            if self.repeat_wait == 0 {
+                // Not piling up behind codes the machine hasn't read.
+                if self.queue.is_empty() {
+                    self.send(code);
+                }
+                self.repeat_wait = self.repeat_interval * self.ms_cycles;
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.queue.clear();
+        self.repeating = None;
+    }
+
+    // Clocks until the next byte arrives or the held key repeats, unless waiting for the
+    // machine to read the last byte or to enable the keyboard.
+    pub fn next_event(&self, mfp: &Mfp, enabled: bool) -> Option<usize> {
+        let send = match self.wait {
+            _ if self.queue.is_empty() || !enabled => None,
+            0 if !mfp.ready_to_receive() => None,
+            wait => Some(wait.max(1)),
+        };
+        let repeat = self.repeating.map(|_| self.repeat_wait.max(1));
+        send.into_iter().chain(repeat).min()
+    }
+
+    pub fn leds(&self) -> Byte {
+        self.leds
+    }
+
+    pub fn msctrl(&self) -> bool {
+        self.msctrl
+    }
+}
+
+// Modifiers and the keys with an LED don't repeat.
+fn repeats(key: Key) -> bool {
+    !matches!(key, Key::Shift | Key::Ctrl | Key::Opt1 | Key::Opt2
+        | Key::Kana | Key::Romaji | Key::Code | Key::Caps | Key::Ins | Key::Hiragana | Key::Zenkaku)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Device;
+    use super::super::super::super::types::Adr;
+
+    const CPU_CLOCK: u32 = 10_000_000;
+    const BYTE_CYCLES: usize = 41666;  // 2400 baud
+    // MFP USART registers.
+    const RSR: Adr = 0x2b;
+    const UDR: Adr = 0x2f;
+
+    fn setup() -> (Keyboard, Mfp) {
+        let mut mfp = Mfp::new(CPU_CLOCK);
+        mfp.write(RSR, 0x01);
+        (Keyboard::new(CPU_CLOCK), mfp)
+    }
+
+    #[test]
+    fn test_byte_pacing() {
+        let (mut keyboard, mut mfp) = setup();
+        keyboard.key_event(Key::Shift, true);
+        keyboard.key_event(Key::Shift, false);
+        assert_eq!(Some(BYTE_CYCLES), keyboard.next_event(&mfp, true));
+        keyboard.tick(BYTE_CYCLES - 1, &mut mfp, true);
+        assert!(mfp.ready_to_receive());
+        keyboard.tick(1, &mut mfp, true);
+        assert!(!mfp.ready_to_receive());
+
+        // The break code waits for the machine to read the make code.
+        keyboard.tick(2 * BYTE_CYCLES, &mut mfp, true);
+        assert_eq!(None, keyboard.next_event(&mfp, true));
+        assert_eq!(Key::Shift as Byte, mfp.read(UDR).unwrap());
+        keyboard.tick(1, &mut mfp, true);
+        assert_eq!(Key::Shift as Byte | BREAK, mfp.read(UDR).unwrap());
+        assert_eq!(None, keyboard.next_event(&mfp, true));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let (mut keyboard, mut mfp) = setup();
+        keyboard.key_event(Key::Shift, true);
+        assert_eq!(None, keyboard.next_event(&mfp, false));
+        keyboard.tick(BYTE_CYCLES, &mut mfp, false);
+        assert!(mfp.ready_to_receive());
+        keyboard.tick(BYTE_CYCLES, &mut mfp, true);
+        assert_eq!(Key::Shift as Byte, mfp.read(UDR).unwrap());
+    }
+
+    // The machine sends `value` to the keyboard.
+    fn command(keyboard: &mut Keyboard, mfp: &mut Mfp, value: Byte) {
+        mfp.write(UDR, value);
+        keyboard.tick(0, mfp, true);
+    }
+
+    #[test]
+    fn test_leds() {
+        let (mut keyboard, mut mfp) = setup();
+        command(&mut keyboard, &mut mfp, 0xff);
+        assert_eq!(0x00, keyboard.leds());
+        command(&mut keyboard, &mut mfp, 0xf6);  // かな and CAPS
+        assert_eq!(0x09, keyboard.leds());
+    }
+
+    #[test]
+    fn test_repeat() {
+        let (mut keyboard, mut mfp) = setup();
+        command(&mut keyboard, &mut mfp, 0x61);  // Delay 300ms
+        command(&mut keyboard, &mut mfp, 0x72);  // Interval 50ms
+        keyboard.key_event(Key::A, true);
+        keyboard.tick(BYTE_CYCLES, &mut mfp, true);
+        assert_eq!(Key::A as Byte, mfp.read(UDR).unwrap());
+
+        assert_eq!(Some(3_000_000 - BYTE_CYCLES), keyboard.next_event(&mfp, true));
+        keyboard.tick(3_000_000 - BYTE_CYCLES, &mut mfp, true);
+        keyboard.tick(BYTE_CYCLES, &mut mfp, true);
+        assert_eq!(Key::A as Byte, mfp.read(UDR).unwrap());
+        assert_eq!(Some(500_000 - BYTE_CYCLES), keyboard.next_event(&mfp, true));
+
+        // Pressing it again from the host doesn't restart the repeat.
+        keyboard.key_event(Key::A, true);
+        assert_eq!(Some(500_000 - BYTE_CYCLES), keyboard.next_event(&mfp, true));
+        keyboard.key_event(Key::A, false);
+        keyboard.tick(BYTE_CYCLES, &mut mfp, true);
+        assert_eq!(Key::A as Byte | BREAK, mfp.read(UDR).unwrap());
+        assert_eq!(None, keyboard.next_event(&mfp, true));
+    }
+}
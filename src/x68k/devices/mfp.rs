@@ -0,0 +1,546 @@
+use std::cell::Cell;
+
+use serde::{Serialize, Deserialize};
+
+use super::Device;
+use super::super::super::cpu::InterruptAck;
+use super::super::super::types::{Adr, Byte, Word};
+
+const MFP_CLOCK: u64 = 4_000_000;  // Hz
+const MFP_LEVEL: Word = 6;
+
+// Register offsets: the MFP sits on the odd addresses.
+const GPIP: Adr = 0x01;
+const AER: Adr = 0x03;
+const DDR: Adr = 0x05;
+const IERA: Adr = 0x07;
+const IERB: Adr = 0x09;
+const IPRA: Adr = 0x0b;
+const IPRB: Adr = 0x0d;
+const ISRA: Adr = 0x0f;
+const ISRB: Adr = 0x11;
+const IMRA: Adr = 0x13;
+const IMRB: Adr = 0x15;
+const VR: Adr = 0x17;
+const TACR: Adr = 0x19;
+const TBCR: Adr = 0x1b;
+const TCDCR: Adr = 0x1d;
+const TADR: Adr = 0x1f;
+const TBDR: Adr = 0x21;
+const TCDR: Adr = 0x23;
+const TDDR: Adr = 0x25;
+const SCR: Adr = 0x27;
+const UCR: Adr = 0x29;
+const RSR: Adr = 0x2b;  // Receiver Status Register.
+const TSR: Adr = 0x2d;  // Transmitter Status Register.
+const UDR: Adr = 0x2f;
+
+const RSR_BUFFER_FULL: Byte = 0x80;
+const RSR_OVERRUN: Byte = 0x40;
+const RSR_ENABLE: Byte = 0x01;
+const TSR_BUFFER_EMPTY: Byte = 0x80;
+
+const VR_SOFTWARE_EOI: Byte = 0x08;  // In-service bits stay set until cleared by the handler.
+
+// GPIP lines of the X68000: 0 RTC alarm, 1 external power on, 2 power switch, 3 FM sound,
+// 4 V-DISP, 6 CRTC raster interrupt and 7 H-SYNC. V-DISP is the timer A input too.
+pub const GPIP_VDISP: usize = 4;
+
+// Interrupt channels of the GPIP lines 0~7.
+const GPIP_CHANNELS: [usize; 8] = [0, 1, 2, 3, 6, 7, 14, 15];
+const RX_FULL_CHANNEL: usize = 12;
+const RX_ERROR_CHANNEL: usize = 11;
+const TX_EMPTY_CHANNEL: usize = 10;
+
+// Interrupt channels of the timers; the A registers hold channels 15~8, the B ones 7~0.
+const TIMER_CHANNELS: [usize; 4] = [13, 8, 5, 4];
+// AER bits of the GPIP lines shared with the timer A and B inputs.
+const TIMER_INPUT_EDGES: [Byte; 2] = [1 << 4, 1 << 3];
+
+// Divisors for the prescaler settings 1~7 of the timer control registers.
+const PRESCALE: [u64; 8] = [0, 4, 10, 16, 50, 64, 100, 200];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TimerMode {
+    Stopped,
+    Delay,
+    EventCount,
+    PulseWidth,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Timer {
+    control: Byte,  // Mode bits of TACR/TBCR, or the TCDCR field.
+    data: Byte,  // Reload value, 0 for 256.
+    count: u32,  // 1~256: timeout when it reaches 0.
+    phase: u64,  // Elapsed time of the current prescaler period, in MFP clocks times the CPU clock.
+    input: bool,  // Timer A/B input is active.
+}
+
+impl Timer {
+    fn new() -> Self {
+        Self { control: 0, data: 0, count: 256, phase: 0, input: false }
+    }
+
+    // 0 stops, 1~7 is delay mode with that prescaler, 8 counts events on the input, and 9~15
+    // is pulse width mode with the prescaler of the low 3 bits.
+    fn mode(&self) -> TimerMode {
+        match self.control {
+            0 => TimerMode::Stopped,
+            1..=7 => TimerMode::Delay,
+            8 => TimerMode::EventCount,
+            _ => TimerMode::PulseWidth,
+        }
+    }
+
+    fn prescale(&self) -> u64 {
+        PRESCALE[(self.control & 7) as usize]
+    }
+
+    fn reload(&self) -> u32 {
+        if self.data == 0 { 256 } else { self.data as u32 }
+    }
+
+    fn set_control(&mut self, value: Byte) {
+        self.control = value & 0x0f;
+        if self.mode() == TimerMode::Stopped {
+            self.phase = 0;
+        }
+    }
+
+    // A stopped timer loads the counter too, a running one only the reload value.
+    fn set_data(&mut self, value: Byte) {
+        self.data = value;
+        if self.mode() == TimerMode::Stopped {
+            self.count = self.reload();
+        }
+    }
+
+    fn running(&self) -> bool {
+        match self.mode() {
+            TimerMode::Delay => true,
+            TimerMode::PulseWidth => self.input,
+            TimerMode::Stopped | TimerMode::EventCount => false,
+        }
+    }
+
+    // Count down `ticks`, returning the number of timeouts.
+    fn count_down(&mut self, ticks: u64) -> u64 {
+        if ticks < self.count as u64 {
+            self.count -= ticks as u32;
+            return 0;
+        }
+        let reload = self.reload() as u64;
+        let rest = ticks - self.count as u64;
+        self.count = (reload - rest % reload) as u32;
+        1 + rest / reload
+    }
+
+    fn tick(&mut self, cycles: usize, cpu_clock: u64) -> u64 {
+        if !self.running() {
+            return 0;
+        }
+        let period = self.prescale() * cpu_clock;
+        self.phase += cycles as u64 * MFP_CLOCK;
+        let ticks = self.phase / period;
+        self.phase %= period;
+        self.count_down(ticks)
+    }
+
+    // CPU clocks until the next timeout.
+    fn next_event(&self, cpu_clock: u64) -> Option<usize> {
+        if !self.running() {
+            return None;
+        }
+        let remaining = self.count as u64 * self.prescale() * cpu_clock - self.phase;
+        Some(remaining.div_ceil(MFP_CLOCK) as usize)
+    }
+}
+
+// MC68901 MFP at $e88000. Timer C makes the system tick and timer D the IOCS timer; timers
+// A and B count V-DISP and clock the keyboard USART. All of its 16 interrupt channels share
+// level 6, prioritized by channel number.
+#[derive(Serialize, Deserialize)]
+pub struct Mfp {
+    cpu_clock: u64,  // Hz, to run the timers on the MFP clock.
+    inputs: Byte,  // Levels on the GPIP lines.
+    gpip: Byte,  // Output data, for the lines set as outputs in DDR.
+    aer: Byte,
+    ddr: Byte,
+    ier: Word,  // A in the high byte, B in the low byte, as for the others.
+    ipr: Word,
+    isr: Word,
+    imr: Word,
+    vr: Byte,
+    timers: [Timer; 4],
+    scr: Byte,
+    ucr: Byte,
+    rsr: Cell<Byte>,  // Reading UDR empties the buffer.
+    tsr: Byte,  // Control bits only.
+    udr: Byte,  // Last byte received.
+    tx: Option<Byte>,  // Written to UDR and not sent yet.
+}
+
+impl Mfp {
+    pub fn new(cpu_clock: u32) -> Self {
+        Self {
+            cpu_clock: cpu_clock as u64,
+            inputs: 0xff,  // Nothing asserted.
+            gpip: 0,
+            aer: 0,
+            ddr: 0,
+            ier: 0,
+            ipr: 0,
+            isr: 0,
+            imr: 0,
+            vr: 0,
+            timers: [Timer::new(), Timer::new(), Timer::new(), Timer::new()],
+            scr: 0,
+            ucr: 0,
+            rsr: Cell::new(0),
+            tsr: 0,
+            udr: 0,
+            tx: None,
+        }
+    }
+
+    // Interrupt channel 0~15 requests service, if enabled.
+    fn raise(&mut self, channel: usize) {
+        self.ipr |= self.ier & (1 << channel);
+    }
+
+    // Pending and unmasked channels of higher priority than any in service.
+    fn pending(&self) -> Word {
+        let blocked = match self.isr {
+            0 => 0,
+            isr => !0 >> isr.leading_zeros(),  // The highest in service and below.
+        };
+        self.ipr & self.imr & !blocked
+    }
+
+    // Whether the USART receiver can take another byte without an overrun.
+    pub fn ready_to_receive(&self) -> bool {
+        (self.rsr.get() & (RSR_ENABLE | RSR_BUFFER_FULL)) == RSR_ENABLE
+    }
+
+    // A byte arrives on the USART, from the keyboard on the X68000. With the receiver
+    // disabled it is lost; with the buffer still full it is an overrun.
+    pub fn receive(&mut self, value: Byte) {
+        let rsr = self.rsr.get();
+        if (rsr & RSR_ENABLE) == 0 {
+            return;
+        }
+        if (rsr & RSR_BUFFER_FULL) != 0 {
+            self.rsr.set(rsr | RSR_OVERRUN);
+            self.raise(RX_ERROR_CHANNEL);
+        } else {
+            self.udr = value;
+            self.rsr.set(rsr | RSR_BUFFER_FULL);
+            self.raise(RX_FULL_CHANNEL);
+        }
+    }
+
+    // The byte written to the USART to send, to the keyboard on the X68000. The transmit
+    // buffer is empty again after that.
+    pub fn take_transmitted(&mut self) -> Option<Byte> {
+        let value = self.tx.take()?;
+        self.raise(TX_EMPTY_CHANNEL);
+        Some(value)
+    }
+
+    // Level change on GPIP line 0~7, e.g. V-DISP: an input requests an interrupt on the edge
+    // selected by AER, rising if the bit is set and falling if not.
+    pub fn set_gpip(&mut self, line: usize, level: bool) {
+        let bit = 1 << line;
+        let old = (self.inputs & bit) != 0;
+        if level {
+            self.inputs |= bit;
+        } else {
+            self.inputs &= !bit;
+        }
+        if (self.ddr & bit) == 0 && old != level && level == ((self.aer & bit) != 0) {
+            self.raise(GPIP_CHANNELS[line]);
+        }
+        if line == GPIP_VDISP {
+            self.timer_input(0, level);
+        }
+    }
+
+    // Level change on the input of timer A (V-DISP) or B, counted by event count mode and
+    // gating pulse width mode. Active as selected by the AER bit of the shared GPIP line.
+    fn timer_input(&mut self, timer: usize, level: bool) {
+        let active = level == ((self.aer & TIMER_INPUT_EDGES[timer]) != 0);
+        let t = &mut self.timers[timer];
+        let edge = active && !t.input;
+        t.input = active;
+        if edge && t.mode() == TimerMode::EventCount && t.count_down(1) > 0 {
+            self.raise(TIMER_CHANNELS[timer]);
+        }
+    }
+}
+
+fn set_high(reg: &mut Word, value: Byte) {
+    *reg = (*reg & 0x00ff) | ((value as Word) << 8);
+}
+
+fn set_low(reg: &mut Word, value: Byte) {
+    *reg = (*reg & 0xff00) | value as Word;
+}
+
+impl Device for Mfp {
+    fn read(&self, offset: Adr) -> Option<Byte> {
+        let value = match offset {
+            GPIP => (self.gpip & self.ddr) | (self.inputs & !self.ddr),
+            AER => self.aer,
+            DDR => self.ddr,
+            IERA => (self.ier >> 8) as Byte,
+            IERB => self.ier as Byte,
+            IPRA => (self.ipr >> 8) as Byte,
+            IPRB => self.ipr as Byte,
+            ISRA => (self.isr >> 8) as Byte,
+            ISRB => self.isr as Byte,
+            IMRA => (self.imr >> 8) as Byte,
+            IMRB => self.imr as Byte,
+            VR => self.vr,
+            TACR => self.timers[0].control,
+            TBCR => self.timers[1].control,
+            TCDCR => (self.timers[2].control << 4) | self.timers[3].control,
+            TADR => self.timers[0].count as Byte,
+            TBDR => self.timers[1].count as Byte,
+            TCDR => self.timers[2].count as Byte,
+            TDDR => self.timers[3].count as Byte,
+            SCR => self.scr,
+            UCR => self.ucr,
+            // The overrun flag is cleared by reading it.
+            RSR => self.rsr.replace(self.rsr.get() & !RSR_OVERRUN),
+            TSR => if self.tx.is_none() { TSR_BUFFER_EMPTY | self.tsr } else { self.tsr },
+            UDR => {
+                self.rsr.set(self.rsr.get() & !RSR_BUFFER_FULL);
+                self.udr
+            },
+            _ => 0,
+        };
+        Some(value)
+    }
+
+    fn write(&mut self, offset: Adr, value: Byte) {
+        match offset {
+            GPIP => self.gpip = value,
+            AER => self.aer = value,
+            DDR => self.ddr = value,
+            // Disabling a channel drops its pending request too.
+            IERA => {
+                set_high(&mut self.ier, value);
+                self.ipr &= self.ier;
+            },
+            IERB => {
+                set_low(&mut self.ier, value);
+                self.ipr &= self.ier;
+            },
+            // Pending bits can only be cleared, by writing 0 to them.
+            IPRA => self.ipr &= ((value as Word) << 8) | 0x00ff,
+            IPRB => self.ipr &= 0xff00 | value as Word,
+            // Likewise for the in-service bits, to end an interrupt in software EOI mode.
+            ISRA => self.isr &= ((value as Word) << 8) | 0x00ff,
+            ISRB => self.isr &= 0xff00 | value as Word,
+            IMRA => set_high(&mut self.imr, value),
+            IMRB => set_low(&mut self.imr, value),
+            VR => {
+                self.vr = value;
+                if (value & VR_SOFTWARE_EOI) == 0 {
+                    self.isr = 0;
+                }
+            },
+            TACR => self.timers[0].set_control(value),
+            TBCR => self.timers[1].set_control(value),
+            // Timers C and D only have delay mode.
+            TCDCR => {
+                self.timers[2].set_control((value >> 4) & 7);
+                self.timers[3].set_control(value & 7);
+            },
+            TADR => self.timers[0].set_data(value),
+            TBDR => self.timers[1].set_data(value),
+            TCDR => self.timers[2].set_data(value),
+            TDDR => self.timers[3].set_data(value),
+            SCR => self.scr = value,
+            UCR => self.ucr = value,
+            // Disabling the receiver clears its status.
+            RSR => {
+                let status = if (value & RSR_ENABLE) != 0 { self.rsr.get() & 0xf0 } else { 0 };
+                self.rsr.set(status | (value & 0x0f));
+            },
+            TSR => self.tsr = value & 0x0f,
+            UDR => self.tx = Some(value),
+            _ => {},
+        }
+    }
+
+    fn tick(&mut self, cycles: usize) {
+        for (i, &channel) in TIMER_CHANNELS.iter().enumerate() {
+            if self.timers[i].tick(cycles, self.cpu_clock) > 0 {
+                self.raise(channel);
+            }
+        }
+    }
+
+    // Registers are cleared and the timers stopped; the timer data registers keep their values.
+    fn reset(&mut self) {
+        let mut mfp = Self::new(self.cpu_clock as u32);
+        mfp.inputs = self.inputs;
+        for (timer, old) in mfp.timers.iter_mut().zip(self.timers.iter()) {
+            timer.data = old.data;
+            timer.count = old.count;
+        }
+        *self = mfp;
+    }
+
+    fn irq(&self) -> Word {
+        if self.pending() != 0 { MFP_LEVEL } else { 0 }
+    }
+
+    // The highest priority pending channel, numbered in the low 4 bits of the vector. In
+    // software EOI mode it stays in service, holding off the lower priority ones.
+    fn acknowledge(&mut self) -> InterruptAck {
+        match self.pending() {
+            0 => InterruptAck::Spurious,
+            pending => {
+                let channel = 15 - pending.leading_zeros() as Byte;
+                self.ipr &= !(1 << channel);
+                if (self.vr & VR_SOFTWARE_EOI) != 0 {
+                    self.isr |= 1 << channel;
+                }
+                InterruptAck::Vector((self.vr & 0xf0) | channel)
+            },
+        }
+    }
+
+    fn next_event(&self) -> Option<usize> {
+        self.timers.iter().filter_map(|t| t.next_event(self.cpu_clock)).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPU_CLOCK: u32 = 10_000_000;
+
+    fn vector(mfp: &mut Mfp) -> Option<Byte> {
+        match mfp.acknowledge() {
+            InterruptAck::Vector(vector) => Some(vector),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_timer_delay_mode() {
+        let mut mfp = Mfp::new(CPU_CLOCK);
+        mfp.write(VR, 0x40);
+        mfp.write(IERB, 0x20);  // Timer C
+        mfp.write(IMRB, 0x20);
+        mfp.write(TCDR, 10);
+        mfp.write(TCDCR, 0x70);  // Prescale 200: 10 * 200 MFP clocks = 500us
+
+        assert_eq!(Some(5000), mfp.next_event());
+        mfp.tick(4999);
+        assert_eq!(0, mfp.irq());
+        assert_eq!(Some(1), mfp.next_event());
+        mfp.tick(1);
+        assert_eq!(MFP_LEVEL, mfp.irq());
+        assert_eq!(Some(0x45), vector(&mut mfp));
+        assert_eq!(0, mfp.irq());
+
+        // Reloaded from the data register, which a running timer takes on the next timeout.
+        assert_eq!(Some(5000), mfp.next_event());
+        mfp.write(TCDR, 20);
+        assert_eq!(10, mfp.read(TCDR).unwrap());
+        mfp.tick(5000);
+        assert_eq!(Some(10000), mfp.next_event());
+    }
+
+    #[test]
+    fn test_timer_prescale() {
+        let mut mfp = Mfp::new(CPU_CLOCK);
+        mfp.write(TDDR, 0);  // 256
+        for (control, prescale) in (1..8).zip(PRESCALE[1..].iter()) {
+            mfp.write(TCDCR, control);
+            assert_eq!(Some((256 * prescale * 10 / 4) as usize), mfp.next_event());
+            mfp.write(TCDCR, 0);
+        }
+        assert_eq!(None, mfp.next_event());
+    }
+
+    #[test]
+    fn test_priority_and_software_eoi() {
+        let mut mfp = Mfp::new(CPU_CLOCK);
+        mfp.write(VR, 0x40 | VR_SOFTWARE_EOI);
+        for reg in [IERA, IERB, IMRA, IMRB] {
+            mfp.write(reg, 0xff);
+        }
+        // Falling edges on GPIP 0 (channel 0) and 7 (channel 15).
+        mfp.set_gpip(0, false);
+        mfp.set_gpip(7, false);
+        assert_eq!(Some(0x4f), vector(&mut mfp));
+        assert_eq!(0x80, mfp.read(ISRA).unwrap());
+        // Channel 0 waits until the handler clears the in-service bit.
+        assert_eq!(0, mfp.irq());
+        mfp.write(ISRA, 0x7f);
+        assert_eq!(Some(0x40), vector(&mut mfp));
+
+        // A higher priority channel interrupts one in service.
+        mfp.set_gpip(GPIP_VDISP, false);
+        assert_eq!(Some(0x46), vector(&mut mfp));
+        assert_eq!(0x41, mfp.read(ISRB).unwrap());
+
+        // Leaving software EOI mode ends them all.
+        mfp.write(VR, 0x40);
+        assert_eq!(0, mfp.read(ISRB).unwrap());
+    }
+
+    #[test]
+    fn test_gpip_edge() {
+        let mut mfp = Mfp::new(CPU_CLOCK);
+        mfp.write(IERB, 0x40);
+        mfp.write(IMRB, 0x40);
+        mfp.write(AER, 1 << GPIP_VDISP);  // Rising edge
+        mfp.set_gpip(GPIP_VDISP, false);
+        assert_eq!(0, mfp.irq());
+        assert_eq!(0, mfp.read(GPIP).unwrap() & (1 << GPIP_VDISP));
+        mfp.set_gpip(GPIP_VDISP, true);
+        assert_eq!(MFP_LEVEL, mfp.irq());
+    }
+
+    #[test]
+    fn test_timer_event_count() {
+        let mut mfp = Mfp::new(CPU_CLOCK);
+        mfp.write(IERA, 0x20);  // Timer A
+        mfp.write(IMRA, 0x20);
+        mfp.write(TADR, 2);
+        mfp.write(TACR, 0x08);  // Counting V-DISP, active low as AER is clear.
+        for _ in 0..2 {
+            assert_eq!(0, mfp.irq());
+            mfp.set_gpip(GPIP_VDISP, false);
+            mfp.set_gpip(GPIP_VDISP, true);
+        }
+        assert_eq!(MFP_LEVEL, mfp.irq());
+    }
+
+    #[test]
+    fn test_usart_overrun() {
+        let mut mfp = Mfp::new(CPU_CLOCK);
+        mfp.write(IERA, 0x18);  // Receive buffer full and receive error
+        mfp.receive(0x12);
+        assert_eq!(0, mfp.read(IPRA).unwrap());  // Lost with the receiver disabled.
+
+        mfp.write(RSR, RSR_ENABLE);
+        assert!(mfp.ready_to_receive());
+        mfp.receive(0x12);
+        assert!(!mfp.ready_to_receive());
+        mfp.receive(0x34);
+        assert_eq!(0x18, mfp.read(IPRA).unwrap());
+        assert_eq!(RSR_BUFFER_FULL | RSR_OVERRUN | RSR_ENABLE, mfp.read(RSR).unwrap());
+        assert_eq!(RSR_BUFFER_FULL | RSR_ENABLE, mfp.read(RSR).unwrap());
+        assert_eq!(0x12, mfp.read(UDR).unwrap());
+        assert!(mfp.ready_to_receive());
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use serde::{Serialize, Deserialize};
+
+use super::scc::{Scc, CHANNEL_B};
+use super::super::super::types::Byte;
+
+const BAUD_RATE: u32 = 4800;
+const BITS_PER_BYTE: u32 = 10;  // Start, 8 data and stop bits.
+
+// Bits of the first byte of a packet.
+const LEFT_BUTTON: Byte = 0x01;
+const RIGHT_BUTTON: Byte = 0x02;
+const X_OVERFLOW: Byte = 0x10;  // Then 0x20 in the negative direction.
+const Y_OVERFLOW: Byte = 0x40;  // And 0x80.
+
+// The mouse on SCC channel B. Each time MSCTRL, driven by the keyboard, goes from high to
+// low, it sends a 3-byte packet: the buttons, and X and Y movement since the last packet.
+#[derive(Serialize, Deserialize)]
+pub struct Mouse {
+    byte_cycles: usize,  // CPU clocks to send one byte.
+    dx: i32,
+    dy: i32,
+    buttons: Byte,
+    msctrl: bool,
+    queue: VecDeque<Byte>,
+    wait: usize,  // Clocks until the byte being sent arrives.
+}
+
+impl Mouse {
+    pub fn new(cpu_clock: u32) -> Self {
+        Self {
+            byte_cycles: (cpu_clock / (BAUD_RATE / BITS_PER_BYTE)) as usize,
+            dx: 0,
+            dy: 0,
+            buttons: 0,
+            msctrl: true,
+            queue: VecDeque::new(),
+            wait: 0,
+        }
+    }
+
+    // Movement, right and down positive, accumulated until the next packet.
+    pub fn mouse_event(&mut self, dx: i32, dy: i32, left: bool, right: bool) {
+        self.dx = self.dx.saturating_add(dx);
+        self.dy = self.dy.saturating_add(dy);
+        self.buttons = if left { LEFT_BUTTON } else { 0 } | if right { RIGHT_BUTTON } else { 0 };
+    }
+
+    pub fn set_msctrl(&mut self, level: bool) {
+        if self.msctrl && !level && self.queue.is_empty() {
+            let (x, x_overflow) = clamp(self.dx, X_OVERFLOW);
+            let (y, y_overflow) = clamp(self.dy, Y_OVERFLOW);
+            self.queue.extend(&[self.buttons | x_overflow | y_overflow, x, y]);
+            self.dx = 0;
+            self.dy = 0;
+            self.wait = self.byte_cycles;
+        }
+        self.msctrl = level;
+    }
+
+    pub fn tick(&mut self, cycles: usize, scc: &mut Scc) {
+        if self.queue.is_empty() {
+            return;
+        }
+        self.wait = self.wait.saturating_sub(cycles);
+        if self.wait == 0 && scc.ready_to_receive(CHANNEL_B) {
+            if let Some(value) = self.queue.pop_front() {
+                scc.receive(CHANNEL_B, value);
+            }
+            self.wait = self.byte_cycles;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.queue.clear();
+    }
+
+    // Clocks until the next byte of a packet arrives.
+    pub fn next_event(&self, scc: &Scc) -> Option<usize> {
+        match self.wait {
+            _ if self.queue.is_empty() => None,
+            0 if !scc.ready_to_receive(CHANNEL_B) => None,
+            wait => Some(wait.max(1)),
+        }
+    }
+}
+
+// A signed movement byte, and the overflow flag for the direction if it doesn't fit.
+fn clamp(delta: i32, overflow: Byte) -> (Byte, Byte) {
+    match delta {
+        d if d > 127 => (127, overflow),
+        d if d < -128 => (0x80, overflow << 1),
+        d => (d as Byte, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Device;
+    use super::super::super::super::types::Adr;
+
+    const CPU_CLOCK: u32 = 10_000_000;
+    const BYTE_CYCLES: usize = 20833;  // 4800 baud
+    // SCC channel B ports.
+    const COMMAND: Adr = 0x01;
+    const DATA: Adr = 0x03;
+
+    fn setup() -> (Mouse, Scc) {
+        let mut scc = Scc::new(CPU_CLOCK);
+        scc.write(COMMAND, 3);
+        scc.write(COMMAND, 0xc1);  // Rx 8 bits, enabled
+        (Mouse::new(CPU_CLOCK), scc)
+    }
+
+    fn packet(mouse: &mut Mouse, scc: &mut Scc) -> Vec<Byte> {
+        for _ in 0..3 {
+            assert_eq!(Some(BYTE_CYCLES), mouse.next_event(scc));
+            mouse.tick(BYTE_CYCLES, scc);
+        }
+        assert_eq!(None, mouse.next_event(scc));
+        (0..3).map(|_| scc.read(DATA).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_packet_on_msctrl() {
+        let (mut mouse, mut scc) = setup();
+        mouse.mouse_event(2, -1, true, false);
+        mouse.mouse_event(3, -2, true, false);
+        mouse.set_msctrl(true);
+        assert_eq!(None, mouse.next_event(&scc));
+
+        mouse.set_msctrl(false);
+        assert_eq!(vec![LEFT_BUTTON, 5, 0xfd], packet(&mut mouse, &mut scc));
+        // Only the falling edge asks for a packet.
+        mouse.set_msctrl(false);
+        assert_eq!(None, mouse.next_event(&scc));
+
+        mouse.set_msctrl(true);
+        mouse.set_msctrl(false);
+        assert_eq!(vec![LEFT_BUTTON, 0, 0], packet(&mut mouse, &mut scc));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let (mut mouse, mut scc) = setup();
+        mouse.mouse_event(200, -300, false, true);
+        mouse.set_msctrl(false);
+        assert_eq!(vec![RIGHT_BUTTON | X_OVERFLOW | (Y_OVERFLOW << 1), 127, 0x80], packet(&mut mouse, &mut scc));
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Serialize, Deserialize};
+
+use super::scc::{Scc, CHANNEL_A};
+use super::super::serial::SerialLine;
+use super::super::super::types::Byte;
+
+// The RS-232C port on SCC channel A, passing bytes to and from a line on the host. Bytes
+// from the host arrive at the baud rate programmed into the channel, and wait on the line
+// while the receive FIFO is full.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Rs232c {
+    #[serde(skip)]
+    line: Option<Box<dyn SerialLine>>,  // Installed by the host, not saved.
+    pending: Option<Byte>,  // Received from the host, on its way to the SCC.
+    wait: usize,  // Clocks until it arrives.
+}
+
+impl Rs232c {
+    pub fn set_line(&mut self, line: Option<Box<dyn SerialLine>>) {
+        self.line = line;
+    }
+
+    pub fn take_line(&mut self, other: &mut Rs232c) {
+        self.line = other.line.take();
+    }
+
+    pub fn tick(&mut self, cycles: usize, scc: &mut Scc) {
+        let line = match self.line.as_mut() {
+            Some(line) => line,
+            None => return,
+        };
+        if let Some(value) = scc.take_transmitted(CHANNEL_A) {
+            line.write(value);
+        }
+        // Without a clock, the receiver sees nothing.
+        let char_cycles = match scc.char_cycles(CHANNEL_A) {
+            Some(cycles) => cycles,
+            None => return,
+        };
+        self.wait = self.wait.saturating_sub(cycles);
+        if self.wait > 0 {
+            return;
+        }
+        if let Some(value) = self.pending {
+            if !scc.ready_to_receive(CHANNEL_A) {
+                return;
+            }
+            scc.receive(CHANNEL_A, value);
+        }
+        // The host is polled once per character time.
+        self.pending = line.read();
+        self.wait = char_cycles;
+        scc.set_dcd(CHANNEL_A, line.connected());
+    }
+
+    pub fn reset(&mut self) {
+        self.pending = None;
+        self.wait = 0;
+    }
+
+    // Clocks until the next byte may arrive, unless waiting for the machine to read the FIFO.
+    pub fn next_event(&self, scc: &Scc) -> Option<usize> {
+        self.line.as_ref()?;
+        scc.char_cycles(CHANNEL_A)?;
+        match self.wait {
+            0 if self.pending.is_some() && !scc.ready_to_receive(CHANNEL_A) => None,
+            wait => Some(wait.max(1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use super::super::Device;
+    use super::super::super::super::types::Adr;
+
+    const CPU_CLOCK: u32 = 10_000_000;
+    const CHAR_CYCLES: usize = 10240;
+    // SCC channel A ports.
+    const COMMAND: Adr = 0x05;
+    const DATA: Adr = 0x07;
+
+    struct Line {
+        input: VecDeque<Byte>,
+        output: Arc<Mutex<Vec<Byte>>>,
+        connected: bool,
+    }
+
+    impl SerialLine for Line {
+        fn read(&mut self) -> Option<Byte> {
+            self.input.pop_front()
+        }
+
+        fn write(&mut self, value: Byte) {
+            self.output.lock().unwrap().push(value);
+        }
+
+        fn connected(&self) -> bool {
+            self.connected
+        }
+    }
+
+    // 9600 baud, 8 bits, 1 stop bit, receiver and transmitter enabled.
+    fn setup(input: &[Byte], connected: bool) -> (Rs232c, Scc, Arc<Mutex<Vec<Byte>>>) {
+        let mut scc = Scc::new(CPU_CLOCK);
+        for &(reg, value) in &[(4, 0x44), (3, 0xc1), (5, 0x68), (12, 14), (13, 0), (14, 0x01)] {
+            scc.write(COMMAND, if reg >= 8 { 0x08 | (reg & 7) } else { reg });
+            scc.write(COMMAND, value);
+        }
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let line = Line { input: input.iter().copied().collect(), output: output.clone(), connected };
+        let mut rs232c = Rs232c::default();
+        rs232c.set_line(Some(Box::new(line)));
+        (rs232c, scc, output)
+    }
+
+    fn rr0(scc: &Scc) -> Byte {
+        scc.read(COMMAND).unwrap()
+    }
+
+    #[test]
+    fn test_receive() {
+        let (mut rs232c, mut scc, _) = setup(b"ABCDE", true);
+        rs232c.tick(0, &mut scc);
+        assert_eq!(Some(CHAR_CYCLES), rs232c.next_event(&scc));
+        rs232c.tick(CHAR_CYCLES - 1, &mut scc);
+        assert_eq!(0, rr0(&scc) & 0x01);
+        rs232c.tick(1, &mut scc);
+        assert_eq!(0x01, rr0(&scc) & 0x01);
+
+        // The rest waits on the line once the FIFO is full.
+        for _ in 0..3 {
+            rs232c.tick(CHAR_CYCLES, &mut scc);
+        }
+        assert_eq!(None, rs232c.next_event(&scc));
+        assert_eq!(b'A', scc.read(DATA).unwrap());
+        rs232c.tick(1, &mut scc);
+        let received: Vec<Byte> = (0..3).map(|_| scc.read(DATA).unwrap()).collect();
+        assert_eq!(b"BCD".to_vec(), received);
+    }
+
+    #[test]
+    fn test_transmit() {
+        let (mut rs232c, mut scc, output) = setup(b"", true);
+        scc.write(DATA, b'x');
+        scc.tick(CHAR_CYCLES);
+        rs232c.tick(CHAR_CYCLES, &mut scc);
+        assert_eq!(b"x".to_vec(), *output.lock().unwrap());
+    }
+
+    #[test]
+    fn test_carrier_detect() {
+        let (mut rs232c, mut scc, _) = setup(b"", false);
+        assert_eq!(0x08, rr0(&scc) & 0x08);
+        rs232c.tick(0, &mut scc);
+        assert_eq!(0, rr0(&scc) & 0x08);
+    }
+}
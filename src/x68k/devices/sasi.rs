@@ -0,0 +1,19 @@
+use serde::{Serialize, Deserialize};
+
+use super::Device;
+use super::super::super::types::{Adr, Byte};
+
+// SASI hard disk interface at $e96000.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Sasi;
+
+impl Device for Sasi {
+    fn read(&self, offset: Adr) -> Option<Byte> {
+        // TODO: Implement.
+        if offset <= 0xfff { Some(0) } else { None }
+    }
+
+    fn write(&mut self, _offset: Adr, _value: Byte) {
+        // TODO: Implement.
+    }
+}
@@ -0,0 +1,566 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use serde::{Serialize, Deserialize};
+
+use super::Device;
+use super::super::super::cpu::InterruptAck;
+use super::super::super::types::{Adr, Byte, Word};
+
+pub const CHANNEL_A: usize = 0;  // RS-232C
+pub const CHANNEL_B: usize = 1;  // Mouse
+
+const PCLK: u64 = 5_000_000;  // Hz, clocking the baud-rate generators.
+const SCC_LEVEL: Word = 5;
+
+const RX_FIFO_SIZE: usize = 3;
+
+// WR0 commands, in bits 3~5.
+const CMD_POINT_HIGH: Byte = 1;
+const CMD_RESET_EXT_STATUS: Byte = 2;
+const CMD_ENABLE_INT_NEXT_RX: Byte = 4;
+const CMD_RESET_TX_INT: Byte = 5;
+const CMD_ERROR_RESET: Byte = 6;
+const CMD_RESET_HIGHEST_IUS: Byte = 7;
+
+const WR1_EXT_INT_ENABLE: Byte = 0x01;
+const WR1_TX_INT_ENABLE: Byte = 0x02;
+const WR3_RX_ENABLE: Byte = 0x01;
+const WR4_PARITY_ENABLE: Byte = 0x01;
+const WR5_TX_ENABLE: Byte = 0x08;
+const WR14_BRG_ENABLE: Byte = 0x01;
+const WR15_DCD_INT_ENABLE: Byte = 0x08;
+
+const WR9_VIS: Byte = 0x01;  // Vector includes status.
+const WR9_NV: Byte = 0x02;  // No vector.
+const WR9_MIE: Byte = 0x08;  // Master interrupt enable.
+const WR9_STATUS_HIGH: Byte = 0x10;
+
+const RR0_RX_AVAILABLE: Byte = 0x01;
+const RR0_TX_EMPTY: Byte = 0x04;
+const RR0_DCD: Byte = 0x08;
+const RR0_CTS: Byte = 0x20;
+const RR0_TX_UNDERRUN: Byte = 0x40;
+const RR1_ALL_SENT: Byte = 0x01;
+const RR1_OVERRUN: Byte = 0x20;
+
+// Interrupt pending bits of a channel, as in RR3 with channel A shifted up by 3. Priority
+// goes from the top: channel A receive down to channel B external/status.
+const IP_EXT: Byte = 0x01;
+const IP_TX: Byte = 0x02;
+const IP_RX: Byte = 0x04;
+
+// Status in the modified vector, by RR3 bit, then for a special receive condition.
+const VECTOR_STATUS: [Byte; 6] = [1, 0, 2, 5, 4, 6];
+const VECTOR_SPECIAL: [Byte; 2] = [7, 3];
+const VECTOR_NONE: Byte = 3;
+
+#[derive(Serialize, Deserialize)]
+struct Channel {
+    pointer: usize,  // Register for the next command port access, set through WR0.
+    wr: [Byte; 16],  // WR2 and WR9 are shared, in Scc.
+    rx: VecDeque<Byte>,
+    overrun: bool,  // Special receive condition, until an error reset.
+    rx_first: bool,  // The next character received interrupts in Rx int mode 1.
+    rx_first_ip: bool,
+    tx_buffer: Option<Byte>,
+    tx_shift: Option<Byte>,  // Being sent.
+    tx_wait: usize,  // Clocks until it is sent.
+    tx_ip: bool,
+    ext_ip: bool,
+    sent: Option<Byte>,  // Last byte sent, lost unless taken by whatever is on the line.
+    dcd: bool,  // Modem inputs, as asserted by the line.
+    cts: bool,
+}
+
+impl Channel {
+    fn new() -> Self {
+        Self {
+            pointer: 0,
+            wr: [0; 16],
+            rx: VecDeque::new(),
+            overrun: false,
+            rx_first: true,
+            rx_first_ip: false,
+            tx_buffer: None,
+            tx_shift: None,
+            tx_wait: 0,
+            tx_ip: false,
+            ext_ip: false,
+            sent: None,
+            dcd: true,
+            cts: true,
+        }
+    }
+
+    // Channel reset: the line inputs stay.
+    fn reset(&mut self) {
+        *self = Self { dcd: self.dcd, cts: self.cts, ..Self::new() };
+    }
+
+    // CPU clocks for a character on the line, per the baud-rate generator, clock mode and
+    // format. None while the generator is stopped.
+    fn char_cycles(&self, cpu_clock: u64) -> Option<usize> {
+        if (self.wr[14] & WR14_BRG_ENABLE) == 0 {
+            return None;
+        }
+        let time_constant = ((self.wr[13] as u64) << 8) | self.wr[12] as u64;
+        let clock_mode = [1, 16, 32, 64][(self.wr[4] >> 6) as usize];
+        let data_bits = [5, 7, 6, 8][((self.wr[5] >> 5) & 3) as usize];
+        let parity = (self.wr[4] & WR4_PARITY_ENABLE) as u64;
+        let stop_halves = match (self.wr[4] >> 2) & 3 {
+            3 => 4,
+            2 => 3,
+            _ => 2,
+        };
+        // Bits take 2 * (time constant + 2) * clock mode PCLKs; counted here in halves.
+        let halves = 2 * (1 + data_bits + parity) + stop_halves;
+        let cycles = halves * (time_constant + 2) * clock_mode * cpu_clock;
+        Some(cycles.div_ceil(PCLK) as usize)
+    }
+
+    // Move the buffered character to the shift register once it is free.
+    fn load_shift(&mut self, cpu_clock: u64) {
+        if self.tx_shift.is_some() || (self.wr[5] & WR5_TX_ENABLE) == 0 {
+            return;
+        }
+        if let Some(cycles) = self.char_cycles(cpu_clock) {
+            if let Some(value) = self.tx_buffer.take() {
+                self.tx_shift = Some(value);
+                self.tx_wait = cycles;
+                if (self.wr[1] & WR1_TX_INT_ENABLE) != 0 {
+                    self.tx_ip = true;
+                }
+            }
+        }
+    }
+
+    fn transmit(&mut self, value: Byte, cpu_clock: u64) {
+        self.tx_buffer = Some(value);
+        self.tx_ip = false;
+        self.load_shift(cpu_clock);
+    }
+
+    fn receive(&mut self, value: Byte) {
+        if (self.wr[3] & WR3_RX_ENABLE) == 0 {
+            return;
+        }
+        if self.rx_first {
+            self.rx_first = false;
+            self.rx_first_ip = true;
+        }
+        if self.rx.len() < RX_FIFO_SIZE {
+            self.rx.push_back(value);
+        } else {
+            // The last character in the FIFO is overwritten.
+            self.rx.pop_back();
+            self.rx.push_back(value);
+            self.overrun = true;
+        }
+    }
+
+    fn read_data(&mut self) -> Byte {
+        self.rx_first_ip = false;
+        self.rx.pop_front().unwrap_or(0)
+    }
+
+    fn tick(&mut self, cycles: usize, cpu_clock: u64) {
+        if self.tx_shift.is_none() {
+            return;
+        }
+        self.tx_wait = self.tx_wait.saturating_sub(cycles);
+        if self.tx_wait == 0 {
+            self.sent = self.tx_shift.take();
+            self.load_shift(cpu_clock);
+        }
+    }
+
+    fn rr0(&self) -> Byte {
+        let mut value = 0;
+        if !self.rx.is_empty() {
+            value |= RR0_RX_AVAILABLE;
+        }
+        if self.tx_buffer.is_none() {
+            value |= RR0_TX_EMPTY;
+        }
+        if self.dcd {
+            value |= RR0_DCD;
+        }
+        if self.cts {
+            value |= RR0_CTS;
+        }
+        if self.tx_shift.is_none() {
+            value |= RR0_TX_UNDERRUN;
+        }
+        value
+    }
+
+    fn rr1(&self) -> Byte {
+        let mut value = 0;
+        if self.tx_buffer.is_none() && self.tx_shift.is_none() {
+            value |= RR1_ALL_SENT;
+        }
+        if self.overrun {
+            value |= RR1_OVERRUN;
+        }
+        value
+    }
+
+    // Rx interrupt modes of WR1: 1 on the first character, 2 on every character, 3 only on
+    // special conditions, which interrupt in the other modes too.
+    fn pending(&self) -> Byte {
+        let rx = match (self.wr[1] >> 3) & 3 {
+            0 => false,
+            1 => self.rx_first_ip || self.overrun,
+            2 => !self.rx.is_empty() || self.overrun,
+            _ => self.overrun,
+        };
+        let mut ip = 0;
+        if rx {
+            ip |= IP_RX;
+        }
+        if self.tx_ip && (self.wr[1] & WR1_TX_INT_ENABLE) != 0 {
+            ip |= IP_TX;
+        }
+        if self.ext_ip && (self.wr[1] & WR1_EXT_INT_ENABLE) != 0 {
+            ip |= IP_EXT;
+        }
+        ip
+    }
+}
+
+// Z8530 SCC at $e98000: the command and data ports of channel B, then of channel A. Channel
+// B takes the mouse and channel A is the RS-232C port. Both channels share the interrupt
+// logic, on level 5.
+#[derive(Serialize, Deserialize)]
+pub struct Scc {
+    cpu_clock: u64,  // Hz, to run the transmitters.
+    channels: RefCell<[Channel; 2]>,  // Reads move the register pointer and pop received bytes.
+    vector: Byte,  // WR2
+    wr9: Byte,  // Master interrupt control.
+    ius: Byte,  // Interrupts under service, in the IP bit layout.
+}
+
+impl Scc {
+    pub fn new(cpu_clock: u32) -> Self {
+        Self {
+            cpu_clock: cpu_clock as u64,
+            channels: RefCell::new([Channel::new(), Channel::new()]),
+            vector: 0,
+            wr9: 0,
+            ius: 0,
+        }
+    }
+
+    pub fn ready_to_receive(&self, channel: usize) -> bool {
+        self.channels.borrow()[channel].rx.len() < RX_FIFO_SIZE
+    }
+
+    // A byte arrives on `channel`. Lost while the receiver is disabled; with the FIFO full,
+    // it is an overrun.
+    pub fn receive(&mut self, channel: usize, value: Byte) {
+        self.channels.get_mut()[channel].receive(value);
+    }
+
+    // The last byte sent on `channel`, if not taken yet.
+    pub fn take_transmitted(&mut self, channel: usize) -> Option<Byte> {
+        self.channels.get_mut()[channel].sent.take()
+    }
+
+    pub fn char_cycles(&self, channel: usize) -> Option<usize> {
+        self.channels.borrow()[channel].char_cycles(self.cpu_clock)
+    }
+
+    // Carrier detect input of `channel`; a change is an external/status interrupt.
+    pub fn set_dcd(&mut self, channel: usize, level: bool) {
+        let ch = &mut self.channels.get_mut()[channel];
+        if ch.dcd != level {
+            ch.dcd = level;
+            if (ch.wr[15] & WR15_DCD_INT_ENABLE) != 0 {
+                ch.ext_ip = true;
+            }
+        }
+    }
+
+    // Interrupt pending bits of both channels, in the RR3 layout.
+    fn pending_bits(channels: &[Channel; 2]) -> Byte {
+        (channels[CHANNEL_A].pending() << 3) | channels[CHANNEL_B].pending()
+    }
+
+    // Pending interrupts of higher priority than any under service.
+    fn pending(&self) -> Byte {
+        if (self.wr9 & WR9_MIE) == 0 {
+            return 0;
+        }
+        let blocked = match self.ius {
+            0 => 0,
+            ius => !0 >> ius.leading_zeros(),
+        };
+        Self::pending_bits(&self.channels.borrow()) & !blocked
+    }
+
+    // WR2 with the status of the highest pending interrupt in bits 1~3, or 4~6 when WR9
+    // selects status high, in reverse order.
+    fn modified_vector(&self, channels: &[Channel; 2], pending: Byte) -> Byte {
+        let status = match pending {
+            0 => VECTOR_NONE,
+            _ => {
+                let bit = 7 - pending.leading_zeros() as usize;
+                let channel = if bit >= 3 { CHANNEL_A } else { CHANNEL_B };
+                if (1 << (bit % 3)) == IP_RX && channels[channel].overrun {
+                    VECTOR_SPECIAL[channel]
+                } else {
+                    VECTOR_STATUS[bit]
+                }
+            },
+        };
+        if (self.wr9 & WR9_STATUS_HIGH) != 0 {
+            let reversed = ((status & 1) << 2) | (status & 2) | (status >> 2);
+            (self.vector & !0x70) | (reversed << 4)
+        } else {
+            (self.vector & !0x0e) | (status << 1)
+        }
+    }
+
+    fn read_register(&self, channels: &mut [Channel; 2], channel: usize, reg: usize) -> Byte {
+        let ch = &channels[channel];
+        match reg {
+            0 | 4 => ch.rr0(),
+            1 | 5 => ch.rr1(),
+            // Channel B reads the vector as modified by status, whether or not WR9 includes it.
+            2 | 6 if channel == CHANNEL_B => {
+                let pending = Self::pending_bits(channels);
+                self.modified_vector(channels, pending)
+            },
+            2 | 6 => self.vector,
+            3 | 7 if channel == CHANNEL_A => Self::pending_bits(channels),
+            3 | 7 => 0,
+            8 => channels[channel].read_data(),
+            9 | 13 => ch.wr[13],
+            11 | 15 => ch.wr[15],
+            12 => ch.wr[12],
+            _ => 0,  // RR10/RR14: loop and SDLC status.
+        }
+    }
+
+    // WR0: the register pointer, and a command.
+    fn command(&mut self, channel: usize, value: Byte) {
+        let ch = &mut self.channels.get_mut()[channel];
+        ch.pointer = (value & 7) as usize;
+        match (value >> 3) & 7 {
+            CMD_POINT_HIGH => ch.pointer |= 8,
+            CMD_RESET_EXT_STATUS => ch.ext_ip = false,
+            CMD_ENABLE_INT_NEXT_RX => ch.rx_first = true,
+            CMD_RESET_TX_INT => ch.tx_ip = false,
+            CMD_ERROR_RESET => ch.overrun = false,
+            CMD_RESET_HIGHEST_IUS if self.ius != 0 => self.ius &= !(0x80 >> self.ius.leading_zeros()),
+            _ => {},
+        }
+    }
+
+    fn reset_channel(&mut self, channel: usize) {
+        self.channels.get_mut()[channel].reset();
+        let bits = IP_RX | IP_TX | IP_EXT;
+        self.ius &= !if channel == CHANNEL_A { bits << 3 } else { bits };
+    }
+
+    fn write_register(&mut self, channel: usize, reg: usize, value: Byte) {
+        match reg {
+            0 => self.command(channel, value),
+            2 => self.vector = value,
+            8 => self.channels.get_mut()[channel].transmit(value, self.cpu_clock),
+            // The reset commands in bits 6~7: channel B, channel A or both.
+            9 => {
+                match value >> 6 {
+                    1 => self.reset_channel(CHANNEL_B),
+                    2 => self.reset_channel(CHANNEL_A),
+                    3 => self.reset(),
+                    _ => {},
+                }
+                self.wr9 = value & 0x3f;
+            },
+            _ => {
+                let ch = &mut self.channels.get_mut()[channel];
+                ch.wr[reg] = value;
+                // A newly enabled transmitter or clock starts sending a waiting character.
+                ch.load_shift(self.cpu_clock);
+            },
+        }
+    }
+}
+
+// Channel and whether it is the data port, for an offset.
+fn port(offset: Adr) -> Option<(usize, bool)> {
+    match offset {
+        0x01 => Some((CHANNEL_B, false)),
+        0x03 => Some((CHANNEL_B, true)),
+        0x05 => Some((CHANNEL_A, false)),
+        0x07 => Some((CHANNEL_A, true)),
+        _ => None,
+    }
+}
+
+impl Device for Scc {
+    fn read(&self, offset: Adr) -> Option<Byte> {
+        let (channel, data) = port(offset)?;
+        let mut channels = self.channels.borrow_mut();
+        let reg = if data {
+            8
+        } else {
+            // The pointer goes back to 0 after each access.
+            std::mem::take(&mut channels[channel].pointer)
+        };
+        Some(self.read_register(&mut channels, channel, reg))
+    }
+
+    fn write(&mut self, offset: Adr, value: Byte) {
+        let (channel, data) = match port(offset) {
+            Some(port) => port,
+            None => return,
+        };
+        let reg = if data {
+            8
+        } else {
+            std::mem::take(&mut self.channels.get_mut()[channel].pointer)
+        };
+        self.write_register(channel, reg, value);
+    }
+
+    fn tick(&mut self, cycles: usize) {
+        let cpu_clock = self.cpu_clock;
+        for ch in self.channels.get_mut().iter_mut() {
+            ch.tick(cycles, cpu_clock);
+        }
+    }
+
+    // Hardware reset: the line inputs stay.
+    fn reset(&mut self) {
+        self.reset_channel(CHANNEL_A);
+        self.reset_channel(CHANNEL_B);
+        self.wr9 = 0;
+        self.ius = 0;
+    }
+
+    fn irq(&self) -> Word {
+        if self.pending() != 0 { SCC_LEVEL } else { 0 }
+    }
+
+    // The highest priority pending interrupt goes under service, holding off itself and the
+    // lower ones until a reset highest IUS command. Without a vector (NV), nothing responds.
+    fn acknowledge(&mut self) -> InterruptAck {
+        let pending = self.pending();
+        if pending == 0 {
+            return InterruptAck::Spurious;
+        }
+        self.ius |= 0x80 >> pending.leading_zeros();
+        if (self.wr9 & WR9_NV) != 0 {
+            return InterruptAck::Spurious;
+        }
+        let vector = if (self.wr9 & WR9_VIS) != 0 {
+            self.modified_vector(&self.channels.borrow(), pending)
+        } else {
+            self.vector
+        };
+        InterruptAck::Vector(vector)
+    }
+
+    fn next_event(&self) -> Option<usize> {
+        self.channels.borrow().iter()
+            .filter(|ch| ch.tx_shift.is_some())
+            .map(|ch| ch.tx_wait.max(1))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPU_CLOCK: u32 = 10_000_000;
+    const COMMAND_A: Adr = 0x05;
+    const DATA_A: Adr = 0x07;
+    const COMMAND_B: Adr = 0x01;
+
+    fn write_register(scc: &mut Scc, port: Adr, reg: Byte, value: Byte) {
+        if reg != 0 {
+            let point_high = if reg >= 8 { CMD_POINT_HIGH << 3 } else { 0 };
+            scc.write(port, point_high | (reg & 7));
+        }
+        scc.write(port, value);
+    }
+
+    fn read_register(scc: &mut Scc, port: Adr, reg: Byte) -> Byte {
+        scc.write(port, reg);
+        scc.read(port).unwrap()
+    }
+
+    fn vector(scc: &mut Scc) -> Option<Byte> {
+        match scc.acknowledge() {
+            InterruptAck::Vector(vector) => Some(vector),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_modified_vector() {
+        let mut scc = Scc::new(CPU_CLOCK);
+        write_register(&mut scc, COMMAND_A, 2, 0x40);
+        write_register(&mut scc, COMMAND_A, 9, WR9_MIE | WR9_VIS);
+        write_register(&mut scc, COMMAND_A, 1, 0x10);  // Rx interrupt on every character
+        write_register(&mut scc, COMMAND_A, 3, WR3_RX_ENABLE);
+
+        scc.receive(CHANNEL_A, 0x12);
+        assert_eq!(SCC_LEVEL, scc.irq());
+        assert_eq!(IP_RX << 3, read_register(&mut scc, COMMAND_A, 3));
+        assert_eq!(0x4c, read_register(&mut scc, COMMAND_B, 2));  // Channel A Rx available
+        assert_eq!(0x40, read_register(&mut scc, COMMAND_A, 2));
+        assert_eq!(Some(0x4c), vector(&mut scc));
+        // Under service until reset highest IUS.
+        assert_eq!(0, scc.irq());
+        scc.write(COMMAND_A, CMD_RESET_HIGHEST_IUS << 3);
+        assert_eq!(SCC_LEVEL, scc.irq());
+
+        // Overrunning the FIFO is a special receive condition.
+        for value in 0..3 {
+            scc.receive(CHANNEL_A, value);
+        }
+        assert_eq!(Some(0x4e), vector(&mut scc));
+        scc.write(COMMAND_A, CMD_ERROR_RESET << 3);
+        scc.write(COMMAND_A, CMD_RESET_HIGHEST_IUS << 3);
+
+        // Status high puts it in bits 4~6, reversed.
+        write_register(&mut scc, COMMAND_A, 9, WR9_MIE | WR9_VIS | WR9_STATUS_HIGH);
+        assert_eq!(Some(0x30), vector(&mut scc));
+    }
+
+    #[test]
+    fn test_baud_rate_generator() {
+        let mut scc = Scc::new(CPU_CLOCK);
+        write_register(&mut scc, COMMAND_A, 4, 0x44);  // x16 clock, 1 stop bit
+        write_register(&mut scc, COMMAND_A, 5, 0x60 | WR5_TX_ENABLE);  // Tx 8 bits
+        write_register(&mut scc, COMMAND_A, 12, 14);  // 9600 baud, about
+        write_register(&mut scc, COMMAND_A, 13, 0);
+        scc.write(DATA_A, 0x55);
+        assert_eq!(None, scc.char_cycles(CHANNEL_A));
+        assert_eq!(None, scc.next_event());
+
+        // 10 bits of 2 * (14 + 2) * 16 PCLKs, at 5MHz.
+        write_register(&mut scc, COMMAND_A, 14, WR14_BRG_ENABLE);
+        assert_eq!(Some(10240), scc.char_cycles(CHANNEL_A));
+        assert_eq!(Some(10240), scc.next_event());
+        scc.write(DATA_A, 0xaa);
+        assert_eq!(0, read_register(&mut scc, COMMAND_A, 0) & RR0_TX_EMPTY);
+        scc.tick(10239);
+        assert_eq!(None, scc.take_transmitted(CHANNEL_A));
+        scc.tick(1);
+        assert_eq!(Some(0x55), scc.take_transmitted(CHANNEL_A));
+        assert_eq!(RR0_TX_EMPTY, read_register(&mut scc, COMMAND_A, 0) & RR0_TX_EMPTY);
+        scc.tick(10240);
+        assert_eq!(Some(0xaa), scc.take_transmitted(CHANNEL_A));
+        assert_eq!(RR1_ALL_SENT, read_register(&mut scc, COMMAND_A, 1) & RR1_ALL_SENT);
+
+        // Parity and 2 stop bits make 12.
+        write_register(&mut scc, COMMAND_A, 4, 0x4c | WR4_PARITY_ENABLE);
+        assert_eq!(Some(12288), scc.char_cycles(CHANNEL_A));
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Serialize, Deserialize};
+
+use super::Device;
+use super::super::super::types::{Adr, Byte};
+
+const PORT4: Adr = 0x07;
+const KEY_CONTROL: Byte = 0x08;  // In PORT4: the keyboard may send key data.
+
+// System port at $e8e000: contrast, display and keyboard control, NMI reset etc.
+#[derive(Serialize, Deserialize)]
+pub struct SystemPort {
+    port4: Byte,
+}
+
+impl Default for SystemPort {
+    fn default() -> Self {
+        Self { port4: KEY_CONTROL }
+    }
+}
+
+impl SystemPort {
+    pub fn key_enabled(&self) -> bool {
+        (self.port4 & KEY_CONTROL) != 0
+    }
+}
+
+impl Device for SystemPort {
+    fn read(&self, offset: Adr) -> Option<Byte> {
+        match offset {
+            PORT4 => Some(self.port4),
+            // TODO: Implement.
+            _ => Some(0),
+        }
+    }
+
+    fn write(&mut self, offset: Adr, value: Byte) {
+        // TODO: The other ports.
+        if offset == PORT4 {
+            self.port4 = value;
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::fmt;
+
+use super::super::cpu::disasm::SymbolTable;
+use super::super::types::{Adr, Byte, Long, Word};
+
+const X_MAGIC: Word = 0x4855;  // "HU"
+const X_HEADER_SIZE: usize = 0x40;
+const Z_MAGIC: Word = 0x601a;  // bra.s over the header
+const Z_HEADER_SIZE: usize = 0x1c;
+
+// Symbol types of .X files: absolute, text, data, bss and stack.
+const SYMBOL_ABSOLUTE: Word = 0x0200;
+const SYMBOL_STACK: Word = 0x0204;
+
+#[derive(Debug)]
+pub enum ExecutableError {
+    BadMagic,
+    Truncated,
+    BadRelocation(usize),  // Offset into the image.
+}
+
+impl fmt::Display for ExecutableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecutableError::BadMagic => write!(f, "not a Human68k executable"),
+            ExecutableError::Truncated => write!(f, "truncated executable"),
+            ExecutableError::BadRelocation(offset) => write!(f, "relocation outside the image at ${:x}", offset),
+        }
+    }
+}
+
+impl Error for ExecutableError {}
+
+// A Human68k executable laid out in memory: text and data, relocated for `load_address`.
+pub struct Executable {
+    pub load_address: Adr,
+    pub entry: Adr,
+    pub image: Vec<Byte>,
+    pub text_size: usize,
+    pub data_size: usize,
+    pub bss_size: usize,
+    pub symbols: SymbolTable,
+}
+
+fn word(data: &[Byte], offset: usize) -> Result<Word, ExecutableError> {
+    match data.get(offset..offset + 2) {
+        Some(bytes) => Ok(Word::from_be_bytes([bytes[0], bytes[1]])),
+        None => Err(ExecutableError::Truncated),
+    }
+}
+
+fn long(data: &[Byte], offset: usize) -> Result<Long, ExecutableError> {
+    Ok(((word(data, offset)? as Long) << 16) | word(data, offset + 2)? as Long)
+}
+
+fn section(data: &[Byte], offset: usize, size: usize) -> Result<&[Byte], ExecutableError> {
+    data.get(offset..offset + size).ok_or(ExecutableError::Truncated)
+}
+
+impl Executable {
+    // .X: relocatable, loaded at `load_address` or at its base address if not given.
+    pub fn parse_x(data: &[Byte], load_address: Option<Adr>) -> Result<Self, ExecutableError> {
+        if word(data, 0)? != X_MAGIC {
+            return Err(ExecutableError::BadMagic);
+        }
+        let base = long(data, 0x04)?;
+        let entry = long(data, 0x08)?;
+        let text_size = long(data, 0x0c)? as usize;
+        let data_size = long(data, 0x10)? as usize;
+        let bss_size = long(data, 0x14)? as usize;
+        let reloc_size = long(data, 0x18)? as usize;
+        let symbol_size = long(data, 0x1c)? as usize;
+
+        let load_address = load_address.unwrap_or(base);
+        let delta = load_address.wrapping_sub(base);
+        let mut image = section(data, X_HEADER_SIZE, text_size + data_size)?.to_vec();
+        let relocs = section(data, X_HEADER_SIZE + image.len(), reloc_size)?;
+        relocate(&mut image, relocs, delta)?;
+
+        let mut symbols = SymbolTable::new();
+        let table = section(data, X_HEADER_SIZE + image.len() + reloc_size, symbol_size)?;
+        let mut offset = 0;
+        while offset < table.len() {
+            let kind = word(table, offset)?;
+            let value = long(table, offset + 2)?;
+            let name = &table[offset + 6..];
+            let len = name.iter().position(|&c| c == 0).ok_or(ExecutableError::Truncated)?;
+            match kind {
+                SYMBOL_ABSOLUTE => symbols.insert(value, &String::from_utf8_lossy(&name[..len])),
+                k if k > SYMBOL_ABSOLUTE && k <= SYMBOL_STACK => {
+                    symbols.insert(value.wrapping_add(delta), &String::from_utf8_lossy(&name[..len]));
+                },
+                _ => {},
+            }
+            // The name is NUL-terminated and padded to an even length.
+            offset += 6 + ((len + 2) & !1);
+        }
+
+        Ok(Self { load_address, entry: entry.wrapping_add(delta), image, text_size, data_size, bss_size, symbols })
+    }
+
+    // .Z: a flat image that runs at the address given in its header.
+    pub fn parse_z(data: &[Byte]) -> Result<Self, ExecutableError> {
+        if word(data, 0)? != Z_MAGIC {
+            return Err(ExecutableError::BadMagic);
+        }
+        let text_size = long(data, 0x02)? as usize;
+        let data_size = long(data, 0x06)? as usize;
+        let bss_size = long(data, 0x0a)? as usize;
+        let load_address = long(data, 0x16)?;
+        let image = section(data, Z_HEADER_SIZE, text_size + data_size)?.to_vec();
+        Ok(Self { load_address, entry: load_address, image, text_size, data_size, bss_size, symbols: SymbolTable::new() })
+    }
+}
+
+// Each relocation entry is the distance from the previous one (a word, or 1 followed by a
+// long); an odd distance marks a word-sized fixup instead of a long one.
+fn relocate(image: &mut [Byte], relocs: &[Byte], delta: Long) -> Result<(), ExecutableError> {
+    let mut pos = 0;
+    let mut offset = 0;
+    while offset < relocs.len() {
+        let mut distance = word(relocs, offset)? as usize;
+        offset += 2;
+        if distance == 1 {
+            distance = long(relocs, offset)? as usize;
+            offset += 4;
+        }
+        pos += distance & !1;
+        if (distance & 1) != 0 {
+            let value = word(image, pos).map_err(|_| ExecutableError::BadRelocation(pos))?;
+            image[pos..pos + 2].copy_from_slice(&value.wrapping_add(delta as Word).to_be_bytes());
+        } else {
+            let value = long(image, pos).map_err(|_| ExecutableError::BadRelocation(pos))?;
+            image[pos..pos + 4].copy_from_slice(&value.wrapping_add(delta).to_be_bytes());
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,20 @@
 mod bus;
+mod devices;
+mod human68k;
+mod runner;
+mod serial;
+mod state;
+mod symbols;
+mod syscalls;
 mod vram;
+#[allow(clippy::module_inception)]
 mod x68k;
 
-pub use self::x68k::X68k;
+pub use self::devices::Key;
+pub use self::human68k::{Executable, ExecutableError};
+pub use self::state::StateError;
+pub use self::runner::{Command, Event, Runner};
+pub use self::serial::{open_line, SerialLine};
+pub use self::symbols::hardware_symbols;
+pub use self::syscalls::call_name;
+pub use self::x68k::{X68k, STOCK_CLOCK};
@@ -0,0 +1,122 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use super::devices::Key;
+use super::state::StateError;
+use super::x68k::X68k;
+use super::super::cpu::{CpuError, StepInfo};
+use super::super::types::Byte;
+
+const SLICE_MICROS: u32 = 1000;  // Emulated time run between looking at commands.
+
+pub enum Command {
+    Run,
+    Pause,
+    Step,  // Execute one instruction while paused.
+    SaveState,
+    LoadState(Vec<u8>),
+    Key(Key, bool),  // Pressed or released.
+    Mouse { dx: i32, dy: i32, left: bool, right: bool },
+}
+
+pub enum Event {
+    Paused,
+    Stepped(StepInfo),
+    Stopped(CpuError),  // Execution failed or hit a breakpoint; paused until Run.
+    Saved(Vec<u8>),
+    Loaded(Result<(), StateError>),
+    Leds(Byte),  // The keyboard LEDs changed, see X68k::keyboard_leds.
+}
+
+// Runs the machine on its own thread, driven by commands, so that the caller
+// (e.g. a UI) never blocks on emulation. Starts paused.
+pub struct Runner {
+    commands: Sender<Command>,
+    events: Receiver<Event>,
+    thread: JoinHandle<X68k>,
+}
+
+impl Runner {
+    pub fn spawn(x68k: X68k) -> Self {
+        let (commands, command_rx) = mpsc::channel();
+        let (event_tx, events) = mpsc::channel();
+        let thread = thread::spawn(move || run_loop(x68k, command_rx, event_tx));
+        Self { commands, events, thread }
+    }
+
+    pub fn send(&self, command: Command) {
+        // The thread only exits through quit, which consumes the runner.
+        self.commands.send(command).ok();
+    }
+
+    pub fn try_event(&self) -> Option<Event> {
+        self.events.try_recv().ok()
+    }
+
+    pub fn wait_event(&self) -> Event {
+        self.events.recv().expect("emulation thread panicked")
+    }
+
+    // Stop the thread and get the machine back.
+    pub fn quit(self) -> X68k {
+        drop(self.commands);
+        self.thread.join().expect("emulation thread panicked")
+    }
+}
+
+fn run_loop(mut x68k: X68k, commands: Receiver<Command>, events: Sender<Event>) -> X68k {
+    let mut running = false;
+    let mut leds = x68k.keyboard_leds();
+    loop {
+        let command = if running { commands.try_recv().ok() } else {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => break,  // Runner dropped.
+            }
+        };
+        let event = match command {
+            Some(Command::Run) => {
+                running = true;
+                None
+            },
+            Some(Command::Pause) => {
+                running = false;
+                Some(Event::Paused)
+            },
+            Some(Command::Step) if !running => Some(match x68k.step() {
+                Ok(info) => Event::Stepped(info),
+                Err(err) => Event::Stopped(err),
+            }),
+            Some(Command::Step) => None,
+            Some(Command::SaveState) => Some(Event::Saved(x68k.save_state())),
+            Some(Command::LoadState(data)) => Some(Event::Loaded(x68k.load_state(&data))),
+            Some(Command::Key(key, pressed)) => {
+                x68k.key_event(key, pressed);
+                None
+            },
+            Some(Command::Mouse { dx, dy, left, right }) => {
+                x68k.mouse_event(dx, dy, left, right);
+                None
+            },
+            None => match x68k.run_micros(SLICE_MICROS) {
+                Ok(_) => None,
+                Err(err) => {
+                    running = false;
+                    Some(Event::Stopped(err))
+                },
+            },
+        };
+        if let Some(event) = event {
+            if events.send(event).is_err() {
+                break;
+            }
+        }
+        if x68k.keyboard_leds() != leds {
+            leds = x68k.keyboard_leds();
+            if events.send(Event::Leds(leds)).is_err() {
+                break;
+            }
+        }
+    }
+    x68k
+}
@@ -0,0 +1,201 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use super::super::types::Byte;
+
+// The host end of the RS-232C port. Called from the emulation thread, so it never blocks.
+pub trait SerialLine: Send {
+    // The next byte from the host, if one arrived.
+    fn read(&mut self) -> Option<Byte>;
+
+    // A byte sent by the machine, lost if nobody is attached.
+    fn write(&mut self, value: Byte);
+
+    // Whether somebody is attached, seen by the machine as DCD.
+    fn connected(&self) -> bool { true }
+}
+
+// A blocking host stream, read and written by threads of its own so that a peer not
+// reading doesn't hold up the emulation.
+struct Stream {
+    tx: Sender<Byte>,
+    rx: Receiver<Byte>,
+    open: Arc<AtomicBool>,  // Cleared once the stream ends or fails.
+}
+
+impl Stream {
+    fn new(mut reader: impl Read + Send + 'static, mut writer: impl Write + Send + 'static) -> Self {
+        let open = Arc::new(AtomicBool::new(true));
+        let (to_line, rx) = mpsc::channel();
+        let reader_open = open.clone();
+        thread::spawn(move || {
+            let mut buf = [0; 256];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        // The line was dropped.
+                        if buf[..n].iter().any(|&b| to_line.send(b).is_err()) {
+                            break;
+                        }
+                    },
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                    Err(_) => break,
+                }
+            }
+            reader_open.store(false, Ordering::Relaxed);
+        });
+        let (tx, from_line) = mpsc::channel::<Byte>();
+        let writer_open = open.clone();
+        thread::spawn(move || {
+            for value in from_line {
+                if writer.write_all(&[value]).is_err() {
+                    writer_open.store(false, Ordering::Relaxed);
+                    break;
+                }
+            }
+        });
+        Self { tx, rx, open }
+    }
+
+    fn tcp(stream: TcpStream) -> io::Result<Self> {
+        // Accepted sockets inherit non-blocking mode from the listener on some platforms.
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream.try_clone()?, stream))
+    }
+}
+
+impl SerialLine for Stream {
+    fn read(&mut self) -> Option<Byte> {
+        self.rx.try_recv().ok()
+    }
+
+    fn write(&mut self, value: Byte) {
+        self.tx.send(value).ok();
+    }
+
+    fn connected(&self) -> bool {
+        self.open.load(Ordering::Relaxed)
+    }
+}
+
+// Takes one client at a time; another can connect once it hangs up.
+struct TcpServer {
+    listener: TcpListener,
+    client: Option<Stream>,
+}
+
+impl TcpServer {
+    fn bind(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener, client: None })
+    }
+}
+
+impl SerialLine for TcpServer {
+    fn read(&mut self) -> Option<Byte> {
+        if let Some(value) = self.client.as_mut().and_then(|client| client.read()) {
+            return Some(value);
+        }
+        if !self.connected() {
+            self.client = self.listener.accept().ok().and_then(|(stream, _)| Stream::tcp(stream).ok());
+        }
+        None
+    }
+
+    fn write(&mut self, value: Byte) {
+        if let Some(client) = self.client.as_mut() {
+            client.write(value);
+        }
+    }
+
+    fn connected(&self) -> bool {
+        self.client.as_ref().is_some_and(|client| client.connected())
+    }
+}
+
+// The master side of a pseudo terminal, for terminal programs on the host to open the slave.
+#[cfg(unix)]
+struct Pty {
+    stream: Stream,
+    _slave: std::fs::File,  // Held open so that reading the master doesn't fail before a program opens it.
+}
+
+#[cfg(unix)]
+impl Pty {
+    // Returns the path of the slave too.
+    fn open() -> io::Result<(Self, String)> {
+        use std::ffi::CStr;
+        use std::fs::{File, OpenOptions};
+        use std::os::unix::fs::OpenOptionsExt;
+        use std::os::unix::io::{AsRawFd, FromRawFd};
+
+        let fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let master = unsafe { File::from_raw_fd(fd) };
+        if unsafe { libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 } {
+            return Err(io::Error::last_os_error());
+        }
+        let name = unsafe { libc::ptsname(fd) };
+        if name.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let path = unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned();
+        let slave = OpenOptions::new().read(true).write(true).custom_flags(libc::O_NOCTTY).open(&path)?;
+
+        // Raw mode: bytes pass through unchanged, without echo.
+        unsafe {
+            let mut termios = std::mem::zeroed();
+            if libc::tcgetattr(slave.as_raw_fd(), &mut termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            libc::cfmakeraw(&mut termios);
+            if libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        let stream = Stream::new(master.try_clone()?, master);
+        Ok((Self { stream, _slave: slave }, path))
+    }
+}
+
+#[cfg(unix)]
+impl SerialLine for Pty {
+    fn read(&mut self) -> Option<Byte> {
+        self.stream.read()
+    }
+
+    fn write(&mut self, value: Byte) {
+        self.stream.write(value);
+    }
+}
+
+// Opens the host end given on the command line, returning it with where to find it:
+//   listen:ADDR   TCP server, e.g. listen:127.0.0.1:2323
+//   connect:ADDR  TCP client
+//   pty           pseudo terminal (Unix)
+pub fn open_line(spec: &str) -> io::Result<(Box<dyn SerialLine>, String)> {
+    if let Some(addr) = spec.strip_prefix("listen:") {
+        let server = TcpServer::bind(addr)?;
+        let description = format!("listening on {}", server.listener.local_addr()?);
+        return Ok((Box::new(server), description));
+    }
+    if let Some(addr) = spec.strip_prefix("connect:") {
+        let stream = Stream::tcp(TcpStream::connect(addr)?)?;
+        return Ok((Box::new(stream), format!("connected to {}", addr)));
+    }
+    #[cfg(unix)]
+    if spec == "pty" {
+        let (pty, path) = Pty::open()?;
+        return Ok((Box::new(pty), path));
+    }
+    Err(io::Error::new(ErrorKind::InvalidInput, format!("unknown serial line: {}", spec)))
+}
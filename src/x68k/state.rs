@@ -0,0 +1,45 @@
+use std::fmt;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+// Save state file: magic, format version (little endian), then the bincode-encoded machine.
+const MAGIC: &[u8; 8] = b"X68KSTAT";
+pub const VERSION: u32 = 3;
+
+#[derive(Debug)]
+pub enum StateError {
+    NotSaveState,
+    UnsupportedVersion(u32),  // Written by a newer or incompatible build.
+    Corrupt(String),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::NotSaveState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => write!(f, "unsupported save state version {}", version),
+            StateError::Corrupt(reason) => write!(f, "corrupt save state: {}", reason),
+        }
+    }
+}
+
+pub fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&VERSION.to_le_bytes());
+    bincode::serialize_into(&mut data, value).expect("save state serialization");
+    data
+}
+
+pub fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, StateError> {
+    if data.len() < MAGIC.len() + 4 || &data[..MAGIC.len()] != MAGIC {
+        return Err(StateError::NotSaveState);
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&data[MAGIC.len()..MAGIC.len() + 4]);
+    let version = u32::from_le_bytes(version);
+    if version != VERSION {
+        return Err(StateError::UnsupportedVersion(version));
+    }
+    bincode::deserialize(&data[MAGIC.len() + 4..]).map_err(|err| StateError::Corrupt(err.to_string()))
+}
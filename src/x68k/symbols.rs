@@ -0,0 +1,57 @@
+use super::super::cpu::disasm::SymbolTable;
+use super::super::types::Adr;
+
+const MFP_REGISTERS: [&str; 24] = [
+    "GPIP", "AER", "DDR", "IERA", "IERB", "IPRA", "IPRB", "ISRA", "ISRB", "IMRA", "IMRB", "VR",
+    "TACR", "TBCR", "TCDCR", "TADR", "TBDR", "TCDR", "TDDR", "SCR", "UCR", "RSR", "TSR", "UDR",
+];
+
+// Per-channel registers of the HD63450 DMAC and their offsets.
+const DMAC_REGISTERS: [(Adr, &str); 17] = [
+    (0x00, "CSR"), (0x01, "CER"), (0x04, "DCR"), (0x05, "OCR"), (0x06, "SCR"), (0x07, "CCR"),
+    (0x0a, "MTC"), (0x0c, "MAR"), (0x14, "DAR"), (0x1a, "BTC"), (0x1c, "BAR"), (0x25, "NIV"),
+    (0x27, "EIV"), (0x29, "MFC"), (0x2d, "CPR"), (0x31, "DFC"), (0x39, "BFC"),
+];
+
+const IO_PORTS: [(Adr, &str); 31] = [
+    (0xe80480, "CRTC_OP"),
+    (0xe82000, "GPALETTE"), (0xe82200, "TPALETTE"),
+    (0xe82400, "VC_R0"), (0xe82500, "VC_R1"), (0xe82600, "VC_R2"),
+    (0xe840ff, "DMAC_GCR"),
+    (0xe86001, "AREASET"),
+    (0xe8c001, "PRN_DATA"), (0xe8c003, "PRN_STROBE"),
+    (0xe90001, "OPM_ADDR"), (0xe90003, "OPM_DATA"),
+    (0xe92001, "ADPCM_CMD"), (0xe92003, "ADPCM_DATA"),
+    (0xe94001, "FDC_STAT"), (0xe94003, "FDC_DATA"), (0xe94005, "FDD_CTRL"), (0xe94007, "FDD_SELECT"),
+    (0xe96001, "SASI_DATA"), (0xe96003, "SASI_STAT"),
+    (0xe98001, "SCC_B_CMD"), (0xe98003, "SCC_B_DATA"), (0xe98005, "SCC_A_CMD"), (0xe98007, "SCC_A_DATA"),
+    (0xe9a001, "PPI_A"), (0xe9a003, "PPI_B"), (0xe9a005, "PPI_C"), (0xe9a007, "PPI_CTRL"),
+    (0xe9c001, "IOC_STAT"), (0xe9c003, "IOC_VECTOR"),
+    (0xed0000, "SRAM"),
+];
+
+// Names of the X68000 I/O registers, for disassembly listings.
+pub fn hardware_symbols() -> SymbolTable {
+    let mut table = SymbolTable::new();
+    for i in 0..24 {
+        table.insert(0xe80000 + i * 2, &format!("CRTC_R{:02}", i));
+    }
+    for ch in 0..4 {
+        for &(offset, name) in DMAC_REGISTERS.iter() {
+            table.insert(0xe84000 + ch * 0x40 + offset, &format!("DMAC{}_{}", ch, name));
+        }
+    }
+    for (i, name) in MFP_REGISTERS.iter().enumerate() {
+        table.insert(0xe88001 + i as Adr * 2, &format!("MFP_{}", name));
+    }
+    for i in 0..16 {
+        table.insert(0xe8a001 + i * 2, &format!("RTC_R{:02}", i));
+    }
+    for i in 0..7 {
+        table.insert(0xe8e001 + i * 2, &format!("SYSPORT{}", i + 1));
+    }
+    for &(adr, name) in IO_PORTS.iter() {
+        table.insert(adr, name);
+    }
+    table
+}
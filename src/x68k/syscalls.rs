@@ -0,0 +1,116 @@
+use super::super::cpu::{StdoutTrace, TraceEvent, TraceSink};
+use super::super::types::{Byte, Long, Word};
+
+const TRAP_IOCS: Word = 0x4e4f;  // trap #15, with the call number in D0.b.
+const DOS_CALL: Word = 0xff00;  // Human68k DOS calls are line F words $ffxx.
+
+const IOCS_CALLS: [(Byte, &str); 192] = [
+    (0x00, "_B_KEYINP"), (0x01, "_B_KEYSNS"), (0x02, "_B_SFTSNS"), (0x03, "_KEY_INIT"),
+    (0x04, "_BITSNS"), (0x05, "_SKEYSET"), (0x06, "_LEDCTRL"), (0x07, "_LEDSET"),
+    (0x08, "_KEYDLY"), (0x09, "_KEYREP"), (0x0c, "_TVCTRL"), (0x0d, "_LEDMOD"),
+    (0x0e, "_TGUSEMD"), (0x0f, "_DEFCHR"), (0x10, "_CRTMOD"), (0x11, "_CONTRAST"),
+    (0x12, "_HSVTORGB"), (0x13, "_TPALET"), (0x14, "_TPALET2"), (0x15, "_TCOLOR"),
+    (0x16, "_FNTADR"), (0x17, "_VRAMGET"), (0x18, "_VRAMPUT"), (0x19, "_FNTGET"),
+    (0x1a, "_TEXTGET"), (0x1b, "_TEXTPUT"), (0x1c, "_CLIPPUT"), (0x1d, "_SCROLL"),
+    (0x1e, "_B_CURON"), (0x1f, "_B_CUROFF"), (0x20, "_B_PUTC"), (0x21, "_B_PRINT"),
+    (0x22, "_B_COLOR"), (0x23, "_B_LOCATE"), (0x24, "_B_DOWN_S"), (0x25, "_B_UP_S"),
+    (0x26, "_B_UP"), (0x27, "_B_DOWN"), (0x28, "_B_RIGHT"), (0x29, "_B_LEFT"),
+    (0x2a, "_B_CLR_ST"), (0x2b, "_B_ERA_ST"), (0x2c, "_B_INS"), (0x2d, "_B_DEL"),
+    (0x2e, "_B_CONSOL"), (0x2f, "_B_PUTMES"), (0x30, "_SET232C"), (0x31, "_LOF232C"),
+    (0x32, "_INP232C"), (0x33, "_ISNS232C"), (0x34, "_OSNS232C"), (0x35, "_OUT232C"),
+    (0x38, "_SETFNTADR"), (0x3b, "_JOYGET"), (0x3c, "_INIT_PRN"), (0x3d, "_SNSPRN"),
+    (0x3e, "_OUTLPT"), (0x3f, "_OUTPRN"), (0x40, "_B_SEEK"), (0x41, "_B_VERIFY"),
+    (0x42, "_B_READDI"), (0x43, "_B_DSKINI"), (0x44, "_B_DRVSNS"), (0x45, "_B_WRITE"),
+    (0x46, "_B_READ"), (0x47, "_B_RECALI"), (0x48, "_B_ASSIGN"), (0x49, "_B_WRITED"),
+    (0x4a, "_B_READID"), (0x4b, "_B_BADFMT"), (0x4c, "_B_READDL"), (0x4d, "_B_FORMAT"),
+    (0x4e, "_B_DRVCHK"), (0x4f, "_B_EJECT"), (0x50, "_DATEBCD"), (0x51, "_DATESET"),
+    (0x52, "_TIMEBCD"), (0x53, "_TIMESET"), (0x54, "_DATEGET"), (0x55, "_TIMEGET"),
+    (0x56, "_DATEBIN"), (0x57, "_TIMEBIN"), (0x58, "_DATECNV"), (0x59, "_TIMECNV"),
+    (0x5a, "_DATEASC"), (0x5b, "_TIMEASC"), (0x5c, "_DAYASC"), (0x5d, "_ALARMMOD"),
+    (0x5e, "_ALARMSET"), (0x5f, "_ALARMGET"), (0x60, "_ADPCMOUT"), (0x61, "_ADPCMINP"),
+    (0x62, "_ADPCMAOT"), (0x63, "_ADPCMAIN"), (0x64, "_ADPCMLOT"), (0x65, "_ADPCMLIN"),
+    (0x66, "_ADPCMSNS"), (0x67, "_ADPCMMOD"), (0x68, "_OPMSET"), (0x69, "_OPMSNS"),
+    (0x6a, "_OPMINTST"), (0x6b, "_TIMERDST"), (0x6c, "_VDISPST"), (0x6d, "_CRTCRAS"),
+    (0x6e, "_HSYNCST"), (0x6f, "_PRNINTST"), (0x70, "_MS_INIT"), (0x71, "_MS_CURON"),
+    (0x72, "_MS_CUROF"), (0x73, "_MS_STAT"), (0x74, "_MS_GETDT"), (0x75, "_MS_CURGT"),
+    (0x76, "_MS_CURST"), (0x77, "_MS_LIMIT"), (0x78, "_MS_OFFTM"), (0x79, "_MS_ONTM"),
+    (0x7a, "_MS_PATST"), (0x7b, "_MS_SEL"), (0x7c, "_MS_SEL2"), (0x7d, "_SKEY_MOD"),
+    (0x7e, "_DENSNS"), (0x7f, "_ONTIME"), (0x80, "_B_INTVCS"), (0x81, "_B_SUPER"),
+    (0x82, "_B_BPEEK"), (0x83, "_B_WPEEK"), (0x84, "_B_LPEEK"), (0x85, "_B_MEMSTR"),
+    (0x86, "_B_BPOKE"), (0x87, "_B_WPOKE"), (0x88, "_B_LPOKE"), (0x89, "_B_MEMSET"),
+    (0x8a, "_DMAMOVE"), (0x8b, "_DMAMOV_A"), (0x8c, "_DMAMOV_L"), (0x8d, "_DMAMODE"),
+    (0x8e, "_BOOTINF"), (0x8f, "_ROMVER"), (0x90, "_G_CLR_ON"), (0x94, "_GPALET"),
+    (0xa0, "_SFTJIS"), (0xa1, "_JISSFT"), (0xa2, "_AKCONV"), (0xa3, "_RMACNV"),
+    (0xa4, "_DAKJOB"), (0xa5, "_HANJOB"), (0xac, "_SYS_STAT"), (0xad, "_B_CONMOD"),
+    (0xae, "_OS_CURON"), (0xaf, "_OS_CUROF"), (0xb0, "_DRAWMODE"), (0xb1, "_APAGE"),
+    (0xb2, "_VPAGE"), (0xb3, "_HOME"), (0xb4, "_WINDOW"), (0xb5, "_WIPE"),
+    (0xb6, "_PSET"), (0xb7, "_POINT"), (0xb8, "_LINE"), (0xb9, "_BOX"),
+    (0xba, "_FILL"), (0xbb, "_CIRCLE"), (0xbc, "_PAINT"), (0xbd, "_SYMBOL"),
+    (0xbe, "_GETGRM"), (0xbf, "_PUTGRM"), (0xc0, "_SP_INIT"), (0xc1, "_SP_ON"),
+    (0xc2, "_SP_OFF"), (0xc3, "_SP_CGCLR"), (0xc4, "_SP_DEFCG"), (0xc5, "_SP_GTPCG"),
+    (0xc6, "_SP_REGST"), (0xc7, "_SP_REGGT"), (0xc8, "_BGSCRLST"), (0xc9, "_BGSCRLGT"),
+    (0xca, "_BGCTRLST"), (0xcb, "_BGCTRLGT"), (0xcc, "_BGTEXTCL"), (0xcd, "_BGTEXTST"),
+    (0xce, "_BGTEXTGT"), (0xcf, "_SPALET"), (0xd3, "_TXXLINE"), (0xd4, "_TXYLINE"),
+    (0xd5, "_TXLINE"), (0xd6, "_TXBOX"), (0xd7, "_TXFILL"), (0xd8, "_TXREV"),
+    (0xdf, "_TXRASCPY"), (0xfd, "_ABORTRST"), (0xfe, "_IPLERR"), (0xff, "_ABORTJOB"),
+];
+
+// Human68k v2 numbers $50-$5f, moved to $80-$8f in v3; both are accepted by v3.
+const DOS_CALLS: [(Byte, &str); 118] = [
+    (0x00, "_EXIT"), (0x01, "_GETCHAR"), (0x02, "_PUTCHAR"), (0x03, "_COMINP"),
+    (0x04, "_COMOUT"), (0x05, "_PRNOUT"), (0x06, "_INPOUT"), (0x07, "_INKEY"),
+    (0x08, "_GETC"), (0x09, "_PRINT"), (0x0a, "_GETS"), (0x0b, "_KEYSNS"),
+    (0x0c, "_KFLUSH"), (0x0d, "_FFLUSH"), (0x0e, "_CHGDRV"), (0x0f, "_DRVCTRL"),
+    (0x10, "_CONSNS"), (0x11, "_PRNSNS"), (0x12, "_CINSNS"), (0x13, "_COUTSNS"),
+    (0x17, "_FATCHK"), (0x18, "_HENDSP"), (0x19, "_CURDRV"), (0x1a, "_GETSS"),
+    (0x1b, "_FGETC"), (0x1c, "_FGETS"), (0x1d, "_FPUTC"), (0x1e, "_FPUTS"),
+    (0x1f, "_ALLCLOSE"), (0x20, "_SUPER"), (0x21, "_FNCKEY"), (0x22, "_KNJCTRL"),
+    (0x23, "_CONCTRL"), (0x24, "_KEYCTRL"), (0x25, "_INTVCS"), (0x26, "_PSPSET"),
+    (0x27, "_GETTIM2"), (0x28, "_SETTIM2"), (0x29, "_NAMESTS"), (0x2a, "_GETDATE"),
+    (0x2b, "_SETDATE"), (0x2c, "_GETTIME"), (0x2d, "_SETTIME"), (0x2e, "_VERIFY"),
+    (0x2f, "_DUP0"), (0x30, "_VERNUM"), (0x31, "_KEEPPR"), (0x32, "_GETDPB"),
+    (0x33, "_BREAKCK"), (0x34, "_DRVXCHG"), (0x35, "_INTVCG"), (0x36, "_DSKFRE"),
+    (0x37, "_NAMECK"), (0x39, "_MKDIR"), (0x3a, "_RMDIR"), (0x3b, "_CHDIR"),
+    (0x3c, "_CREATE"), (0x3d, "_OPEN"), (0x3e, "_CLOSE"), (0x3f, "_READ"),
+    (0x40, "_WRITE"), (0x41, "_DELETE"), (0x42, "_SEEK"), (0x43, "_CHMOD"),
+    (0x44, "_IOCTRL"), (0x45, "_DUP"), (0x46, "_DUP2"), (0x47, "_CURDIR"),
+    (0x48, "_MALLOC"), (0x49, "_MFREE"), (0x4a, "_SETBLOCK"), (0x4b, "_EXEC"),
+    (0x4c, "_EXIT2"), (0x4d, "_WAIT"), (0x4e, "_FILES"), (0x4f, "_NFILES"),
+    (0x50, "_SETPDB"), (0x51, "_GETPDB"), (0x52, "_SETENV"), (0x53, "_GETENV"),
+    (0x54, "_VERIFYG"), (0x55, "_COMMON"), (0x56, "_RENAME"), (0x57, "_FILEDATE"),
+    (0x58, "_MALLOC2"), (0x5a, "_MAKETMP"), (0x5b, "_NEWFILE"), (0x5c, "_LOCK"),
+    (0x5f, "_ASSIGN"), (0x80, "_SETPDB"), (0x81, "_GETPDB"), (0x82, "_SETENV"),
+    (0x83, "_GETENV"), (0x84, "_VERIFYG"), (0x85, "_COMMON"), (0x86, "_RENAME"),
+    (0x87, "_FILEDATE"), (0x88, "_MALLOC2"), (0x8a, "_MAKETMP"), (0x8b, "_NEWFILE"),
+    (0x8c, "_LOCK"), (0x8f, "_ASSIGN"), (0xf0, "_EXITVC"), (0xf1, "_CTRLVC"),
+    (0xf2, "_ERRJVC"), (0xf3, "_DISKRED"), (0xf4, "_DISKWRT"), (0xf5, "_INDOSFLG"),
+    (0xf6, "_SUPER_JSR"), (0xf7, "_BUS_ERR"), (0xf8, "_OPEN_PR"), (0xf9, "_KILL_PR"),
+    (0xfa, "_GET_PR"), (0xfb, "_SUSPEND_PR"), (0xfc, "_SLEEP_PR"), (0xfd, "_SEND_PR"),
+    (0xfe, "_TIME_PR"), (0xff, "_CHANGE_PR"),
+];
+
+fn lookup(table: &[(Byte, &'static str)], no: Byte) -> Option<&'static str> {
+    table.iter().find(|&&(n, _)| n == no).map(|&(_, name)| name)
+}
+
+// Name of the IOCS or DOS call made by the instruction with opcode `op`: trap #15 with the
+// IOCS call number in D0.b, when `d0` is known, or a DOS call word.
+pub fn call_name(op: Word, d0: Option<Long>) -> Option<&'static str> {
+    match op {
+        TRAP_IOCS => lookup(&IOCS_CALLS, d0? as Byte),
+        _ if (op & 0xff00) == DOS_CALL => lookup(&DOS_CALLS, op as Byte),
+        _ => None,
+    }
+}
+
+// Prints like StdoutTrace, with the calls into IOCS and Human68k named.
+pub struct CallTrace;
+
+impl TraceSink for CallTrace {
+    fn trace(&mut self, event: &TraceEvent) {
+        match event.words.first().and_then(|&op| call_name(op, Some(event.regs.d[0]))) {
+            Some(name) => StdoutTrace.trace(&TraceEvent { comment: Some(format!("; {}", name)), ..event.clone() }),
+            None => StdoutTrace.trace(event),
+        }
+    }
+}
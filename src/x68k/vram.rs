@@ -1,12 +1,17 @@
+use serde::{Serialize, Deserialize};
+
 use super::super::types::{Byte, Adr};
 
 const GRAPHIC_SIZE: usize = 0x200000;
 const TEXT_SIZE: usize    =  0x80000;
 
+#[derive(Serialize, Deserialize)]
 pub struct Vram {
     // 0xc00000~0xdfffff
+    #[serde(with = "boxed_bytes")]
     graphic: Box<[Byte; GRAPHIC_SIZE]>,
     // 0xe00000~0xe7ffff
+    #[serde(with = "boxed_bytes")]
     text: Box<[Byte; TEXT_SIZE]>,
 }
 
@@ -34,3 +39,22 @@ impl Vram {
         self.text[adr as usize] = value;
     }
 }
+
+// serde only handles arrays up to 32 elements: go through a byte sequence.
+mod boxed_bytes {
+    use std::convert::TryInto;
+
+    use serde::{Serialize, Deserialize, Serializer, Deserializer};
+    use serde::de::Error;
+
+    use super::super::super::types::Byte;
+
+    pub fn serialize<S: Serializer, const N: usize>(array: &[Byte; N], serializer: S) -> Result<S::Ok, S::Error> {
+        array[..].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<Box<[Byte; N]>, D::Error> {
+        let bytes = Vec::<Byte>::deserialize(deserializer)?;
+        bytes.into_boxed_slice().try_into().map_err(|_| D::Error::custom("VRAM size mismatch"))
+    }
+}
@@ -1,25 +1,120 @@
+use serde::{Serialize, Deserialize};
+
 use super::bus::Bus;
+use super::devices::Key;
+use super::serial::SerialLine;
+use super::state::{self, StateError};
+use super::syscalls::CallTrace;
 use super::vram::Vram;
-use super::super::cpu::Cpu;
-use super::super::types::Byte;
+use super::super::cpu::{Cpu, CpuError, StepInfo};
+use super::super::cpu::disasm::{self, Instruction};
+use super::super::types::{Adr, Byte};
+
+pub const STOCK_CLOCK: u32 = 10_000_000;  // Hz
 
+#[derive(Serialize, Deserialize)]
 pub struct X68k {
     cpu: Cpu<Bus>,
+    clock: u32,  // CPU clock in Hz: 10MHz stock, 16/24MHz on accelerated models.
+    overrun: usize,  // Cycles executed beyond the previous budget.
 }
 
 impl X68k {
-    pub fn new(ipl: Vec<Byte>) -> Self {
+    pub fn new(ipl: Vec<Byte>, clock: u32) -> Self {
         let vram = Vram::new();
-        let bus = Bus::new(ipl, vram);
+        let bus = Bus::new(ipl, vram, clock);
         let mut cpu = Cpu::new(bus);
+        // Memory is only written by the CPU, which drops the cached instructions it overwrites.
+        cpu.set_decode_cache(true);
         cpu.reset();
 
         Self {
             cpu,
+            clock,
+            overrun: 0,
         }
     }
 
-    pub fn update(&mut self, cycles: usize) {
-        self.cpu.run_cycles(cycles);
+    // Trace to stdout, naming the IOCS and DOS calls.
+    pub fn set_trace(&mut self, enable: bool) {
+        self.cpu.set_trace_sink(if enable { Some(Box::new(CallTrace)) } else { None });
+    }
+
+    // Connect the RS-232C port to a line on the host, see serial::open_line. Not saved with the state.
+    pub fn set_serial_line(&mut self, line: Option<Box<dyn SerialLine>>) {
+        self.cpu.bus_mut().set_serial_line(line);
+    }
+
+    // Extra clocks on each access to VRAM and to the I/O area, on top of the CPU's own bus cycles.
+    pub fn set_wait_states(&mut self, vram: usize, io: usize) {
+        self.cpu.bus_mut().set_wait_states(vram, io);
+    }
+
+    #[cfg(feature = "jit")]
+    pub fn set_jit(&mut self, enable: bool) -> bool {
+        self.cpu.set_jit(enable)
+    }
+
+    // Snapshot the whole machine, except the IPL ROM, into a versioned binary blob.
+    pub fn save_state(&self) -> Vec<u8> {
+        state::encode(self)
+    }
+
+    // Restore a snapshot taken by save_state. On error the machine is left untouched.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut saved: X68k = state::decode(data)?;
+        saved.cpu.bus_mut().take_ipl(self.cpu.bus_mut());
+        saved.cpu.bus_mut().take_serial_line(self.cpu.bus_mut());
+        self.cpu.restore(saved.cpu);
+        self.clock = saved.clock;
+        self.overrun = saved.overrun;
+        Ok(())
+    }
+
+    // Execute a single instruction, e.g. for a debugger.
+    pub fn step(&mut self) -> Result<StepInfo, CpuError> {
+        self.cpu.step()
+    }
+
+    // Disassemble memory without touching devices; unreadable words show as ????.
+    pub fn disassemble(&self, adr: Adr) -> Instruction {
+        disasm::decode(self.cpu.bus(), adr)
+    }
+
+    // A key pressed or released on the keyboard, delivered to the machine at the keyboard's
+    // serial speed.
+    pub fn key_event(&mut self, key: Key, pressed: bool) {
+        self.cpu.bus_mut().key_event(key, pressed);
+    }
+
+    // Lit keyboard LEDs, a bit each from bit 0 up: かな, ローマ字, コード入力, CAPS, INS,
+    // ひらがな and 全角.
+    pub fn keyboard_leds(&self) -> Byte {
+        self.cpu.bus().keyboard_leds()
+    }
+
+    // The mouse moved by `dx`, `dy` (right and down positive) with the buttons in this state.
+    // Movement adds up until the machine asks the mouse for the next packet.
+    pub fn mouse_event(&mut self, dx: i32, dy: i32, left: bool, right: bool) {
+        self.cpu.bus_mut().mouse_event(dx, dy, left, right);
+    }
+
+    // Returns the clock cycles actually consumed, which can overrun `cycles` slightly.
+    // Device timers advance by the same amount, even while the CPU is stopped.
+    pub fn update(&mut self, cycles: usize) -> Result<usize, CpuError> {
+        self.cpu.run_cycles(cycles)
+    }
+
+    // Run for `micros` microseconds of emulated time, converted to cycles at the
+    // configured clock so that faster CPUs get a larger budget for the same time.
+    pub fn run_micros(&mut self, micros: u32) -> Result<usize, CpuError> {
+        let budget = (self.clock as u64 * micros as u64 / 1_000_000) as usize;
+        if budget <= self.overrun {
+            self.overrun -= budget;
+            return Ok(0);
+        }
+        let executed = self.update(budget - self.overrun)?;
+        self.overrun = (self.overrun + executed) - budget;
+        Ok(executed)
     }
 }